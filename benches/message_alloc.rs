@@ -0,0 +1,92 @@
+//! Allocations per message when sending with owned or borrowed node ids.
+//!
+//! Run using `cargo bench --bench message_alloc`.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use gossip_glomers::{
+    derive_response,
+    message::{Body, Message},
+};
+
+/// System allocator counting allocations.
+struct CountingAlloc;
+
+/// Number of allocations till now.
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+derive_response!(
+    enum GossipResponse {
+        Consensus { seen: Vec<usize> },
+    }
+);
+
+/// Number of peers gossiped to per tick.
+const PEERS: usize = 24;
+/// Number of ticks measured.
+const TICKS: usize = 1000;
+
+/// Average allocations per message sent by `send_tick`.
+fn allocations_per_message(mut send_tick: impl FnMut(&mut Vec<u8>)) -> f64 {
+    let mut writer = Vec::with_capacity(1 << 20);
+    let start = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..TICKS {
+        writer.clear();
+        send_tick(&mut writer);
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - start) as f64 / (TICKS * PEERS) as f64
+}
+
+fn main() {
+    let node = "n0".to_string();
+    let peers = (1..=PEERS).map(|n| format!("n{n}")).collect::<Vec<_>>();
+    let seen = vec![1, 2, 3];
+
+    let owned = allocations_per_message(|writer| {
+        for peer in &peers {
+            Message {
+                src: node.to_string(),
+                dst: peer.to_string(),
+                body: Body {
+                    id: None,
+                    reply_id: None,
+                    payload: GossipResponse::Consensus { seen: seen.clone() },
+                },
+            }
+            .send(writer);
+        }
+    });
+    let borrowed = allocations_per_message(|writer| {
+        for peer in &peers {
+            Message {
+                src: node.as_str(),
+                dst: peer.as_str(),
+                body: Body {
+                    id: None,
+                    reply_id: None,
+                    payload: GossipResponse::Consensus { seen: seen.clone() },
+                },
+            }
+            .send(writer);
+        }
+    });
+    println!("allocations per message with owned ids: {owned:.2}");
+    println!("allocations per message with borrowed ids: {borrowed:.2}");
+    assert!(borrowed < owned);
+}
@@ -1,71 +1,22 @@
 //! Implements echo node using [main].
-use std::io::{stdin, stdout};
-
 use gossip_glomers::{
-    derive_request, derive_response,
-    init::init,
-    message::{Body, Message},
+    config::Config, echo::Echo, init::init_transport, log::exit_on_warnings,
+    transport::StdioTransport,
 };
 
-derive_request!(
-    /// Request payload for echo node.
-    pub enum EchoRequest {
-        /// Echo request.
-        ///
-        /// This message requests that a node echo a message.
-        /// ```json
-        /// { "echo": "Please echo 35"}
-        /// ```
-        Echo {
-            /// holds the message.
-            echo: String,
-        },
-    }
-);
-
-derive_response!(
-    /// Response payload for echo node.
-    pub enum EchoResponse {
-        /// Echo ok response.
-        ///
-        /// This message acknowledges Echo request.
-        /// It includes the echo message from request.
-        /// ```json
-        /// {
-        ///     "type": "echo_ok",
-        ///     "echo": "Please echo 35"
-        /// }
-        /// ```
-        EchoOk {
-            /// holds the message.
-            echo: String,
-        },
-    }
-);
-
 /// Echo node entry point.
 ///
 /// The echo server
-/// * Handle Initialization Protocol using [init].
-/// * Read standard input for [Request][EchoRequest::Echo]
-///   and reply with [Response][EchoResponse::EchoOk].
+/// * Handle Initialization Protocol using [init_transport] over [StdioTransport].
+/// * Read standard input for echo requests and reply to them, see [Echo::serve].
+/// * If `ECHO_EXTRA=1` then extra fields of request body are echoed back as well.
+/// * If `ECHO_DELAY_MS=<ms>` then node sleeps that long before every reply,
+///   to act as a slow responder for latency and timeout testing.
 fn main() {
-    let stdin = stdin().lock();
-    let mut deseralizer = serde_json::Deserializer::from_reader(stdin);
-    let mut stdout = stdout().lock();
-    let _init = init(&mut stdout, &mut deseralizer);
-    for (id, request) in deseralizer.into_iter::<Message<_>>().flatten().enumerate() {
-        match request.body.payload {
-            EchoRequest::Echo { echo } => Message {
-                src: request.dst,
-                dst: request.src,
-                body: Body {
-                    id: Some(id),
-                    reply_id: request.body.id,
-                    payload: EchoResponse::EchoOk { echo },
-                },
-            },
-        }
-        .send(&mut stdout);
+    Config::load();
+    let mut transport = StdioTransport::stdio();
+    if init_transport(&mut transport).is_some() {
+        Echo::new().serve(&mut transport);
     }
+    exit_on_warnings();
 }
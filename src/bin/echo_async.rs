@@ -0,0 +1,18 @@
+//! Implements echo node on async runtime using [main].
+use gossip_glomers::{echo::Echo, log::exit_on_warnings, runtime::Runtime};
+use tokio::io::{stdin, stdout, BufReader};
+
+/// Echo node entry point.
+///
+/// Async port of `echo` node, reference for [Runtime].
+/// * Handle Initialization Protocol using [Runtime::init].
+/// * Read standard input for echo requests and reply to them, see [Echo::serve_async].
+/// * `ECHO_EXTRA` and `ECHO_DELAY_MS` apply as for `echo` node.
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let mut runtime = Runtime::new(BufReader::new(stdin()), stdout());
+    if runtime.init().await.is_some() {
+        Echo::new().serve_async(&mut runtime).await;
+    }
+    exit_on_warnings();
+}
@@ -1,89 +1,55 @@
 //! Implements unique id generation node using [main].
-use std::io::{stdin, stdout};
+use std::{io::stdin, sync::mpsc};
 
 use gossip_glomers::{
-    derive_request, derive_response,
-    init::{init, InitRequest},
-    message::{Body, Message},
+    config::Config,
+    event::{channel, input_recv, ticker},
+    init::init_transport,
+    log::exit_on_warnings,
+    transport::StdioTransport,
+    unique_ids::{BlockAllocator, Generator},
 };
 
-derive_request!(
-    /// Request payload for unique id generation node.
-    pub enum GenRequest {
-        /// Generate Id request.
-        ///
-        /// This message requests node to generate unique id.
-        /// ```json
-        /// { "type": "generate" }
-        /// ```
-        Generate,
-    }
-);
-
-derive_response!(
-    /// Response payload for unique id generation node.
-    pub enum GenRespone {
-        /// Generate ok response.
-        ///
-        /// This message acknowledges Generate request.
-        /// It includes the uniquely generated Id.
-        /// ```json
-        /// {
-        ///     "type": "generate_ok",
-        ///     "id": 123
-        /// }
-        /// ```
-        GenerateOk {
-            /// Newly generated id.
-            id: usize,
-        },
-    }
-);
-
 /// Unique Id generation node entry point.
 ///
 /// The unique id server.
-/// * Handle Initialization Protocol using [init].
-/// * Read standard input for [Request][GenRequest::Generate]
-///   and reply with [Response][GenRespone::GenerateOk].
+/// * Handle Initialization Protocol using [init_transport] over [StdioTransport].
+/// * Read standard input for generate requests and reply with generated id, see [Generator].
+///
+/// # Key Value Logic
 ///
-/// # Logic
+/// When `ID_MODE=kv` the ids are handed out by [BlockAllocator] instead,
+/// hence ids are globally sortable by reservation order of blocks.
+/// * Spawn [ticker] thread, which retries failed reservations.
+/// * Spawn [input_recv] thread, bounded by `EVENT_QUEUE`, see [channel].
+/// * Run [BlockAllocator::handle_events].
 ///
-/// `unique_id = node_id + node_count * counter`
+/// # Snowflake Logic
 ///
-/// Hence
-/// * `unique_id % node_count = node_id`
-///   which means the ids generated by two node cannot collide
-/// * `unique_id - node_id = node_count * counter`
-///   as counter is updated after each message sent, id generated by a node don't collide.
+/// When `ID_FORMAT=snowflake` the ids are [Snowflake](gossip_glomers::unique_ids::Snowflake) ids
+/// rendered as string, hence ids are roughly sortable by generation time across nodes.
+/// Key value mode takes precedence over the format.
 fn main() {
-    let stdin = stdin().lock();
-    let mut deseralizer = serde_json::Deserializer::from_reader(stdin);
-    let mut stdout = stdout().lock();
-    let (node_id, node_count) = match init(&mut stdout, &mut deseralizer) {
-        InitRequest::Init {
-            node_id,
-            mut node_ids,
-        } => {
-            node_ids.sort();
-            let node_pos = node_ids.iter().position(|n| n.eq(&node_id)).unwrap();
-            (node_pos, node_ids.len())
-        }
+    let config = Config::load();
+    let mut transport = StdioTransport::stdio();
+    let Some(init_request) = init_transport(&mut transport) else {
+        return exit_on_warnings();
     };
-    for (counter, request) in deseralizer.into_iter::<Message<_>>().flatten().enumerate() {
-        match request.body.payload {
-            GenRequest::Generate => Message {
-                src: request.dst,
-                dst: request.src,
-                body: Body {
-                    id: Some(counter),
-                    reply_id: request.body.id,
-                    payload: GenRespone::GenerateOk {
-                        id: node_id + counter * node_count,
-                    },
-                },
-            },
-        }
-        .send(&mut stdout);
+    let mode = config.id_mode.as_deref();
+    if mode == Some("kv") {
+        let mut stdout = transport.into_inner();
+        let (event_tx, event_rx) = channel();
+        let (tick_tx, tick_rx) = mpsc::channel();
+        std::thread::spawn({
+            let event_tx = event_tx.clone();
+            move || ticker(event_tx, tick_rx, 1000)
+        });
+        std::thread::spawn(move || input_recv(stdin().lock(), event_tx));
+        BlockAllocator::new(init_request).handle_events(event_rx, tick_tx, &mut stdout);
+    } else {
+        Generator::new(init_request)
+            .snowflake(config.id_format.as_deref() == Some("snowflake"))
+            .serve(&mut transport);
     }
+    exit_on_warnings();
 }
@@ -0,0 +1,967 @@
+//! Broadcast Node Implementation.
+//!
+//! Describe [BroadcastRequest] and [BroadcastRespone] payloads of broadcast node.
+//! Provides [EventHandler] which runs the node on events,
+//! allowing node to be driven in-process, see [inputs](crate::event::inputs).
+
+use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
+use std::{
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::Config,
+    derive_request, derive_response,
+    event::{AdaptiveTick, Event},
+    init::InitRequest,
+    log,
+    log::Level,
+    message::{first_id, ErrorCode, Message, Writer},
+    random::Rng,
+};
+
+derive_request!(
+    /// Request payload for broadcast node.
+    pub enum BroadcastRequest {
+        /// Broadcast request.
+        ///
+        /// This message requests that a value be broadcast out to all nodes in the cluster.
+        /// ```json
+        /// {
+        ///     "type": "broadcast",
+        ///     "message": 1000
+        /// }
+        /// ```
+        /// Multiple values can be broadcast at once.
+        /// ```json
+        /// {
+        ///     "type": "broadcast",
+        ///     "messages": [1000, 1001]
+        /// }
+        /// ```
+        Broadcast {
+            /// message to broadcast.
+            #[serde(default)]
+            message: Option<usize>,
+            /// messages to broadcast.
+            #[serde(default)]
+            messages: Vec<usize>,
+        },
+        /// Read request.
+        ///
+        /// This message requests that a node return all values that it has seen.
+        /// ```json
+        /// { "type": "read" }
+        /// ```
+        Read,
+        /// Topology request.
+        ///
+        /// This message informs the node of who its neighboring nodes are.
+        /// ```json
+        /// {
+        ///     "type": "topology",
+        ///     "topology": {
+        ///         "n1": ["n2", "n3"],
+        ///         "n2": ["n1"],
+        ///         "n3": ["n1"]
+        ///     }
+        /// }
+        /// ```
+        Topology {
+            /// Map from node to all the its neighboring nodes.
+            topology: HashMap<String, Vec<String>>,
+        },
+        /// Consensus request.
+        ///
+        /// This message informs new values seen from other nodes.
+        /// It includes values newly seen by the other node.
+        /// It also acknowledges last response by current to other node.
+        /// ```json
+        /// {
+        ///     "type": "consensus",
+        ///     "seen": ["2", "3"],
+        ///     "seen_ack": ["2", "3"]
+        /// }
+        /// ```
+        Consensus {
+            /// Values seen newly by other node.
+            seen: HashSet<usize>,
+            /// Values received in last request of current node.
+            seen_ack: Vec<usize>,
+        },
+        /// Pull request.
+        ///
+        /// This message asks for values the other node has not seen.
+        /// It includes all values seen by the other node.
+        /// ```json
+        /// {
+        ///     "type": "pull",
+        ///     "messages": [1, 8]
+        /// }
+        /// ```
+        Pull {
+            /// Values seen by other node.
+            messages: HashSet<usize>,
+        },
+        /// Reconcile request.
+        ///
+        /// This message informs all values seen by the other node.
+        /// ```json
+        /// {
+        ///     "type": "reconcile",
+        ///     "messages": [1, 8]
+        /// }
+        /// ```
+        Reconcile {
+            /// Values seen by other node.
+            messages: HashSet<usize>,
+        },
+        /// Checkpoint value request.
+        ///
+        /// This message acknowledge [BroadcastRespone::ReadCheckpoint].
+        /// ```json
+        /// {
+        ///     "type": "read_ok",
+        ///     "value": [1, 8, 72, 25]
+        /// }
+        /// ```
+        #[serde(rename = "read_ok")]
+        ReadCheckpointOk {
+            /// messages seen before checkpoint.
+            value: HashSet<usize>,
+        },
+        /// Checkpoint written request.
+        ///
+        /// This message acknowledge [BroadcastRespone::WriteCheckpoint].
+        /// ```json
+        /// { "type": "write_ok" }
+        /// ```
+        #[serde(rename = "write_ok")]
+        CheckpointWritten,
+        /// Error request.
+        ///
+        /// This message acknowledge error in operation.
+        /// ```json
+        /// {
+        ///     "type": "error"
+        ///     "code": 20,
+        ///     "text": "Some messsage"
+        /// }
+        /// ```
+        Error {
+            /// error code.
+            code: ErrorCode,
+            /// error message.
+            text: String,
+        },
+    }
+);
+
+derive_response!(
+    /// Response payload for broadcast node.
+    pub enum BroadcastRespone {
+        /// Broadcast ok response.
+        ///
+        /// This message acknowledges Broadcast request.
+        /// ```json
+        /// { "type": "broadcast_ok" }
+        /// ```
+        BroadcastOk,
+        /// Read ok response.
+        ///
+        /// This message acknowledges Read request.
+        /// It includes a list of values it has seen.
+        /// ```json
+        /// {
+        ///     "type": "read_ok",
+        ///     "messages": [1, 8, 72, 25]
+        /// }
+        /// ```
+        ReadOk {
+            /// List of all message seen until now.
+            messages: Vec<usize>,
+        },
+        /// Topology ok response.
+        ///
+        /// This message acknowledges Topology request.
+        /// ```json
+        /// { "type": "topology_ok" }
+        /// ```
+        TopologyOk,
+        /// Consensus response.
+        ///
+        /// This message informs new values seen by current nodes.
+        /// It also acknowledges last request by other to current node.
+        /// ```json
+        /// {
+        ///     "type": "consensus",
+        ///     "seen": ["2", "3"],
+        ///     "seen_ack": ["2", "3"]
+        /// }
+        /// ```
+        Consensus {
+            /// Values seen newly by current node.
+            seen: Vec<usize>,
+            /// Values received in last response of other node.
+            seen_ack: Vec<usize>,
+        },
+        /// Pull response.
+        ///
+        /// This message asks other node for values not seen by current node,
+        /// which are sent back as [Consensus](BroadcastRespone::Consensus).
+        /// ```json
+        /// {
+        ///     "type": "pull",
+        ///     "messages": [1, 8]
+        /// }
+        /// ```
+        Pull {
+            /// Values seen by current node.
+            messages: Vec<usize>,
+        },
+        /// Reconcile response.
+        ///
+        /// This message informs all values seen by current node,
+        /// repairing gossip state of other node even if acknowledgements were lost.
+        /// ```json
+        /// {
+        ///     "type": "reconcile",
+        ///     "messages": [1, 8]
+        /// }
+        /// ```
+        Reconcile {
+            /// Values seen by current node.
+            messages: Vec<usize>,
+        },
+        /// Read checkpoint response.
+        ///
+        /// This message to read checkpoint of messages from key store.
+        /// ```json
+        /// {
+        ///     "type": "read",
+        ///     "key": "broadcast/n1"
+        /// }
+        /// ```
+        #[serde(rename = "read")]
+        ReadCheckpoint {
+            /// Key of checkpoint in store.
+            key: String,
+        },
+        /// Write checkpoint response.
+        ///
+        /// This message to write checkpoint of messages to key store.
+        /// ```json
+        /// {
+        ///     "type": "write",
+        ///     "key": "broadcast/n1",
+        ///     "value": [1, 8, 72, 25]
+        /// }
+        /// ```
+        #[serde(rename = "write")]
+        WriteCheckpoint {
+            /// Key of checkpoint in store.
+            key: String,
+            /// Messages seen until now.
+            value: HashSet<usize>,
+        },
+    }
+);
+
+/// Node id for key store.
+const KV_NODE: &str = "lin-kv";
+
+/// Statistics of gossip sent by node.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GossipStats {
+    /// Consensus messages sent.
+    pub consensus: usize,
+    /// Values sent in consensus messages.
+    pub values: usize,
+    /// Values sent to peer which peer had already sent to node, hence peer had them.
+    ///
+    /// Every send is counted until peer acknowledges the value.
+    pub redundant: usize,
+}
+
+/// Memory of messages seen by peer.
+#[derive(Default)]
+struct PeerState {
+    /// Messages known to peer.
+    known: HashSet<usize>,
+    /// Messages in last consensus of peer, to acknowledge.
+    last_sent: HashSet<usize>,
+    /// Messages sent by peer which it has not acknowledged yet.
+    held: HashSet<usize>,
+}
+
+/// Gossip state of broadcast node.
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::broadcast::{BroadcastRespone, BroadcastState};
+/// let mut state = BroadcastState::new("n1", ["n1".into(), "n2".into()]);
+/// assert!(state.on_broadcast([1]));
+/// assert!(matches!(
+///     state.gossip_messages("n2"),
+///     Some(BroadcastRespone::Consensus { seen, .. }) if seen == [1]
+/// ));
+/// assert!(!state.on_consensus("n2", Default::default(), vec![1]));
+/// assert!(state.gossip_messages("n2").is_none());
+/// ```
+pub struct BroadcastState {
+    /// Message seen till now.
+    messages: HashSet<usize>,
+    /// Memory of other nodes seen message, by node id.
+    known: HashMap<String, PeerState>,
+    /// Statistics of gossip sent.
+    stats: GossipStats,
+    /// Whether messages or acknowledgements may be pending since gossip last had nothing to send.
+    dirty: bool,
+}
+
+impl BroadcastState {
+    /// Create state of node with all nodes of cluster.
+    pub fn new(node: &str, node_ids: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            messages: HashSet::default(),
+            known: node_ids
+                .into_iter()
+                .filter(|n| n != node)
+                .map(|nid| (nid, PeerState::default()))
+                .collect(),
+            stats: GossipStats::default(),
+            dirty: false,
+        }
+    }
+    /// Message seen till now.
+    pub fn messages(&self) -> &HashSet<usize> {
+        &self.messages
+    }
+    /// Statistics of gossip sent till now.
+    pub fn stats(&self) -> &GossipStats {
+        &self.stats
+    }
+    /// Whether gossip may have anything to send.
+    ///
+    /// State is dirty on new messages or seen messages to acknowledge,
+    /// and stays dirty until gossip to every peer has nothing to send.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+    /// Mark gossip as pending, for example when peers change.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+    /// Mark gossip as done, once gossip to every peer had nothing to send.
+    pub fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+    /// Remember messages.
+    ///
+    /// # Returns
+    /// Whether any message was new.
+    pub fn on_broadcast(&mut self, messages: impl IntoIterator<Item = usize>) -> bool {
+        let len = self.messages.len();
+        self.messages.extend(messages);
+        let new = self.messages.len() > len;
+        self.dirty |= new;
+        new
+    }
+    /// Remember consensus of peer.
+    ///
+    /// * Acknowledged messages are known to peer.
+    /// * Seen messages are remembered and acknowledged in next gossip to peer.
+    /// * Seen messages are held by peer, hence sending them is redundant until acknowledged.
+    ///
+    /// # Returns
+    /// Whether any message was new.
+    ///
+    /// # Panics
+    /// Panics if peer is not part of cluster.
+    pub fn on_consensus(&mut self, peer: &str, seen: HashSet<usize>, seen_ack: Vec<usize>) -> bool {
+        let PeerState {
+            known,
+            last_sent,
+            held,
+        } = self.known.get_mut(peer).expect("node are pre-determined");
+        known.extend(seen_ack);
+        held.extend(seen.iter().copied());
+        held.retain(|m| !known.contains(m));
+        let new = !self.messages.is_superset(&seen);
+        self.messages.extend(seen.iter().copied());
+        self.dirty |= !seen.is_empty();
+        *last_sent = seen;
+        new
+    }
+    /// Consensus for peer.
+    ///
+    /// * seen: messages not known to peer.
+    /// * seen_ack: messages seen in last consensus of peer, which are acknowledged only once.
+    ///
+    /// # Returns
+    /// Consensus if there is anything to send.
+    ///
+    /// # Panics
+    /// Panics if peer is not part of cluster.
+    pub fn gossip_messages(&mut self, peer: &str) -> Option<BroadcastRespone> {
+        let PeerState {
+            known,
+            last_sent,
+            held,
+        } = self.known.get_mut(peer).expect("node are pre-determined");
+        let seen = self.messages.difference(known).copied().collect::<Vec<_>>();
+        self.stats.redundant += seen.iter().filter(|m| held.contains(m)).count();
+        let seen_ack = last_sent.drain().collect::<Vec<_>>();
+        if seen.is_empty() & seen_ack.is_empty() {
+            return None;
+        }
+        self.stats.consensus += 1;
+        self.stats.values += seen.len();
+        log!(
+            Level::Debug,
+            "gossip {} seen and {} acks to {peer}",
+            seen.len(),
+            seen_ack.len()
+        );
+        Some(BroadcastRespone::Consensus { seen, seen_ack })
+    }
+    /// Pull of messages not seen by current node.
+    pub fn pull_messages(&self) -> BroadcastRespone {
+        BroadcastRespone::Pull {
+            messages: self.messages.iter().copied().collect(),
+        }
+    }
+    /// Full state of current node, see [Self::on_reconcile].
+    pub fn reconcile_messages(&self) -> BroadcastRespone {
+        BroadcastRespone::Reconcile {
+            messages: self.messages.iter().copied().collect(),
+        }
+    }
+    /// Reconcile with full state of peer.
+    ///
+    /// * Messages are remembered and are known to peer.
+    /// * State is marked dirty to gossip messages peer lacks.
+    ///
+    /// # Returns
+    /// Whether any message was new.
+    ///
+    /// # Panics
+    /// Panics if peer is not part of cluster.
+    pub fn on_reconcile(&mut self, peer: &str, messages: HashSet<usize>) -> bool {
+        let PeerState { known, .. } = self.known.get_mut(peer).expect("node are pre-determined");
+        known.extend(messages.iter().copied());
+        self.dirty = true;
+        self.on_broadcast(messages)
+    }
+    /// Answer pull of peer.
+    ///
+    /// Pulled messages are known to peer.
+    ///
+    /// # Returns
+    /// Consensus with messages not known to peer, if any.
+    ///
+    /// # Panics
+    /// Panics if peer is not part of cluster.
+    pub fn on_pull(&mut self, peer: &str, messages: HashSet<usize>) -> Option<BroadcastRespone> {
+        let PeerState { known, .. } = self.known.get_mut(peer).expect("node are pre-determined");
+        let seen = self
+            .messages
+            .difference(&messages)
+            .copied()
+            .collect::<Vec<_>>();
+        known.extend(messages);
+        if seen.is_empty() {
+            return None;
+        }
+        log!(
+            Level::Debug,
+            "answer pull of {peer} with {} seen",
+            seen.len()
+        );
+        self.stats.consensus += 1;
+        self.stats.values += seen.len();
+        Some(BroadcastRespone::Consensus {
+            seen,
+            seen_ack: Vec::new(),
+        })
+    }
+}
+
+/// How broadcast node gossips on every tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GossipMode {
+    /// Send messages unknown to peers, see [BroadcastState::gossip_messages].
+    Push,
+    /// Ask a random peer for messages not seen, see [BroadcastState::pull_messages].
+    Pull,
+    /// Both push and pull.
+    Both,
+}
+
+impl GossipMode {
+    /// Gossip mode from [Config::gossip_mode], one of `push`, `pull` or `both`, defaults to push.
+    ///
+    /// # Panics
+    ///
+    /// Panics if configured gossip mode is not a gossip mode.
+    pub fn from_config() -> Self {
+        match Config::load().gossip_mode.as_deref() {
+            None | Some("push") => GossipMode::Push,
+            Some("pull") => GossipMode::Pull,
+            Some("both") => GossipMode::Both,
+            Some(mode) => panic!("invalid GOSSIP_MODE {mode:?}"),
+        }
+    }
+    /// Whether unknown messages are pushed to peers.
+    pub fn push(&self) -> bool {
+        matches!(self, GossipMode::Push | GossipMode::Both)
+    }
+    /// Whether missing messages are pulled from peers.
+    pub fn pull(&self) -> bool {
+        matches!(self, GossipMode::Pull | GossipMode::Both)
+    }
+}
+
+/// Progress of reading checkpoint back when persisting.
+///
+/// Checkpoint is written only once it was read,
+/// else the write would overwrite messages seen before restart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Restore {
+    /// Read is to be sent, on start or on next tick after failed read.
+    Unread,
+    /// Read is sent and not replied yet.
+    Reading,
+    /// Read is replied, or not persisting.
+    Done,
+}
+
+/// Event handler for broadcast node.
+pub struct EventHandler {
+    /// Message id counter, starting from [first_id].
+    ///
+    /// Only requests to key store and replies take an id,
+    /// gossip is [notified](Message::notify) as no reply is expected.
+    id: usize,
+    /// Node id.
+    node: String,
+    /// Gossip state.
+    state: BroadcastState,
+    /// Peer of current node.
+    peers: HashSet<String>,
+    /// Force tick.
+    force: bool,
+    /// Checkpoint messages to key store.
+    persist: bool,
+    /// Reply to read with sorted messages.
+    sorted: bool,
+    /// Number of messages in last checkpoint.
+    checkpointed: usize,
+    /// Progress of reading checkpoint back.
+    restore: Restore,
+    /// Number of random peers to gossip with on every tick and generator choosing them.
+    fanout: Option<(usize, Rng)>,
+    /// Push messages to peers.
+    push: bool,
+    /// Generator choosing peer to pull from on every tick, if pulling.
+    pull: Option<Rng>,
+    /// Number of ticks between full state reconciliations and generator choosing node for them.
+    reconcile: Option<(usize, Rng)>,
+    /// Other nodes of cluster, in order of node id.
+    nodes: Vec<String>,
+    /// Number of ticks handled.
+    ticks: usize,
+    /// Creation time of node.
+    start: Instant,
+    /// Time since start when last new message was seen.
+    converged: Option<Duration>,
+    /// Tick interval adapted to gossip, if adaptive.
+    adaptive: Option<AdaptiveTick>,
+}
+
+impl EventHandler {
+    /// Create new event handler from initialization message.
+    pub fn new(init_request: InitRequest) -> Self {
+        let (node, node_ids) = match init_request {
+            InitRequest::Init { node_id, node_ids } => (node_id, node_ids),
+        };
+        let config = Config::load();
+        let force = config.force_tick.unwrap_or(true);
+        let persist = config.broadcast_persist.unwrap_or(false);
+        let sorted = config.broadcast_sorted.unwrap_or(false);
+        let fanout = config.gossip_fanout;
+        let reconcile = config.reconcile_ticks;
+        let mut nodes = node_ids
+            .iter()
+            .filter(|nid| **nid != node)
+            .cloned()
+            .collect::<Vec<_>>();
+        nodes.sort();
+        Self {
+            id: first_id(),
+            state: BroadcastState::new(&node, node_ids),
+            peers: HashSet::default(),
+            node,
+            force,
+            persist: false,
+            sorted,
+            checkpointed: 0,
+            restore: Restore::Done,
+            fanout: None,
+            push: true,
+            pull: None,
+            reconcile: None,
+            nodes,
+            ticks: 0,
+            start: Instant::now(),
+            converged: None,
+            adaptive: None,
+        }
+        .gossip(GossipMode::from_config())
+        .fanout(fanout)
+        .reconcile(reconcile)
+        .persist(persist)
+    }
+    /// Change how node gossips, see [GossipMode::from_config].
+    ///
+    /// Peer to pull from is chosen using generator seeded by `GOSSIP_SEED`.
+    pub fn gossip(mut self, mode: GossipMode) -> Self {
+        self.push = mode.push();
+        self.pull = match self.pull.take() {
+            Some(rng) if mode.pull() => Some(rng),
+            _ => mode.pull().then(|| Rng::from_env("GOSSIP_SEED")),
+        };
+        self
+    }
+    /// Send full state to a random node every `ticks` ticks, defaults to `RECONCILE_TICKS`.
+    ///
+    /// Node is chosen from whole cluster using generator seeded by `GOSSIP_SEED`,
+    /// see [BroadcastState::on_reconcile].
+    pub fn reconcile(mut self, ticks: Option<usize>) -> Self {
+        self.reconcile = ticks
+            .filter(|ticks| *ticks > 0)
+            .map(|ticks| (ticks, Rng::from_env("GOSSIP_SEED")));
+        self
+    }
+    /// Gossip to `fanout` random peers on every tick, defaults to `GOSSIP_FANOUT`.
+    ///
+    /// Peers are chosen using generator seeded by `GOSSIP_SEED`, all peers if none.
+    pub fn fanout(mut self, fanout: Option<usize>) -> Self {
+        self.fanout = fanout.map(|fanout| (fanout, Rng::from_env("GOSSIP_SEED")));
+        self
+    }
+    /// Seed generators choosing peers with `seed` instead of `GOSSIP_SEED`.
+    ///
+    /// Set after [fanout](Self::fanout), [gossip](Self::gossip) and [reconcile](Self::reconcile),
+    /// as they seed their generator from `GOSSIP_SEED`.
+    pub fn seed(mut self, seed: u64) -> Self {
+        if let Some((_, rng)) = &mut self.fanout {
+            *rng = Rng::new(seed);
+        }
+        if let Some(rng) = &mut self.pull {
+            *rng = Rng::new(seed);
+        }
+        if let Some((_, rng)) = &mut self.reconcile {
+            *rng = Rng::new(seed);
+        }
+        self
+    }
+    /// Checkpoint messages to key store, defaults to `BROADCAST_PERSIST`.
+    ///
+    /// Checkpoint is read back before any checkpoint is written.
+    pub fn persist(mut self, persist: bool) -> Self {
+        self.persist = persist;
+        self.restore = if persist {
+            Restore::Unread
+        } else {
+            Restore::Done
+        };
+        self
+    }
+    /// Start message ids from id, defaults to [first_id].
+    pub fn start_id(mut self, id: usize) -> Self {
+        self.id = id;
+        self
+    }
+    /// Adapt tick interval to gossip sent on every tick, see [AdaptiveTick].
+    ///
+    /// Values not sent to peer before are new, see [GossipStats] for redundant.
+    pub fn adaptive(mut self, tick: AdaptiveTick) -> Self {
+        self.adaptive = Some(tick);
+        self
+    }
+    /// Key of checkpoint in key store.
+    fn checkpoint_key(&self) -> String {
+        format!("broadcast/{}", self.node)
+    }
+    /// Send [read checkpoint](BroadcastRespone::ReadCheckpoint) if it is to be sent.
+    fn read_checkpoint<W: std::io::Write>(&mut self, writer: &mut Writer<W>) {
+        if self.restore != Restore::Unread {
+            return;
+        }
+        let response = Message::request(
+            self.node.as_str(),
+            KV_NODE,
+            self.id,
+            BroadcastRespone::ReadCheckpoint {
+                key: self.checkpoint_key(),
+            },
+        );
+        writer.send(response);
+        self.id += 1;
+        self.restore = Restore::Reading;
+    }
+    /// Handle input requests.
+    ///
+    /// Handle requests in following ways:
+    /// * [Broadcast](BroadcastRequest::Broadcast):
+    ///     * remember the message(s) and force tick.
+    ///     * are replied with broadcast ok.
+    /// * [Read](BroadcastRequest::Read):
+    ///     * send read ok with all messages, sorted if `BROADCAST_SORTED=1`.
+    /// * [Topology](BroadcastRequest::Topology):
+    ///     * update peers list and mark state dirty.
+    /// * [Consensus](BroadcastRequest::Consensus):
+    ///     * For any new message update seen and force tick.
+    ///     * Update the source node's known list.
+    ///     * Remember the message for seen_ack.
+    /// * [Pull](BroadcastRequest::Pull):
+    ///     * Update the source node's known list.
+    ///     * reply with [Consensus](BroadcastRespone::Consensus) of messages unknown to source.
+    /// * [Reconcile](BroadcastRequest::Reconcile):
+    ///     * For any new message update seen and force tick.
+    ///     * Update the source node's known list and mark state dirty.
+    /// * [Read checkpoint ok](BroadcastRequest::ReadCheckpointOk):
+    ///     * For any new message update seen and force tick.
+    ///     * Skip next checkpoint if nothing new was seen before.
+    ///     * Allow writing checkpoints.
+    /// * [Checkpoint written](BroadcastRequest::CheckpointWritten): nothing to do.
+    /// * [Error](BroadcastRequest::Error):
+    ///     * [KeyDoesNotExist](ErrorCode::KeyDoesNotExist):
+    ///         * no checkpoint yet, hence treat as empty and allow writing checkpoints.
+    ///     * other errors are logged:
+    ///         * re-send checkpoint read on next tick, if it was not replied yet.
+    ///         * re-send checkpoint on next tick otherwise.
+    ///
+    /// # Arguments
+    /// * payload: request to be handled requests.
+    /// * src: source node id.
+    /// * tick_tx: tick sender to allow force ticking, ignored once ticker has stopped.
+    ///
+    /// # Returns
+    /// Response if any for payload.
+    fn handle_input_payload(
+        &mut self,
+        payload: BroadcastRequest,
+        src: &str,
+        tick_tx: &mut Sender<()>,
+    ) -> Option<BroadcastRespone> {
+        match payload {
+            BroadcastRequest::Broadcast { message, messages } => {
+                if self.state.on_broadcast(message.into_iter().chain(messages)) & self.force {
+                    let _ = tick_tx.send(());
+                }
+                Some(BroadcastRespone::BroadcastOk)
+            }
+            BroadcastRequest::Read => {
+                let mut messages = self.state.messages().iter().copied().collect::<Vec<_>>();
+                if self.sorted {
+                    messages.sort_unstable();
+                }
+                Some(BroadcastRespone::ReadOk { messages })
+            }
+            BroadcastRequest::Topology { mut topology } => {
+                if let Some(peers) = topology.remove(&self.node) {
+                    self.peers = peers.into_iter().collect();
+                    self.state.mark_dirty();
+                }
+                Some(BroadcastRespone::TopologyOk)
+            }
+            BroadcastRequest::Consensus { seen, seen_ack } => {
+                if self.state.on_consensus(src, seen, seen_ack) & self.force {
+                    let _ = tick_tx.send(());
+                }
+                None
+            }
+            BroadcastRequest::Pull { messages } => self.state.on_pull(src, messages),
+            BroadcastRequest::Reconcile { messages } => {
+                if self.state.on_reconcile(src, messages) & self.force {
+                    let _ = tick_tx.send(());
+                }
+                None
+            }
+            BroadcastRequest::ReadCheckpointOk { value } => {
+                self.restore = Restore::Done;
+                if value.is_superset(self.state.messages()) {
+                    self.checkpointed = value.len();
+                }
+                if self.state.on_broadcast(value) & self.force {
+                    let _ = tick_tx.send(());
+                }
+                None
+            }
+            BroadcastRequest::CheckpointWritten => None,
+            BroadcastRequest::Error { code, text } => {
+                match code {
+                    ErrorCode::KeyDoesNotExist => self.restore = Restore::Done,
+                    code if self.restore == Restore::Done => {
+                        log!(Level::Warn, "checkpoint failed, retrying: {code:?} {text}");
+                        self.checkpointed = 0;
+                    }
+                    code => {
+                        log!(
+                            Level::Warn,
+                            "checkpoint read failed, retrying: {code:?} {text}"
+                        );
+                        self.restore = Restore::Unread;
+                    }
+                }
+                None
+            }
+        }
+    }
+    /// Handle events.
+    ///
+    /// Handle events in following ways:
+    /// * [close](Event::Close): write [GossipStats] and time last new message was seen
+    ///   to standard error, regardless of `LOG_LEVEL`, then close the loop.
+    /// * [tick](Event::Tick):
+    ///     * if pulling send [Pull](BroadcastRespone::Pull) to a random peer.
+    ///     * every [reconcile](Self::reconcile) ticks send
+    ///       [Reconcile](BroadcastRespone::Reconcile) to a random node.
+    ///     * if pushing send [Consensus](BroadcastRequest::Consensus) message to every peer,
+    ///       or to `GOSSIP_FANOUT` random peers if set.
+    ///     * skip pushing if state is not [dirty](BroadcastState::is_dirty),
+    ///       state is clean once every peer had nothing to receive.
+    ///     * send only difference from known of peer and message list.
+    ///     * send acknowledge for  peers last [Consensus](BroadcastRequest::Consensus).
+    ///     * adapt tick interval to values sent if adaptive, see [Self::adaptive].
+    ///     * send [checkpoint](BroadcastRespone::WriteCheckpoint) if persisting and there are new messages,
+    ///       once checkpoint was read, else re-send failed [read](BroadcastRespone::ReadCheckpoint).
+    /// * [input](Event::Input):
+    ///     * send payload to `Self::handle_input_payload`.
+    ///     * send any response via writer.
+    ///
+    /// # Arguments
+    /// * events: Events to handle, usually receiver channel.
+    /// * tick_tx: Tick sender to allow force ticking.
+    /// * writer: Output response via writer, serialization buffer is reused across messages.
+    ///
+    /// If persisting then [checkpoint](BroadcastRespone::ReadCheckpoint) is read before handling events.
+    pub fn handle_events<W: std::io::Write>(
+        &mut self,
+        events: impl IntoIterator<Item = Event<BroadcastRequest>>,
+        mut tick_tx: Sender<()>,
+        writer: &mut W,
+    ) {
+        let mut writer = Writer::new(writer);
+        self.read_checkpoint(&mut writer);
+        for event in events {
+            match event {
+                Event::Close => {
+                    let GossipStats {
+                        consensus,
+                        values,
+                        redundant,
+                    } = self.state.stats();
+                    eprintln!(
+                        "gossip stats: {consensus} consensus, {values} values, {redundant} redundant, \
+                         {} messages, converged after {:?}",
+                        self.state.messages().len(),
+                        self.converged.unwrap_or_default()
+                    );
+                    break;
+                }
+                Event::Tick(_) => {
+                    let before = self.state.stats().clone();
+                    self.ticks += 1;
+                    if let Some((every, rng)) = &mut self.reconcile {
+                        if self.ticks.is_multiple_of(*every) {
+                            if let Some(node) = rng.sample(&self.nodes, 1).first() {
+                                let response = Message::notify(
+                                    self.node.as_str(),
+                                    node.as_str(),
+                                    self.state.reconcile_messages(),
+                                );
+                                writer.send(response);
+                            }
+                        }
+                    }
+                    let mut peers = self.peers.iter().collect::<Vec<_>>();
+                    peers.sort();
+                    if let Some(rng) = &mut self.pull {
+                        if let Some(peer) = rng.sample(&peers, 1).first() {
+                            let response = Message::notify(
+                                self.node.as_str(),
+                                peer.as_str(),
+                                self.state.pull_messages(),
+                            );
+                            writer.send(response);
+                        }
+                    }
+                    if !self.push | !self.state.is_dirty() {
+                        peers.clear();
+                    }
+                    let every_peer = self.fanout.is_none() & !peers.is_empty();
+                    if let Some((fanout, rng)) = &mut self.fanout {
+                        peers = rng.sample(&peers, *fanout).into_iter().copied().collect();
+                    }
+                    let mut sent = false;
+                    for peer in peers {
+                        let Some(payload) = self.state.gossip_messages(peer) else {
+                            continue;
+                        };
+                        sent = true;
+                        let response = Message::notify(self.node.as_str(), peer.as_str(), payload);
+                        writer.send(response);
+                    }
+                    if every_peer & !sent {
+                        self.state.mark_clean();
+                    }
+                    if let Some(tick) = &self.adaptive {
+                        let stats = self.state.stats();
+                        let redundant = stats.redundant - before.redundant;
+                        let interval =
+                            tick.observe(stats.values - before.values - redundant, redundant);
+                        log!(Level::Debug, "tick interval adapted to {interval:?}");
+                    }
+                    self.read_checkpoint(&mut writer);
+                    if self.persist
+                        & (self.restore == Restore::Done)
+                        & (self.state.messages().len() != self.checkpointed)
+                    {
+                        self.checkpointed = self.state.messages().len();
+                        let response = Message::request(
+                            self.node.as_str(),
+                            KV_NODE,
+                            self.id,
+                            BroadcastRespone::WriteCheckpoint {
+                                key: self.checkpoint_key(),
+                                value: self.state.messages().clone(),
+                            },
+                        );
+                        writer.send(response);
+                        self.id += 1;
+                    }
+                }
+                Event::Input(request) => {
+                    let seen = self.state.messages().len();
+                    let payload =
+                        self.handle_input_payload(request.body.payload, &request.src, &mut tick_tx);
+                    if self.state.messages().len() > seen {
+                        self.converged = Some(self.start.elapsed());
+                    }
+                    if let Some(payload) = payload {
+                        let response =
+                            Message::reply(request.dst, request.src, request.body.id, payload)
+                                .with_id(self.id);
+                        writer.send(response);
+                        self.id += 1;
+                    }
+                }
+            };
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Tuning Configuration Implementation.
+//!
+//! Describe [Config] which gathers tuning knobs of all nodes.
+//! Knobs are read from a TOML file, falling back to environment variables.
+//! Seeds, log level and output tee remain environment variables only.
+
+use std::{path::Path, str::FromStr, sync::OnceLock};
+
+use serde::Deserialize;
+
+/// Tuning knobs of nodes.
+///
+/// Every knob is optional, nodes apply their own default for unset knobs.
+/// TOML key of knob is the field name, environment variable is noted per field.
+///
+/// # Example
+/// ```rust
+/// # use gossip_glomers::config::Config;
+/// let config = Config::parse("tick_time = 100\ncounter_kv = \"lin-kv\"").unwrap();
+/// assert_eq!(config.tick_time, Some(100));
+/// assert_eq!(config.counter_kv.as_deref(), Some("lin-kv"));
+/// assert_eq!(config.gossip_fanout, None);
+/// ```
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Tick interval in milliseconds, `TICK_TIME`.
+    pub tick_time: Option<u64>,
+    /// Tick interval jitter in percent, `TICK_JITTER`.
+    pub tick_jitter: Option<u32>,
+    /// Lower bound of adaptive tick in milliseconds, `TICK_MIN`.
+    pub tick_min: Option<u64>,
+    /// Upper bound of adaptive tick in milliseconds, `TICK_MAX`.
+    pub tick_max: Option<u64>,
+    /// Adapt tick interval to gossip, `TICK_ADAPTIVE`.
+    pub tick_adaptive: Option<bool>,
+    /// Force tick on new messages, `FORCE_TICK`.
+    pub force_tick: Option<bool>,
+    /// Bound of event channel, `EVENT_QUEUE`.
+    pub event_queue: Option<usize>,
+    /// Gossip mode, one of `push`, `pull` or `both`, `GOSSIP_MODE`.
+    pub gossip_mode: Option<String>,
+    /// Number of random peers to gossip with on every tick, `GOSSIP_FANOUT`.
+    pub gossip_fanout: Option<usize>,
+    /// Number of ticks between full state reconciliations, `RECONCILE_TICKS`.
+    pub reconcile_ticks: Option<usize>,
+    /// Checkpoint broadcast messages to key store, `BROADCAST_PERSIST`.
+    pub broadcast_persist: Option<bool>,
+    /// Reply to broadcast read with sorted messages, `BROADCAST_SORTED`.
+    pub broadcast_sorted: Option<bool>,
+    /// Key store of counter, `lin-kv` or sequential by default, `COUNTER_KV`.
+    pub counter_kv: Option<String>,
+    /// Shard counter by node, `COUNTER_SHARDED`.
+    pub counter_sharded: Option<bool>,
+    /// Read counter after every completed update to verify it, `COUNTER_VERIFY`.
+    pub counter_verify: Option<bool>,
+    /// Backoff of failed counter update in milliseconds, `CAS_BACKOFF`.
+    pub cas_backoff: Option<u64>,
+    /// Attempts of failed counter update before next tick, `CAS_ATTEMPTS`.
+    pub cas_attempts: Option<u32>,
+    /// Echo extra fields of request body, `ECHO_EXTRA`.
+    pub echo_extra: Option<bool>,
+    /// Delay before every echo reply in milliseconds, `ECHO_DELAY_MS`.
+    pub echo_delay_ms: Option<u64>,
+    /// Unique id generation mode, `kv` for key store blocks, `ID_MODE`.
+    pub id_mode: Option<String>,
+    /// Unique id format, `snowflake` for snowflake ids, `ID_FORMAT`.
+    pub id_format: Option<String>,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Knob of environment variable, unset or invalid values are ignored.
+fn var<T: FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok().and_then(|var| var.parse().ok())
+}
+
+/// Flag of environment variable, set by `1` or `true` and unset by `0` or `false`.
+///
+/// Other values are ignored.
+fn flag(key: &str) -> Option<bool> {
+    match std::env::var(key).ok()?.as_str() {
+        "1" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+impl Config {
+    /// Parse knobs from TOML.
+    ///
+    /// # Errors
+    ///
+    /// Errors with location on invalid TOML, unknown key or value of wrong type.
+    pub fn parse(toml: &str) -> Result<Self, String> {
+        toml::from_str(toml).map_err(|e| e.to_string())
+    }
+
+    /// Read knobs from TOML file.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let toml = std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {e}", path.display()))?;
+        Self::parse(&toml).map_err(|e| format!("invalid {}: {e}", path.display()))
+    }
+
+    /// Knobs from environment variables.
+    pub fn from_env() -> Self {
+        Self {
+            tick_time: var("TICK_TIME"),
+            tick_jitter: var("TICK_JITTER"),
+            tick_min: var("TICK_MIN"),
+            tick_max: var("TICK_MAX"),
+            tick_adaptive: flag("TICK_ADAPTIVE"),
+            force_tick: flag("FORCE_TICK"),
+            event_queue: var("EVENT_QUEUE"),
+            gossip_mode: var("GOSSIP_MODE"),
+            gossip_fanout: var("GOSSIP_FANOUT"),
+            reconcile_ticks: var("RECONCILE_TICKS"),
+            broadcast_persist: flag("BROADCAST_PERSIST"),
+            broadcast_sorted: flag("BROADCAST_SORTED"),
+            counter_kv: var("COUNTER_KV"),
+            counter_sharded: flag("COUNTER_SHARDED"),
+            counter_verify: flag("COUNTER_VERIFY"),
+            cas_backoff: var("CAS_BACKOFF"),
+            cas_attempts: var("CAS_ATTEMPTS"),
+            echo_extra: flag("ECHO_EXTRA"),
+            echo_delay_ms: var("ECHO_DELAY_MS"),
+            id_mode: var("ID_MODE"),
+            id_format: var("ID_FORMAT"),
+        }
+    }
+
+    /// Knobs of self, with unset knobs taken from fallback.
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            tick_time: self.tick_time.or(fallback.tick_time),
+            tick_jitter: self.tick_jitter.or(fallback.tick_jitter),
+            tick_min: self.tick_min.or(fallback.tick_min),
+            tick_max: self.tick_max.or(fallback.tick_max),
+            tick_adaptive: self.tick_adaptive.or(fallback.tick_adaptive),
+            force_tick: self.force_tick.or(fallback.force_tick),
+            event_queue: self.event_queue.or(fallback.event_queue),
+            gossip_mode: self.gossip_mode.or(fallback.gossip_mode),
+            gossip_fanout: self.gossip_fanout.or(fallback.gossip_fanout),
+            reconcile_ticks: self.reconcile_ticks.or(fallback.reconcile_ticks),
+            broadcast_persist: self.broadcast_persist.or(fallback.broadcast_persist),
+            broadcast_sorted: self.broadcast_sorted.or(fallback.broadcast_sorted),
+            counter_kv: self.counter_kv.or(fallback.counter_kv),
+            counter_sharded: self.counter_sharded.or(fallback.counter_sharded),
+            counter_verify: self.counter_verify.or(fallback.counter_verify),
+            cas_backoff: self.cas_backoff.or(fallback.cas_backoff),
+            cas_attempts: self.cas_attempts.or(fallback.cas_attempts),
+            echo_extra: self.echo_extra.or(fallback.echo_extra),
+            echo_delay_ms: self.echo_delay_ms.or(fallback.echo_delay_ms),
+            id_mode: self.id_mode.or(fallback.id_mode),
+            id_format: self.id_format.or(fallback.id_format),
+        }
+    }
+
+    /// Knobs of node, loaded once and shared by every caller.
+    ///
+    /// File at `GOSSIP_CONFIG` is read if set, no file is read otherwise.
+    /// Knobs missing from file fall back to environment variables.
+    /// Nodes call this at startup, hence invalid file fails before any message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if file at `GOSSIP_CONFIG` is missing, or if file is invalid.
+    pub fn load() -> &'static Self {
+        CONFIG.get_or_init(|| {
+            std::env::var("GOSSIP_CONFIG")
+                .ok()
+                .map(|path| Self::read(Path::new(&path)))
+                .transpose()
+                .unwrap_or_else(|e| panic!("{e}"))
+                .unwrap_or_default()
+                .or(Self::from_env())
+        })
+    }
+}
@@ -0,0 +1,738 @@
+//! Grow Counter Node Implementation.
+//!
+//! Describe [CounterRequest] and [CounterResponse] payloads of counter node.
+//! Provides [EventHandler] which runs the node on events,
+//! allowing node to be driven in-process, see [inputs](crate::event::inputs).
+
+use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
+use std::{sync::mpsc::Sender, time::Duration};
+
+use crate::{
+    config::Config,
+    derive_request, derive_response,
+    event::{tick_after, Backoff, Event},
+    init::InitRequest,
+    log,
+    log::Level,
+    message::{first_id, Body, ErrorCode, Message},
+};
+
+derive_request!(
+    /// Request payload for counter node.
+    pub enum CounterRequest {
+        /// Add request.
+        ///
+        /// This message requests that a value be added to a single global counter.
+        /// The value can be negative to decrement the counter.
+        /// ```json
+        /// {
+        ///     "type": "add",
+        ///     "delta": -10
+        /// }
+        /// ```
+        Add {
+            /// delta value.
+            delta: i64,
+        },
+        /// Read request.
+        ///
+        /// This message requests that value be read.
+        /// ```json
+        /// { "type": "read" }
+        /// ```
+        Read,
+        /// Counter value request.
+        ///
+        /// This message acknowledge [CounterResponse::ReadCounter].
+        /// ```json
+        /// {
+        ///     "type": "read_ok",
+        ///     "value": 10
+        /// }
+        /// ```
+        #[serde(rename = "read_ok")]
+        ReadCounterOk {
+            /// counter value.
+            value: i64,
+        },
+        /// Update Success request.
+        ///
+        /// This message acknowledge [CounterResponse::UpdateCounter].
+        /// ```json
+        /// { "type": "cas_ok" }
+        /// ```
+        #[serde(rename = "cas_ok")]
+        CounterUpdated,
+        /// Shard written request.
+        ///
+        /// This message acknowledge [CounterResponse::WriteShard].
+        /// ```json
+        /// { "type": "write_ok" }
+        /// ```
+        #[serde(rename = "write_ok")]
+        ShardWritten,
+        /// Error request.
+        ///
+        /// This message acknowledge error in operation.
+        /// ```json
+        /// {
+        ///     "type": "error"
+        ///     "code": 20,
+        ///     "text": "Some messsage"
+        /// }
+        /// ```
+        Error {
+            /// error code.
+            code: ErrorCode,
+            /// error message.
+            text: String,
+        },
+    }
+);
+
+derive_response!(
+    /// Response payload for counter node.
+    pub enum CounterResponse {
+        /// Add ok response.
+        ///
+        /// This message acknowledge to [CounterRequest::Add].
+        /// ```json
+        /// { "type": "add_ok" }
+        /// ```
+        AddOk,
+        /// Read counter response.
+        ///
+        /// This message to read to counter value from key store.
+        /// ```json
+        /// {
+        ///     "type": "read",
+        ///     "key": "COUNTER"
+        /// }
+        /// ```
+        #[serde(rename = "read")]
+        ReadCounter {
+            /// Key of counter from key store.
+            key: String,
+        },
+        /// Update counter response.
+        ///
+        /// This message to update to counter value in key store.
+        /// ```json
+        /// {
+        ///     "type": "cas",
+        ///     "key": "COUNTER",
+        ///     "old": 10,
+        ///     "new": 20
+        /// }
+        /// ```
+        #[serde(rename = "cas")]
+        UpdateCounter {
+            /// Counter key in store.
+            key: String,
+            /// Value to be updated from.
+            #[serde(rename = "from")]
+            old: i64,
+            /// Value to be updated to.
+            #[serde(rename = "to")]
+            new: i64,
+            /// Create key if not exists.
+            #[serde(rename = "create_if_not_exists")]
+            create: bool,
+        },
+        /// Write shard response.
+        ///
+        /// This message to write counter shard of node in key store.
+        /// ```json
+        /// {
+        ///     "type": "write",
+        ///     "key": "COUNTER/n1",
+        ///     "value": 20
+        /// }
+        /// ```
+        #[serde(rename = "write")]
+        WriteShard {
+            /// Shard key in store.
+            key: String,
+            /// Value of the shard.
+            value: i64,
+        },
+        /// Read Ok response.
+        ///
+        /// This message acknowledge to [CounterResponse::ReadOk].
+        /// It provide counter value from memory.
+        /// ```json
+        /// {
+        ///     "type": "read_ok",
+        ///     "value": 20
+        /// }
+        /// ```
+        ReadOk {
+            /// The value of counter from memory.
+            value: i64,
+        },
+    }
+);
+
+/// Default node id for key store.
+const KV_NODE: &str = "seq-kv";
+/// Key of the counter from store.
+const KEY: &str = "COUNTER";
+
+/// Compare and swap state of counter node.
+///
+/// Each method handles one request and returns response if any,
+/// message ids are of messages sent to key store.
+/// Replies are acted on only if they reply to an outstanding read or update.
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::counter::{CounterResponse, CounterState};
+/// # use gossip_glomers::message::ErrorCode;
+/// let mut state = CounterState::default();
+/// state.on_add(10);
+/// state.read(0);
+/// assert!(matches!(
+///     state.on_read_ok(5, Some(0), 1),
+///     Some(CounterResponse::UpdateCounter { old: 5, new: 15, .. })
+/// ));
+/// state.on_error(ErrorCode::PreconditionFailed, Some(1), 2);
+/// assert!(matches!(state.on_read(), CounterResponse::ReadOk { value: 15 }));
+/// ```
+#[derive(Default)]
+pub struct CounterState {
+    /// Value of counter.
+    value: i64,
+    /// Delta for counter.
+    delta: i64,
+    /// Counter update status.
+    ///
+    /// Stores:
+    ///     - update counter message id,
+    ///     - old counter value.
+    ///     - new counter value.
+    last_update: Option<(usize, i64, i64)>,
+    /// Message ids of outstanding reads.
+    reads: HashSet<usize>,
+    /// Value expected by outstanding verification read, by message id.
+    verifies: HashMap<usize, i64>,
+    /// Number of verification reads which did not find the committed value.
+    discrepancies: usize,
+}
+
+impl CounterState {
+    /// Add delta to counter.
+    pub fn on_add(&mut self, delta: i64) -> CounterResponse {
+        self.delta += delta;
+        CounterResponse::AddOk
+    }
+    /// Read current value and delta.
+    pub fn on_read(&self) -> CounterResponse {
+        CounterResponse::ReadOk {
+            value: self.value + self.delta,
+        }
+    }
+    /// Read counter value from key store with given message id.
+    pub fn read(&mut self, id: usize) -> CounterResponse {
+        self.reads.insert(id);
+        CounterResponse::ReadCounter { key: KEY.into() }
+    }
+    /// Update to counter value read from key store.
+    ///
+    /// * ignore reply unless it is for outstanding read, older reads are stale hence dropped.
+    /// * update current value to read value + delta.
+    /// * if delta != 0 then move delta to update and send update counter with given id.
+    pub fn on_read_ok(
+        &mut self,
+        value: i64,
+        reply_id: Option<usize>,
+        id: usize,
+    ) -> Option<CounterResponse> {
+        let Some(reply_id) = reply_id.filter(|reply_id| self.reads.contains(reply_id)) else {
+            log!(Level::Debug, "ignoring read reply to {reply_id:?}");
+            return None;
+        };
+        self.reads.retain(|read| *read > reply_id);
+        self.value = value + self.delta;
+        if self.delta == 0 {
+            return None;
+        }
+        self.last_update = Some((id, value, self.value));
+        Some(CounterResponse::UpdateCounter {
+            key: KEY.into(),
+            old: value,
+            new: value + std::mem::take(&mut self.delta),
+            create: false,
+        })
+    }
+    /// Complete update replied to.
+    ///
+    /// # Returns
+    /// New counter value of pending update if it was completed.
+    pub fn on_cas_ok(&mut self, reply_id: Option<usize>) -> Option<i64> {
+        if self.is_update(reply_id) {
+            self.last_update.take().map(|(_, _, new)| new)
+        } else {
+            log!(Level::Debug, "ignoring update reply to {reply_id:?}");
+            None
+        }
+    }
+    /// Read counter value from key store with given message id, expecting committed value.
+    pub fn verify(&mut self, committed: i64, id: usize) -> CounterResponse {
+        self.verifies.insert(id, committed);
+        CounterResponse::ReadCounter { key: KEY.into() }
+    }
+    /// Check counter value read by verification read replied to.
+    ///
+    /// Mismatch is logged and counted, see [Self::discrepancies].
+    /// Concurrent update by other node also causes mismatch, hence it is only a debugging aid.
+    ///
+    /// # Returns
+    /// Whether value matched committed value, `None` if reply is not for verification read.
+    pub fn on_verify_ok(&mut self, value: i64, reply_id: Option<usize>) -> Option<bool> {
+        let committed = self.verifies.remove(&reply_id?)?;
+        if value != committed {
+            log!(
+                Level::Warn,
+                "update to {committed} was acknowledged, but key store has {value}"
+            );
+            self.discrepancies += 1;
+        }
+        Some(value == committed)
+    }
+    /// Number of verification reads which did not find the committed value.
+    pub fn discrepancies(&self) -> usize {
+        self.discrepancies
+    }
+    /// Handle error replied to.
+    ///
+    /// * if error is for pending update, revert it.
+    /// * if error is for outstanding read, forget it.
+    /// * [KeyDoesNotExist](ErrorCode::KeyDoesNotExist): send create key request.
+    /// * [PreconditionFailed](ErrorCode::PreconditionFailed): send read counter request with given id.
+    /// * [timeout](ErrorCode::Timeout) or [key already exists](ErrorCode::KeyAlreadyExists):
+    ///   wait for next tick.
+    ///
+    /// # Returns
+    /// Response if any and whether update was reverted.
+    ///
+    /// # Panics
+    /// Panics on other error codes.
+    pub fn on_error(
+        &mut self,
+        code: ErrorCode,
+        reply_id: Option<usize>,
+        id: usize,
+    ) -> (Option<CounterResponse>, bool) {
+        let reverted = self.is_update(reply_id) && self.revert_update();
+        if let Some(reply_id) = reply_id {
+            self.reads.remove(&reply_id);
+            self.verifies.remove(&reply_id);
+        }
+        let response = match code {
+            ErrorCode::KeyDoesNotExist => Some(CounterResponse::UpdateCounter {
+                key: KEY.into(),
+                old: 0,
+                new: 0,
+                create: true,
+            }),
+            ErrorCode::PreconditionFailed => Some(self.read(id)),
+            ErrorCode::Timeout | ErrorCode::KeyAlreadyExists => None,
+            error => panic!("Unhandled error code: {error:?}"),
+        };
+        (response, reverted)
+    }
+    /// Pending update to be re-sent with its message id.
+    pub fn pending_update(&self) -> Option<(usize, CounterResponse)> {
+        let (id, old, new) = self.last_update?;
+        log!(Level::Debug, "pending cas {id} from {old} to {new}");
+        Some((
+            id,
+            CounterResponse::UpdateCounter {
+                key: KEY.into(),
+                old,
+                new,
+                create: false,
+            },
+        ))
+    }
+    /// Whether reply is for pending update.
+    fn is_update(&self, reply_id: Option<usize>) -> bool {
+        matches!((self.last_update, reply_id), (Some((id, ..)), Some(reply_id)) if id == reply_id)
+    }
+    /// Revert last counter update.
+    ///
+    /// The update is moved back to delta and value is restored to old counter value.
+    /// If moving update back overflows delta then the update is dropped with an error log.
+    ///
+    /// # Returns
+    /// Whether there was a update to revert.
+    fn revert_update(&mut self) -> bool {
+        let Some((_, old, new)) = self.last_update.take() else {
+            return false;
+        };
+        match new
+            .checked_sub(old)
+            .and_then(|update| self.delta.checked_add(update))
+        {
+            Some(delta) => self.delta = delta,
+            None => log!(
+                Level::Error,
+                "dropping update from {old} to {new} as it overflows delta {}",
+                self.delta
+            ),
+        }
+        self.value = old;
+        true
+    }
+}
+
+/// Event handler for counter node.
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::counter::EventHandler;
+/// # use gossip_glomers::event::{inputs, Event};
+/// # use gossip_glomers::init::InitRequest;
+/// let init = InitRequest::Init {
+///     node_id: "n1".into(),
+///     node_ids: vec!["n1".into()],
+/// };
+/// let input = r#"{ "src": "c1", "dest": "n1", "body": { "type": "read", "msg_id": 1 } }"#;
+/// let events = inputs(input.as_bytes()).chain([Event::tick()]);
+/// let mut output = Vec::new();
+/// let (tick_tx, _) = std::sync::mpsc::channel();
+/// EventHandler::new(init).handle_events(events, tick_tx, &mut output);
+/// let output = String::from_utf8(output).unwrap();
+/// assert!(output.contains(r#""type":"read_ok","value":0"#), "{output}");
+/// assert!(output.contains(r#""type":"read","key":"COUNTER""#), "{output}");
+/// ```
+pub struct EventHandler {
+    /// Message id counter, starting from [first_id].
+    id: usize,
+    /// Node id.
+    node: String,
+    /// Node id of key store.
+    kv: String,
+    /// Compare and swap state.
+    state: CounterState,
+    /// Shard value of each node when sharded.
+    ///
+    /// Own shard is updated on add and other shards on reading key store.
+    shards: Option<HashMap<String, i64>>,
+    /// Own shard value in last write.
+    written: Option<i64>,
+    /// Message id of latest read of shard by node id.
+    ///
+    /// Each tick replaces read of every node, hence reply to older or lost read is dropped.
+    reads: HashMap<String, usize>,
+    /// Backoff of retrying failed update before next tick.
+    backoff: Backoff,
+    /// Read counter after every completed update to verify it.
+    verify: bool,
+}
+
+impl EventHandler {
+    /// Create new event handler from initialization message.
+    pub fn new(init_request: InitRequest) -> Self {
+        let config = Config::load();
+        let kv = match config.counter_kv.as_deref() {
+            Some("lin-kv") => "lin-kv",
+            _ => KV_NODE,
+        };
+        let (node, node_ids) = match init_request {
+            InitRequest::Init { node_id, node_ids } => (node_id, node_ids),
+        };
+        let shards = config
+            .counter_sharded
+            .unwrap_or(false)
+            .then(|| node_ids.into_iter().map(|node| (node, 0)).collect());
+        Self {
+            id: first_id(),
+            kv: kv.into(),
+            node,
+            state: CounterState::default(),
+            shards,
+            written: None,
+            reads: HashMap::default(),
+            backoff: Backoff::new(
+                Duration::from_millis(config.cas_backoff.unwrap_or(10)),
+                config.cas_attempts.unwrap_or(5),
+            ),
+            verify: config.counter_verify.unwrap_or(false),
+        }
+    }
+    /// Read counter after every completed update to verify it, defaults to `COUNTER_VERIFY`.
+    ///
+    /// See [CounterState::on_verify_ok].
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+    /// Shard counter across given nodes, defaults to nodes of cluster when `COUNTER_SHARDED`.
+    pub fn sharded(mut self, nodes: &[&str]) -> Self {
+        self.shards = Some(nodes.iter().map(|node| (node.to_string(), 0)).collect());
+        self
+    }
+    /// Number of verification reads which did not find the committed value.
+    pub fn discrepancies(&self) -> usize {
+        self.state.discrepancies()
+    }
+    /// Start message ids from id, defaults to [first_id].
+    pub fn start_id(mut self, id: usize) -> Self {
+        self.id = id;
+        self
+    }
+    /// Key of counter shard of node.
+    fn shard_key(node: &str) -> String {
+        format!("{KEY}/{node}")
+    }
+    /// Handle input requests.
+    ///
+    /// Replies from nodes other than key store are ignored.
+    ///
+    /// Handle requests in following ways:
+    /// * [Add](CounterRequest::Add):
+    ///     * add to delta and send add ok.
+    /// * [Read](CounterRequest::Read):
+    ///     * send force tick.
+    ///     * send read ok with current value + delta.
+    /// * [Read counter ok](CounterRequest::ReadCounterOk):
+    ///     * if it is verification read, check value, see [CounterState::on_verify_ok].
+    ///     * if it is replied to, update current value to new value + delta.
+    ///     * if delta != 0 then
+    ///         * set counter update delta.
+    ///         * send update counter request.
+    /// * [Counter update](CounterRequest::CounterUpdated):
+    ///     * unset counter update delta if it is replied to and reset [Backoff].
+    ///     * send verification read if verifying, see [Self::verify].
+    /// * [Error](CounterRequest::Error):
+    ///     * revert last counter update if it is replied to, see [CounterState::on_error].
+    ///     * [KeyDoesNotExist](ErrorCode::KeyDoesNotExist):
+    ///         * update counter failed due to key not existing.
+    ///         * send create key request.
+    ///     * [PreconditionFailed](ErrorCode::PreconditionFailed):
+    ///         * update counter failed due to stale value.
+    ///         * send read counter request to get fresh value.
+    ///     * Update key failed with errors:
+    ///         * [timeout](ErrorCode::Timeout)
+    ///         * [key already exists](ErrorCode::KeyAlreadyExists)
+    ///         * force tick after [Backoff] delay to retry update,
+    ///           once attempts are exhausted wait for next tick.
+    ///
+    /// # Arguments
+    /// * payload: request to be handled requests.
+    /// * src: source node id.
+    /// * reply_id: id of message being replied to.
+    /// * tick_tx: tick sender to allow force ticking, ignored once ticker has stopped.
+    ///
+    /// # Returns
+    /// Response if any for payload.
+    fn handle_input_payload(
+        &mut self,
+        payload: CounterRequest,
+        src: &str,
+        reply_id: Option<usize>,
+        tick_tx: &mut Sender<()>,
+    ) -> Option<CounterResponse> {
+        if !matches!(payload, CounterRequest::Add { .. } | CounterRequest::Read) && src != self.kv {
+            log!(Level::Debug, "ignoring reply {payload:?} from {src}");
+            return None;
+        }
+        if self.shards.is_some() {
+            return self.handle_sharded_payload(payload, reply_id);
+        }
+        match payload {
+            CounterRequest::Add { delta } => Some(self.state.on_add(delta)),
+            CounterRequest::Read => {
+                let _ = tick_tx.send(());
+                Some(self.state.on_read())
+            }
+            CounterRequest::ReadCounterOk { value } => {
+                match self.state.on_verify_ok(value, reply_id) {
+                    Some(_) => None,
+                    None => self.state.on_read_ok(value, reply_id, self.id),
+                }
+            }
+            CounterRequest::CounterUpdated => {
+                let committed = self.state.on_cas_ok(reply_id)?;
+                self.backoff.reset();
+                self.verify.then(|| self.state.verify(committed, self.id))
+            }
+            CounterRequest::ShardWritten => None,
+            CounterRequest::Error { code, .. } => {
+                let retry = !matches!(code, ErrorCode::PreconditionFailed);
+                let (response, reverted) = self.state.on_error(code, reply_id, self.id);
+                if reverted && retry {
+                    if let Some(delay) = self.backoff.next_delay() {
+                        tick_after(tick_tx.clone(), delay);
+                    }
+                }
+                response
+            }
+        }
+    }
+    /// Handle input requests when sharded.
+    ///
+    /// Handle requests in following ways:
+    /// * [Add](CounterRequest::Add):
+    ///     * add to own shard and send add ok.
+    /// * [Read](CounterRequest::Read):
+    ///     * send read ok with sum of all shards.
+    /// * [Read counter ok](CounterRequest::ReadCounterOk):
+    ///     * update shard of node whose read is replied.
+    /// * [Error](CounterRequest::Error):
+    ///     * failed read keeps last known shard value,
+    ///       key not existing means node has not written yet.
+    ///     * failed write is re-sent on next tick.
+    /// * others are ignored.
+    fn handle_sharded_payload(
+        &mut self,
+        payload: CounterRequest,
+        reply_id: Option<usize>,
+    ) -> Option<CounterResponse> {
+        let shards = self.shards.as_mut()?;
+        let read_shard = reply_id.and_then(|reply_id| {
+            let node = self
+                .reads
+                .iter()
+                .find_map(|(node, id)| (*id == reply_id).then(|| node.clone()))?;
+            self.reads.remove(&node);
+            Some(node)
+        });
+        match payload {
+            CounterRequest::Add { delta } => {
+                *shards.entry(self.node.clone()).or_default() += delta;
+                Some(CounterResponse::AddOk)
+            }
+            CounterRequest::Read => Some(CounterResponse::ReadOk {
+                value: shards.values().sum(),
+            }),
+            CounterRequest::ReadCounterOk { value } => {
+                if let Some(node) = read_shard {
+                    shards.insert(node, value);
+                }
+                None
+            }
+            CounterRequest::Error { .. } => {
+                if read_shard.is_none() {
+                    self.written.take();
+                }
+                None
+            }
+            CounterRequest::CounterUpdated | CounterRequest::ShardWritten => None,
+        }
+    }
+    /// Handle tick when sharded.
+    ///
+    /// * send [CounterResponse::WriteShard] if own shard changed since last write.
+    /// * send [CounterResponse::ReadCounter] for shard of every other node,
+    ///   replacing read still pending from earlier tick.
+    fn sharded_tick<W: std::io::Write>(&mut self, writer: &mut W) {
+        let Some(shards) = &self.shards else {
+            return;
+        };
+        let shard = shards.get(&self.node).copied().unwrap_or_default();
+        let mut payloads = Vec::new();
+        if self.written != Some(shard) {
+            self.written = Some(shard);
+            payloads.push(CounterResponse::WriteShard {
+                key: Self::shard_key(&self.node),
+                value: shard,
+            });
+        }
+        log!(
+            Level::Debug,
+            "shard {shard}, writing {}",
+            !payloads.is_empty()
+        );
+        for node in shards.keys().filter(|node| **node != self.node) {
+            self.reads.insert(node.clone(), self.id + payloads.len());
+            payloads.push(CounterResponse::ReadCounter {
+                key: Self::shard_key(node),
+            });
+        }
+        for payload in payloads {
+            let response = Message {
+                body: Body {
+                    id: Some(self.id),
+                    reply_id: None,
+                    payload,
+                },
+                src: self.node.clone(),
+                dst: self.kv.clone(),
+            };
+            response.send(writer);
+            self.id += 1;
+        }
+    }
+    /// Handle events.
+    ///
+    /// Handle events in following ways:
+    /// * [close](Event::Close): close the loop.
+    /// * [tick](Event::Tick):
+    ///     * if sharded then use `Self::sharded_tick`.
+    ///     * send [CounterResponse::UpdateCounter] if there is some delta.
+    ///     * else send [CounterResponse::ReadCounter] if there is no delta.
+    /// * [input](Event::Input):
+    ///     * send payload to `Self::handle_input_payload`.
+    ///     * send any response via writer.
+    ///
+    /// # Arguments
+    /// * events: Events to handle, usually receiver channel.
+    /// * tick_tx: Tick sender to allow force ticking.
+    /// * writer: Output response via writer.
+    pub fn handle_events<W: std::io::Write>(
+        &mut self,
+        events: impl IntoIterator<Item = Event<CounterRequest>>,
+        mut tick_tx: Sender<()>,
+        writer: &mut W,
+    ) {
+        for event in events {
+            match event {
+                Event::Close => {
+                    break;
+                }
+                Event::Tick(_) if self.shards.is_some() => self.sharded_tick(writer),
+                Event::Tick(_) => {
+                    let (msg_id, payload) = self.state.pending_update().unwrap_or_else(|| {
+                        let id = self.id;
+                        self.id += 1;
+                        (id, self.state.read(id))
+                    });
+                    let response = Message {
+                        body: Body {
+                            id: Some(msg_id),
+                            reply_id: None,
+                            payload,
+                        },
+                        src: self.node.clone(),
+                        dst: self.kv.clone(),
+                    };
+                    response.send(writer);
+                }
+                Event::Input(request) => {
+                    if let Some(payload) = self.handle_input_payload(
+                        request.body.payload,
+                        &request.src,
+                        request.body.reply_id,
+                        &mut tick_tx,
+                    ) {
+                        let response = Message {
+                            body: Body {
+                                id: Some(self.id),
+                                reply_id: request.body.id,
+                                payload,
+                            },
+                            src: request.dst,
+                            dst: request.src,
+                        };
+                        response.send(writer);
+                        self.id += 1;
+                    }
+                }
+            };
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Echo Node Implementation.
+//!
+//! Describe [EchoRequest] and [EchoResponse] payloads of echo node.
+//! Provides [Echo] which serves requests over any [Transport],
+//! or over async `Runtime` with `async` feature.
+
+use std::time::Duration;
+
+use serde_json::{Map, Value};
+
+use crate::{
+    config::Config, derive_request, derive_response, message::Message, transport::Transport,
+};
+
+derive_request!(
+    /// Request payload for echo node.
+    pub enum EchoRequest {
+        /// Echo request.
+        ///
+        /// This message requests that a node echo a message.
+        /// ```json
+        /// { "echo": "Please echo 35"}
+        /// ```
+        Echo {
+            /// holds the message.
+            echo: String,
+            /// holds fields other than message.
+            #[serde(flatten)]
+            extra: Map<String, Value>,
+        },
+    }
+);
+
+derive_response!(
+    /// Response payload for echo node.
+    pub enum EchoResponse {
+        /// Echo ok response.
+        ///
+        /// This message acknowledges Echo request.
+        /// It includes the echo message from request.
+        /// ```json
+        /// {
+        ///     "type": "echo_ok",
+        ///     "echo": "Please echo 35"
+        /// }
+        /// ```
+        EchoOk {
+            /// holds the message.
+            echo: String,
+            /// holds fields other than message from request.
+            ///
+            /// Only filled when `ECHO_EXTRA=1`.
+            #[serde(flatten)]
+            extra: Map<String, Value>,
+        },
+    }
+);
+
+/// Echo node replying to every echo request.
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::echo::Echo;
+/// # use gossip_glomers::transport::StdioTransport;
+/// let input = r#"
+///     { "src": "c1", "dest": "n1", "body": { "type": "echo", "msg_id": 1, "echo": "hi" } }
+/// "#.as_bytes();
+/// let mut transport = StdioTransport::new(input, Vec::new());
+/// Echo::new().serve(&mut transport);
+/// let output = String::from_utf8(transport.into_inner()).unwrap();
+/// assert!(output.contains(r#""type":"echo_ok","echo":"hi""#), "{output}");
+/// ```
+pub struct Echo {
+    /// Echo extra fields of request body.
+    extra: bool,
+    /// Delay before every reply.
+    delay: Option<Duration>,
+}
+
+impl Echo {
+    /// Create echo node, configured by `ECHO_EXTRA` and `ECHO_DELAY_MS`, see [Config].
+    pub fn new() -> Self {
+        let config = Config::load();
+        Self {
+            extra: config.echo_extra.unwrap_or(false),
+            delay: config.echo_delay_ms.map(Duration::from_millis),
+        }
+    }
+    /// Echo extra fields of request body.
+    pub fn extra(mut self, extra: bool) -> Self {
+        self.extra = extra;
+        self
+    }
+    /// Sleep before every reply, to act as a slow responder.
+    pub fn delay(mut self, delay: Option<Duration>) -> Self {
+        self.delay = delay;
+        self
+    }
+    /// Reply to echo requests until input is closed.
+    ///
+    /// Replies take message ids from 0 in order of requests.
+    pub fn serve<T: Transport>(&self, transport: &mut T) {
+        let mut id = 0;
+        while let Some(request) = transport.recv::<EchoRequest>() {
+            if let Some(delay) = self.delay {
+                std::thread::sleep(delay);
+            }
+            transport.send(self.reply(request, id));
+            id += 1;
+        }
+    }
+    /// Reply to echo requests on async runtime until input is closed, see [Self::serve].
+    #[cfg(feature = "async")]
+    pub async fn serve_async<R, W>(&self, runtime: &mut crate::runtime::Runtime<R, W>)
+    where
+        R: tokio::io::AsyncBufRead + Unpin,
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        let mut id = 0;
+        while let Some(request) = runtime.recv::<EchoRequest>().await {
+            if let Some(delay) = self.delay {
+                tokio::time::sleep(delay).await;
+            }
+            runtime.send(self.reply(request, id)).await;
+            id += 1;
+        }
+    }
+    /// Reply with id to echo request.
+    fn reply(&self, request: Message<EchoRequest>, id: usize) -> Message<EchoResponse> {
+        let EchoRequest::Echo { echo, extra } = request.body.payload;
+        let extra = if self.extra { extra } else { Map::new() };
+        Message::reply(
+            request.dst,
+            request.src,
+            request.body.id,
+            EchoResponse::EchoOk { echo, extra },
+        )
+        .with_id(id)
+    }
+}
+
+impl Default for Echo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,450 @@
+//! Event Loop Implementation.
+//!
+//! Describe [Event] handled by nodes.
+//! Provides function to generate tick and input events.
+
+use std::{
+    io::BufRead,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{sync_channel, Receiver, RecvTimeoutError, Sender, SyncSender},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    config::Config,
+    log,
+    log::Level,
+    message::{receive, Message, Request},
+    random::Rng,
+};
+
+/// Event for node to handle.
+pub enum Event<Payload> {
+    /// Tick Event to handle timer based events.
+    Tick(PendingTick),
+    /// Close Event to stop node.
+    Close,
+    /// Input Event from other nodes.
+    Input(Message<Payload>),
+}
+
+impl<Payload> Event<Payload> {
+    /// Tick not tracked by [ticker], allows driving node without ticker.
+    pub fn tick() -> Self {
+        Event::Tick(PendingTick(Arc::new(AtomicBool::new(true))))
+    }
+}
+
+/// Marks tick as pending until it is dropped.
+///
+/// Allows [ticker] to skip ticks while handler has not handled the last one.
+pub struct PendingTick(Arc<AtomicBool>);
+
+impl PendingTick {
+    /// Mark tick as pending.
+    ///
+    /// # Returns
+    /// Pending tick if no other tick is pending.
+    fn new(pending: &Arc<AtomicBool>) -> Option<Self> {
+        (!pending.swap(true, Ordering::AcqRel)).then(|| Self(pending.clone()))
+    }
+}
+
+impl Drop for PendingTick {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// Detects ticks consistently delayed beyond the tick interval.
+///
+/// Under load the process can be descheduled,
+/// hence timeout of tick may fire much later than configured.
+///
+/// # Example
+/// ```rust
+/// # use gossip_glomers::event::TickSkew;
+/// # use std::time::Duration;
+/// let interval = Duration::from_millis(100);
+/// let mut skew = TickSkew::new(interval);
+/// assert!(!skew.observe(interval * 10));
+/// // single on time tick resets the delayed ticks.
+/// assert!(!skew.observe(interval));
+/// let warnings = (0..TickSkew::LIMIT * 2)
+///     .filter(|_| skew.observe(interval * 10))
+///     .count();
+/// assert_eq!(warnings, 2);
+/// ```
+pub struct TickSkew {
+    interval: Duration,
+    delayed: usize,
+}
+
+impl TickSkew {
+    /// Tick delayed by this factor of interval is considered delayed.
+    pub const FACTOR: u32 = 3;
+    /// Number of consecutive delayed ticks to warn about.
+    pub const LIMIT: usize = 5;
+
+    /// Create detector for tick interval.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            delayed: 0,
+        }
+    }
+
+    /// Observe elapsed time between ticks.
+    ///
+    /// # Returns
+    /// `true` on every [Self::LIMIT] consecutive delayed tick.
+    pub fn observe(&mut self, elapsed: Duration) -> bool {
+        if elapsed > self.interval * Self::FACTOR {
+            self.delayed += 1;
+        } else {
+            self.delayed = 0;
+        }
+        if self.delayed == Self::LIMIT {
+            self.delayed = 0;
+            return true;
+        }
+        false
+    }
+}
+
+/// Tick interval randomized within percent of base interval.
+///
+/// Avoids all nodes ticking at the same instant.
+///
+/// # Example
+/// ```rust
+/// # use gossip_glomers::{event::Jitter, random::Rng};
+/// # use std::time::Duration;
+/// let jitter = Jitter::new(Duration::from_millis(200), 10);
+/// let mut rng = Rng::new(1);
+/// for _ in 0..1000 {
+///     let interval = jitter.interval(&mut rng);
+///     assert!(interval >= Duration::from_millis(180), "{interval:?}");
+///     assert!(interval <= Duration::from_millis(220), "{interval:?}");
+/// }
+/// ```
+pub struct Jitter {
+    base: Duration,
+    percent: u32,
+}
+
+impl Jitter {
+    /// Create jitter of percent around base interval, percent is capped at 100.
+    pub fn new(base: Duration, percent: u32) -> Self {
+        Self {
+            base,
+            percent: percent.min(100),
+        }
+    }
+
+    /// Next tick interval, base interval if percent is zero.
+    pub fn interval(&self, rng: &mut Rng) -> Duration {
+        if self.percent == 0 {
+            return self.base;
+        }
+        let spread = self.base * self.percent / 100;
+        let offset = rng.below(spread.as_micros() as usize * 2 + 1) as u64;
+        self.base - spread + Duration::from_micros(offset)
+    }
+}
+
+/// Exponential backoff for retrying failed requests.
+///
+/// Delay doubles from base on every attempt, growing at most [Self::MAX_FACTOR] times base.
+/// Once max attempts are made no delay is given, hence retry is left to [ticker].
+///
+/// # Example
+/// ```rust
+/// # use gossip_glomers::event::Backoff;
+/// # use std::time::Duration;
+/// let mut backoff = Backoff::new(Duration::from_millis(10), 3);
+/// assert_eq!(backoff.next_delay(), Some(Duration::from_millis(10)));
+/// assert_eq!(backoff.next_delay(), Some(Duration::from_millis(20)));
+/// assert_eq!(backoff.next_delay(), Some(Duration::from_millis(40)));
+/// assert_eq!(backoff.next_delay(), None);
+/// backoff.reset();
+/// assert_eq!(backoff.next_delay(), Some(Duration::from_millis(10)));
+/// ```
+pub struct Backoff {
+    base: Duration,
+    max_attempts: u32,
+    attempt: u32,
+}
+
+impl Backoff {
+    /// Maximum factor of base delay.
+    pub const MAX_FACTOR: u32 = 64;
+
+    /// Create backoff starting from base delay for at most max attempts.
+    pub fn new(base: Duration, max_attempts: u32) -> Self {
+        Self {
+            base,
+            max_attempts,
+            attempt: 0,
+        }
+    }
+
+    /// Delay before next attempt.
+    ///
+    /// # Returns
+    /// `None` once max attempts are made.
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        if self.attempt >= self.max_attempts {
+            return None;
+        }
+        let factor = 1u32
+            .checked_shl(self.attempt)
+            .unwrap_or(u32::MAX)
+            .min(Self::MAX_FACTOR);
+        self.attempt += 1;
+        Some(self.base.saturating_mul(factor))
+    }
+
+    /// Start again from base delay, usually after a successful attempt.
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+/// Force tick after delay, see [ticker].
+///
+/// Sending is ignored if ticker has stopped meanwhile.
+pub fn tick_after(tick_tx: Sender<()>, delay: Duration) {
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let _ = tick_tx.send(());
+    });
+}
+
+/// Event channel bounded by `EVENT_QUEUE` events, defaults to [EVENT_QUEUE].
+///
+/// Senders block while channel is full, hence input is not read faster than handled.
+pub fn channel<Payload>() -> (SyncSender<Event<Payload>>, Receiver<Event<Payload>>) {
+    sync_channel(Config::load().event_queue.unwrap_or(EVENT_QUEUE))
+}
+
+/// Default bound of event channel.
+pub const EVENT_QUEUE: usize = 1024;
+
+/// Send tick event to node and provides force ticking.
+///
+/// The tick interval is read from `TICK_TIME` in milliseconds,
+/// defaults to `default_tick`.
+/// At most one tick is pending at a time, hence ticks do not pile up behind slow handler.
+/// Force ticks received together collapse into one immediate tick and restart the interval.
+/// Force tick is never lost, as any pending tick is handled after the force was sent.
+/// Logs warning if ticks are consistently delayed, see [TickSkew].
+/// Interval is randomized within `TICK_JITTER` percent, defaults to no jitter, see [Jitter].
+/// Stops when handler stops, i.e. tick sender or event receiver is dropped.
+///
+/// # Example
+/// ```rust
+/// # use gossip_glomers::event::{channel, ticker, Event};
+/// # use std::time::Duration;
+/// let (event_tx, event_rx) = channel::<()>();
+/// let (tick_tx, tick_rx) = std::sync::mpsc::channel();
+/// std::thread::spawn(move || ticker(event_tx, tick_rx, 10));
+/// // handler is blocked for multiple tick intervals.
+/// std::thread::sleep(Duration::from_millis(100));
+/// let ticks = event_rx.try_iter().collect::<Vec<_>>();
+/// assert_eq!(ticks.len(), 1);
+/// // handling the tick allows next tick.
+/// drop(ticks);
+/// assert!(matches!(event_rx.recv(), Ok(Event::Tick(_))));
+/// # drop(tick_tx);
+/// ```
+pub fn ticker<Payload>(
+    event_tx: SyncSender<Event<Payload>>,
+    tick_rx: Receiver<()>,
+    default_tick: u64,
+) {
+    let interval = Duration::from_millis(tick_time(default_tick));
+    let jitter = Jitter::new(interval, Config::load().tick_jitter.unwrap_or(0));
+    let mut rng = Rng::from_entropy();
+    tick_loop(event_tx, tick_rx, interval, || jitter.interval(&mut rng));
+}
+
+/// Tick interval from `TICK_TIME` in milliseconds, defaults to `default_tick`.
+fn tick_time(default_tick: u64) -> u64 {
+    Config::load().tick_time.unwrap_or(default_tick)
+}
+
+/// Lower bound of any [AdaptiveTick] interval.
+pub const MIN_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Tick interval adapted to gossip observed by node.
+///
+/// Interval shrinks by half when new values outnumber redundant ones,
+/// as there is data pending to spread,
+/// and grows by a quarter otherwise, including when there is nothing to gossip.
+/// Interval is clamped within min and max bounds,
+/// where min is at least [MIN_INTERVAL] so interval can grow back and never spins.
+/// Clones share the interval, hence node observes while [adaptive_ticker] ticks.
+///
+/// # Example
+/// ```rust
+/// # use gossip_glomers::event::AdaptiveTick;
+/// # use std::time::Duration;
+/// let ms = Duration::from_millis;
+/// let tick = AdaptiveTick::new(ms(200), ms(50), ms(400));
+/// assert_eq!(tick.observe(10, 1), ms(100));
+/// assert_eq!(tick.observe(10, 1), ms(50));
+/// assert_eq!(tick.observe(10, 1), ms(50));
+/// assert_eq!(tick.observe(0, 0), Duration::from_micros(62_500));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdaptiveTick {
+    /// Current interval in microseconds.
+    interval: Arc<AtomicU64>,
+    /// Lower bound of interval.
+    min: Duration,
+    /// Upper bound of interval.
+    max: Duration,
+}
+
+impl AdaptiveTick {
+    /// Create adaptive interval starting from base, clamped within min and max.
+    ///
+    /// Min below [MIN_INTERVAL] is raised to it.
+    pub fn new(base: Duration, min: Duration, max: Duration) -> Self {
+        let tick = Self {
+            interval: Arc::new(AtomicU64::new(0)),
+            min: min.max(MIN_INTERVAL),
+            max,
+        };
+        tick.set(base);
+        tick
+    }
+
+    /// Create adaptive interval from [Config].
+    ///
+    /// Base is [Config::tick_time] in milliseconds, defaults to `default_tick`.
+    /// Bounds are [Config::tick_min] and [Config::tick_max] in milliseconds,
+    /// defaults to quarter and four times the base.
+    pub fn from_config(default_tick: u64) -> Self {
+        let base = tick_time(default_tick);
+        let config = Config::load();
+        Self::new(
+            Duration::from_millis(base),
+            Duration::from_millis(config.tick_min.unwrap_or(base / 4)),
+            Duration::from_millis(config.tick_max.unwrap_or(base * 4)),
+        )
+    }
+
+    /// Current interval.
+    pub fn interval(&self) -> Duration {
+        Duration::from_micros(self.interval.load(Ordering::Acquire))
+    }
+
+    /// Upper bound of interval.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Adjust interval to values gossiped in last tick.
+    ///
+    /// # Returns
+    /// Adjusted interval.
+    pub fn observe(&self, new: usize, redundant: usize) -> Duration {
+        let interval = self.interval().max(MIN_INTERVAL);
+        let interval = if new > redundant {
+            interval / 2
+        } else {
+            interval * 5 / 4
+        };
+        self.set(interval);
+        self.interval()
+    }
+
+    /// Set interval clamped within bounds.
+    fn set(&self, interval: Duration) {
+        let interval = interval.clamp(self.min, self.max.max(self.min));
+        self.interval
+            .store(interval.as_micros() as u64, Ordering::Release);
+    }
+}
+
+/// Send tick event to node on adaptive interval and provides force ticking.
+///
+/// Same as [ticker], but interval is read from [AdaptiveTick] before every tick,
+/// and ticks are considered delayed against its upper bound.
+pub fn adaptive_ticker<Payload>(
+    event_tx: SyncSender<Event<Payload>>,
+    tick_rx: Receiver<()>,
+    tick: AdaptiveTick,
+) {
+    tick_loop(event_tx, tick_rx, tick.max(), || tick.interval());
+}
+
+/// Send tick event on every interval or force tick, see [ticker].
+///
+/// Ticks are considered delayed against given interval, see [TickSkew].
+fn tick_loop<Payload>(
+    event_tx: SyncSender<Event<Payload>>,
+    tick_rx: Receiver<()>,
+    interval: Duration,
+    mut next_interval: impl FnMut() -> Duration,
+) {
+    let pending = Arc::new(AtomicBool::new(false));
+    let mut skew = TickSkew::new(interval);
+    loop {
+        let start = Instant::now();
+        match tick_rx.recv_timeout(next_interval()) {
+            Err(RecvTimeoutError::Timeout) => {
+                let elapsed = start.elapsed();
+                if skew.observe(elapsed) {
+                    log!(
+                        Level::Warn,
+                        "ticks delayed, last took {elapsed:?} instead of {interval:?}"
+                    );
+                }
+            }
+            Ok(_) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        tick_rx.try_iter().fuse().for_each(drop);
+        if let Some(tick) = PendingTick::new(&pending) {
+            if event_tx.send(Event::Tick(tick)).is_err() {
+                // handler has stopped, hence node is shutting down.
+                break;
+            }
+        }
+    }
+}
+
+/// Input events from reader.
+///
+/// Malformed input is logged and skipped, see [receive].
+pub fn inputs<Payload: DeserializeOwned + Request, R: BufRead>(
+    reader: R,
+) -> impl Iterator<Item = Event<Payload>> {
+    receive(reader).map(Event::Input)
+}
+
+/// Receive input from reader and send events to channel.
+///
+/// Malformed input is logged and skipped, see [inputs].
+/// Blocks reading while channel is full, see [channel].
+/// Sends [Event::Close] when input is closed.
+pub fn input_recv<Payload: DeserializeOwned + Request, R: BufRead>(
+    reader: R,
+    event_tx: SyncSender<Event<Payload>>,
+) {
+    for event in inputs(reader) {
+        if event_tx.send(event).is_err() {
+            break;
+        }
+    }
+    // handler may have already stopped.
+    let _ = event_tx.send(Event::Close);
+}
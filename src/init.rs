@@ -1,7 +1,13 @@
 //! Initialization Protocol Implementation.
+//!
+//! Provides [init] to handle the protocol and [Cluster] to order nodes it describes.
+use serde::{de::DeserializeOwned, Deserialize};
+
 use crate::{
-    derive_request, derive_response,
-    message::{Body, Message},
+    derive_request, derive_response, log,
+    log::Level,
+    message::{Message, Request},
+    transport::Transport,
 };
 
 derive_request!(
@@ -90,16 +96,173 @@ pub fn init<'a, W: std::io::Write, R: serde_json::de::Read<'a>>(
     writer: &mut W,
     deseralizer: &mut serde_json::Deserializer<R>,
 ) -> InitRequest {
-    let init_msg = Message::recv(deseralizer);
-    let reply = Message {
-        src: init_msg.dst,
-        dst: init_msg.src,
-        body: Body {
-            id: None,
-            reply_id: init_msg.body.id,
-            payload: InitRespone::InitOk,
-        },
-    };
+    init_with::<NoExtension, W, R>(writer, deseralizer).init
+}
+
+/// Initialization payload with workload specific fields.
+///
+/// Fields of `init` body other than [InitRequest] fields are captured by extension,
+/// which is usually a struct of the expected fields, unknown fields are ignored.
+#[derive(Deserialize, Debug)]
+#[serde(bound = "Ext: DeserializeOwned")]
+pub struct InitExtension<Ext> {
+    /// Initialization payload.
+    #[serde(flatten)]
+    pub init: InitRequest,
+    /// Workload specific fields.
+    #[serde(flatten)]
+    pub extension: Ext,
+}
+
+impl<Ext: DeserializeOwned> Request for InitExtension<Ext> {}
+
+/// Extension without any field, used by [init].
+#[derive(Deserialize, Debug, Default)]
+pub struct NoExtension {}
+
+/// Handles Initialization Protocol and returns Initialization payload with extension.
+///
+/// Same as [init], but also reads workload specific fields of `init` body.
+///
+/// # Example
+/// ```rust
+/// # use gossip_glomers::init::{init_with, InitRequest};
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     config: serde_json::Value,
+/// }
+/// let input = r#"
+///     {
+///         "src": "c1",
+///         "dest": "n1",
+///         "body": {
+///             "msg_id": 1,
+///             "type": "init",
+///             "node_id": "n1",
+///             "node_ids": ["n1"],
+///             "config": { "shards": 4 }
+///         }
+///     }
+/// "#.as_bytes();
+/// let mut deseralizer = serde_json::Deserializer::from_reader(input);
+/// let mut writer = Vec::new();
+/// let init = init_with::<Config, _, _>(&mut writer, &mut deseralizer);
+/// assert_eq!(init.extension.config["shards"], 4);
+/// let InitRequest::Init { node_id, .. } = init.init;
+/// assert_eq!(node_id, "n1");
+/// ```
+pub fn init_with<'a, Ext: DeserializeOwned, W: std::io::Write, R: serde_json::de::Read<'a>>(
+    writer: &mut W,
+    deseralizer: &mut serde_json::Deserializer<R>,
+) -> InitExtension<Ext> {
+    let init_msg = Message::<InitExtension<Ext>>::recv(deseralizer);
+    let (reply, init) = on_init(init_msg);
     reply.send(writer);
-    init_msg.body.payload
+    init
+}
+
+/// Handles Initialization Protocol over transport and returns Initialization payload.
+///
+/// Same as [init], but messages which are not `init` are skipped.
+///
+/// Returns [None] if input is closed before initialization.
+pub fn init_transport<T: Transport>(transport: &mut T) -> Option<InitRequest> {
+    let init_msg = transport.recv::<InitExtension<NoExtension>>()?;
+    let (reply, init) = on_init(init_msg);
+    transport.send(reply);
+    Some(init.init)
+}
+
+/// Log initialization and return reply to it along with its payload.
+fn on_init<Ext>(
+    init_msg: Message<InitExtension<Ext>>,
+) -> (Message<InitRespone>, InitExtension<Ext>) {
+    let InitRequest::Init { node_id, node_ids } = &init_msg.body.payload.init;
+    log!(
+        Level::Info,
+        "initialized node {node_id} of {} nodes",
+        node_ids.len()
+    );
+    let reply = Message::reply(
+        init_msg.dst,
+        init_msg.src,
+        init_msg.body.id,
+        InitRespone::InitOk,
+    );
+    (reply, init_msg.body.payload)
+}
+
+/// Cluster of nodes in order sent by Maelstrom.
+///
+/// Every node receives the same `node_ids`,
+/// hence the order is agreed upon for ring and tree topologies.
+///
+/// # Example
+/// ```rust
+/// # use gossip_glomers::init::{Cluster, InitRequest};
+/// let cluster = Cluster::from(InitRequest::Init {
+///     node_id: "n2".into(),
+///     node_ids: vec!["n1".into(), "n2".into(), "n3".into()],
+/// });
+/// assert_eq!(cluster.node_index("n2"), 1);
+/// assert_eq!(cluster.successor("n3"), "n1");
+/// assert_eq!(cluster.predecessor("n1"), "n3");
+/// assert_eq!(cluster.children_in_binary_tree("n1"), ["n2", "n3"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    /// ID of current node.
+    pub node_id: String,
+    /// IDs of all nodes, including current node.
+    pub node_ids: Vec<String>,
+}
+
+impl From<InitRequest> for Cluster {
+    fn from(init_request: InitRequest) -> Self {
+        let InitRequest::Init { node_id, node_ids } = init_request;
+        Self { node_id, node_ids }
+    }
+}
+
+impl Cluster {
+    /// Index of node in cluster.
+    ///
+    /// # Panics
+    /// Panics if node is not part of cluster.
+    pub fn node_index(&self, id: &str) -> usize {
+        self.node_ids
+            .iter()
+            .position(|node| node == id)
+            .unwrap_or_else(|| panic!("node {id} is not part of cluster"))
+    }
+    /// Next node in ring, wrapping around to first node.
+    ///
+    /// # Panics
+    /// Panics if node is not part of cluster.
+    pub fn successor(&self, id: &str) -> &str {
+        let index = (self.node_index(id) + 1) % self.node_ids.len();
+        &self.node_ids[index]
+    }
+    /// Previous node in ring, wrapping around to last node.
+    ///
+    /// # Panics
+    /// Panics if node is not part of cluster.
+    pub fn predecessor(&self, id: &str) -> &str {
+        let count = self.node_ids.len();
+        let index = (self.node_index(id) + count - 1) % count;
+        &self.node_ids[index]
+    }
+    /// Children of node in binary tree rooted at first node.
+    ///
+    /// Node at index `i` has children at index `2i + 1` and `2i + 2`, if present.
+    ///
+    /// # Panics
+    /// Panics if node is not part of cluster.
+    pub fn children_in_binary_tree(&self, id: &str) -> Vec<&str> {
+        let index = self.node_index(id);
+        [2 * index + 1, 2 * index + 2]
+            .into_iter()
+            .filter_map(|child| self.node_ids.get(child).map(String::as_str))
+            .collect()
+    }
 }
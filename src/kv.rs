@@ -0,0 +1,468 @@
+//! Key Store Client Implementation.
+//!
+//! Available with `async` feature, see [runtime](crate::runtime).
+//! Describe [KvRequest] and [KvResponse] payloads of maelstrom key stores
+//! `seq-kv`, `lin-kv` and `lww-kv`.
+//! Provides [Kv] client with [Kv::update] to read, modify and compare and swap a key,
+//! and [Tso] client of `lin-tso` timestamp oracle.
+
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncWrite};
+
+use crate::{
+    derive_request, derive_response, log,
+    log::Level,
+    message::{Body, ErrorCode, Message},
+    runtime::Runtime,
+};
+
+derive_request!(
+    /// Reply payload from key store.
+    pub enum KvRequest {
+        /// Read ok request.
+        ///
+        /// This message acknowledge [KvResponse::Read].
+        /// ```json
+        /// {
+        ///     "type": "read_ok",
+        ///     "value": 10
+        /// }
+        /// ```
+        ReadOk {
+            /// Value of key.
+            value: Value,
+        },
+        /// Write ok request.
+        ///
+        /// This message acknowledge [KvResponse::Write].
+        /// ```json
+        /// { "type": "write_ok" }
+        /// ```
+        WriteOk,
+        /// Compare and swap ok request.
+        ///
+        /// This message acknowledge [KvResponse::Cas].
+        /// ```json
+        /// { "type": "cas_ok" }
+        /// ```
+        CasOk,
+        /// Error request.
+        ///
+        /// This message acknowledge error in operation.
+        /// ```json
+        /// {
+        ///     "type": "error"
+        ///     "code": 22,
+        ///     "text": "Some messsage"
+        /// }
+        /// ```
+        Error {
+            /// error code.
+            code: ErrorCode,
+            /// error message.
+            text: String,
+        },
+    }
+);
+
+derive_response!(
+    /// Request payload to key store.
+    pub enum KvResponse {
+        /// Read response.
+        ///
+        /// This message reads value of key.
+        /// ```json
+        /// {
+        ///     "type": "read",
+        ///     "key": "COUNTER"
+        /// }
+        /// ```
+        Read {
+            /// Key in store.
+            key: String,
+        },
+        /// Write response.
+        ///
+        /// This message writes value of key.
+        /// ```json
+        /// {
+        ///     "type": "write",
+        ///     "key": "COUNTER",
+        ///     "value": 20
+        /// }
+        /// ```
+        Write {
+            /// Key in store.
+            key: String,
+            /// Value to be written.
+            value: Value,
+        },
+        /// Compare and swap response.
+        ///
+        /// This message updates value of key if it is still the old value.
+        /// ```json
+        /// {
+        ///     "type": "cas",
+        ///     "key": "COUNTER",
+        ///     "from": 10,
+        ///     "to": 20,
+        ///     "create_if_not_exists": false
+        /// }
+        /// ```
+        Cas {
+            /// Key in store.
+            key: String,
+            /// Value to be updated from.
+            from: Value,
+            /// Value to be updated to.
+            to: Value,
+            /// Create key if not exists.
+            create_if_not_exists: bool,
+        },
+    }
+);
+
+/// Default time to wait for reply of key store.
+pub const KV_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Client of key store service.
+///
+/// Messages are sent with ids of the client,
+/// which do not collide with other messages as replies are matched by source too,
+/// see [Runtime::rpc].
+/// Missing reply is reported as [ErrorCode::Timeout].
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::kv::Kv;
+/// # use gossip_glomers::runtime::Runtime;
+/// # tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap().block_on(async {
+/// let input = r#"
+///     { "src": "seq-kv", "dest": "n1", "body": { "in_reply_to": 0, "type": "error", "code": 20, "text": "missing" } }
+///     { "src": "seq-kv", "dest": "n1", "body": { "in_reply_to": 1, "type": "cas_ok" } }
+/// "#.as_bytes();
+/// let mut output = Vec::new();
+/// let mut runtime = Runtime::new(input, &mut output);
+/// let mut kv = Kv::new("n1", "seq-kv");
+/// let value = kv.update(&mut runtime, "COUNTER", |value: Option<i64>| value.unwrap_or(0) + 5).await;
+/// assert_eq!(value.unwrap(), 5);
+/// drop(runtime);
+/// let output = String::from_utf8(output).unwrap();
+/// assert!(output.contains(r#""type":"cas","key":"COUNTER","from":null,"to":5,"create_if_not_exists":true"#), "{output}");
+/// # });
+/// ```
+pub struct Kv {
+    /// Node id of client.
+    node: String,
+    /// Node id of key store.
+    service: String,
+    /// Message id counter.
+    id: usize,
+    /// Time to wait for reply.
+    timeout: Duration,
+}
+
+impl Kv {
+    /// Create client of key store service for node.
+    pub fn new(node: &str, service: &str) -> Self {
+        Self {
+            node: node.to_string(),
+            service: service.to_string(),
+            id: 0,
+            timeout: KV_TIMEOUT,
+        }
+    }
+
+    /// Change time to wait for reply, defaults to [KV_TIMEOUT].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send payload to key store and wait for reply.
+    ///
+    /// Error replies are returned as error.
+    async fn call<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin>(
+        &mut self,
+        runtime: &mut Runtime<R, W>,
+        payload: KvResponse,
+    ) -> Result<KvRequest, ErrorCode> {
+        let message = Message {
+            src: self.node.clone(),
+            dst: self.service.clone(),
+            body: Body {
+                id: Some(self.id),
+                reply_id: None,
+                payload,
+            },
+        };
+        self.id += 1;
+        let reply = runtime
+            .rpc::<_, KvRequest>(message, self.timeout)
+            .await
+            .ok_or(ErrorCode::Timeout)?;
+        match reply.body.payload {
+            KvRequest::Error { code, .. } => Err(code),
+            payload => Ok(payload),
+        }
+    }
+
+    /// Read value of key, [None] if key does not exist.
+    async fn read_value<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin>(
+        &mut self,
+        runtime: &mut Runtime<R, W>,
+        key: &str,
+    ) -> Result<Option<Value>, ErrorCode> {
+        let read = KvResponse::Read { key: key.into() };
+        match self.call(runtime, read).await {
+            Ok(KvRequest::ReadOk { value }) => Ok(Some(value)),
+            Ok(reply) => Err(unexpected(reply)),
+            Err(ErrorCode::KeyDoesNotExist) => Ok(None),
+            Err(code) => Err(code),
+        }
+    }
+
+    /// Read value of key, [None] if key does not exist.
+    pub async fn read<T: DeserializeOwned, R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin>(
+        &mut self,
+        runtime: &mut Runtime<R, W>,
+        key: &str,
+    ) -> Result<Option<T>, ErrorCode> {
+        self.read_value(runtime, key)
+            .await?
+            .map(from_value)
+            .transpose()
+    }
+
+    /// Write value of key.
+    pub async fn write<T: Serialize, R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin>(
+        &mut self,
+        runtime: &mut Runtime<R, W>,
+        key: &str,
+        value: &T,
+    ) -> Result<(), ErrorCode> {
+        let write = KvResponse::Write {
+            key: key.into(),
+            value: to_value(value),
+        };
+        match self.call(runtime, write).await? {
+            KvRequest::WriteOk => Ok(()),
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    /// Compare and swap value of key.
+    ///
+    /// Key is created with value if it does not exist and `from` is [None].
+    pub async fn cas<T: Serialize, R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin>(
+        &mut self,
+        runtime: &mut Runtime<R, W>,
+        key: &str,
+        from: Option<&T>,
+        to: &T,
+    ) -> Result<(), ErrorCode> {
+        let cas = KvResponse::Cas {
+            key: key.into(),
+            create_if_not_exists: from.is_none(),
+            from: from.map_or(Value::Null, to_value),
+            to: to_value(to),
+        };
+        match self.call(runtime, cas).await? {
+            KvRequest::CasOk => Ok(()),
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    /// Update value of key using function of current value.
+    ///
+    /// Reads current value, [None] if key does not exist,
+    /// and compare and swaps it with value from function,
+    /// creating the key if it does not exist.
+    /// Retries with fresh value while compare and swap fails with
+    /// [PreconditionFailed](ErrorCode::PreconditionFailed) or
+    /// [KeyDoesNotExist](ErrorCode::KeyDoesNotExist), hence updates are not lost.
+    ///
+    /// # Returns
+    /// Value committed, or error code of the failed read or compare and swap.
+    pub async fn update<T, F, R, W>(
+        &mut self,
+        runtime: &mut Runtime<R, W>,
+        key: &str,
+        f: F,
+    ) -> Result<T, ErrorCode>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Fn(Option<T>) -> T,
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        loop {
+            let current = self.read_value(runtime, key).await?;
+            let value = f(current.clone().map(from_value).transpose()?);
+            let cas = KvResponse::Cas {
+                key: key.into(),
+                create_if_not_exists: current.is_none(),
+                from: current.unwrap_or(Value::Null),
+                to: to_value(&value),
+            };
+            match self.call(runtime, cas).await {
+                Ok(KvRequest::CasOk) => return Ok(value),
+                Ok(reply) => return Err(unexpected(reply)),
+                Err(code @ (ErrorCode::PreconditionFailed | ErrorCode::KeyDoesNotExist)) => {
+                    log!(Level::Debug, "retrying update of {key} after {code:?}");
+                }
+                Err(code) => return Err(code),
+            }
+        }
+    }
+}
+
+derive_request!(
+    /// Reply payload from timestamp oracle.
+    pub enum TsoRequest {
+        /// Timestamp ok request.
+        ///
+        /// This message acknowledge [TsoResponse::Ts].
+        /// ```json
+        /// {
+        ///     "type": "ts_ok",
+        ///     "ts": 10
+        /// }
+        /// ```
+        TsOk {
+            /// Timestamp.
+            ts: usize,
+        },
+        /// Error request.
+        ///
+        /// This message acknowledge error in operation.
+        /// ```json
+        /// {
+        ///     "type": "error"
+        ///     "code": 11,
+        ///     "text": "Some messsage"
+        /// }
+        /// ```
+        Error {
+            /// error code.
+            code: ErrorCode,
+            /// error message.
+            text: String,
+        },
+    }
+);
+
+derive_response!(
+    /// Request payload to timestamp oracle.
+    pub enum TsoResponse {
+        /// Timestamp response.
+        ///
+        /// This message requests next timestamp.
+        /// ```json
+        /// { "type": "ts" }
+        /// ```
+        Ts,
+    }
+);
+
+/// Node id of timestamp oracle.
+pub const TSO_NODE: &str = "lin-tso";
+
+/// Client of `lin-tso` timestamp oracle.
+///
+/// The oracle is only available in workloads which enable it,
+/// e.g. `txn-list-append` and `txn-rw-register`,
+/// otherwise requests are reported as [ErrorCode::Timeout].
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::kv::Tso;
+/// # use gossip_glomers::runtime::Runtime;
+/// # tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap().block_on(async {
+/// let input = r#"
+///     { "src": "lin-tso", "dest": "n1", "body": { "in_reply_to": 0, "type": "ts_ok", "ts": 7 } }
+/// "#.as_bytes();
+/// let mut output = Vec::new();
+/// let mut runtime = Runtime::new(input, &mut output);
+/// assert_eq!(Tso::new("n1").next(&mut runtime).await.unwrap(), 7);
+/// # });
+/// ```
+pub struct Tso {
+    /// Node id of client.
+    node: String,
+    /// Message id counter.
+    id: usize,
+    /// Time to wait for reply.
+    timeout: Duration,
+}
+
+impl Tso {
+    /// Create client of timestamp oracle for node.
+    pub fn new(node: &str) -> Self {
+        Self {
+            node: node.to_string(),
+            id: 0,
+            timeout: KV_TIMEOUT,
+        }
+    }
+
+    /// Change time to wait for reply, defaults to [KV_TIMEOUT].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Request next timestamp, which is greater than every timestamp given before.
+    pub async fn next<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin>(
+        &mut self,
+        runtime: &mut Runtime<R, W>,
+    ) -> Result<usize, ErrorCode> {
+        let message = Message {
+            src: self.node.clone(),
+            dst: TSO_NODE.to_string(),
+            body: Body {
+                id: Some(self.id),
+                reply_id: None,
+                payload: TsoResponse::Ts,
+            },
+        };
+        self.id += 1;
+        let reply = runtime
+            .rpc::<_, TsoRequest>(message, self.timeout)
+            .await
+            .ok_or(ErrorCode::Timeout)?;
+        match reply.body.payload {
+            TsoRequest::TsOk { ts } => Ok(ts),
+            TsoRequest::Error { code, .. } => Err(code),
+        }
+    }
+}
+
+/// Serialize value sent to key store.
+///
+/// # Panics
+///
+/// Panics if value cannot be serialized.
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value)
+        .unwrap_or_else(|_| panic!("serialize value of {} failed", std::any::type_name::<T>()))
+}
+
+/// Deserialize value read from key store.
+fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, ErrorCode> {
+    serde_json::from_value(value).map_err(|e| {
+        log!(Level::Error, "failed to de-serialize value: {e}");
+        ErrorCode::MalformedRequest
+    })
+}
+
+/// Error for reply not acknowledging the request.
+fn unexpected(reply: KvRequest) -> ErrorCode {
+    log!(Level::Error, "unexpected reply {reply:?}");
+    ErrorCode::MalformedRequest
+}
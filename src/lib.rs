@@ -1,5 +1,20 @@
 #![warn(missing_docs)]
 #![doc = include_str!("../README.md")]
 
+pub mod broadcast;
+pub mod config;
+pub mod counter;
+pub mod echo;
+pub mod event;
 pub mod init;
+#[cfg(feature = "async")]
+pub mod kv;
+pub mod log;
 pub mod message;
+pub mod node;
+pub mod random;
+#[cfg(feature = "async")]
+pub mod runtime;
+pub mod tee;
+pub mod transport;
+pub mod unique_ids;
@@ -0,0 +1,163 @@
+//! Logging Implementation.
+//!
+//! Describe [Logger] which writes leveled messages to standard error.
+//! Maelstrom captures standard error of every node separately.
+//! Provides [exit_on_warnings] to fail node which logged warnings in strict mode.
+
+use std::{
+    fmt::Arguments,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
+};
+
+/// Level of log message, ordered from most to least severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Failures of node.
+    Error,
+    /// Unexpected but handled conditions.
+    Warn,
+    /// Node life cycle.
+    Info,
+    /// Node state on every tick.
+    Debug,
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        match level.to_ascii_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            _ => Err(format!("unknown log level {level}")),
+        }
+    }
+}
+
+/// Logger suppressing messages less severe than its level.
+///
+/// # Example
+/// ```rust
+/// # use gossip_glomers::log::{Level, Logger};
+/// let logger = Logger::new(Some(Level::Info));
+/// let mut writer = Vec::new();
+/// logger.write(&mut writer, Level::Info, format_args!("node {} initialized", "n1"));
+/// logger.write(&mut writer, Level::Debug, format_args!("suppressed"));
+/// assert_eq!(String::from_utf8_lossy(&writer), "[Info] node n1 initialized\n");
+/// assert_eq!(logger.warnings(), 0);
+/// logger.write(&mut writer, Level::Warn, format_args!("dead letter"));
+/// assert_eq!(logger.warnings(), 1);
+/// assert!(!logger.failed());
+/// let strict = Logger::new(None).strict(true);
+/// assert!(!strict.failed());
+/// strict.write(&mut writer, Level::Error, format_args!("suppressed, but counted"));
+/// assert!(strict.failed());
+/// ```
+#[derive(Debug)]
+pub struct Logger {
+    level: Option<Level>,
+    /// Number of warnings and errors written, even if suppressed.
+    warnings: AtomicUsize,
+    /// Whether warnings fail the node, see [Self::failed].
+    strict: bool,
+}
+
+impl Logger {
+    /// Create logger with level, `None` suppresses all messages.
+    pub fn new(level: Option<Level>) -> Self {
+        Self {
+            level,
+            warnings: AtomicUsize::new(0),
+            strict: false,
+        }
+    }
+
+    /// Fail node which logged warnings, see [Self::failed].
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Create logger with level from `LOG_LEVEL`, defaults to [Level::Warn].
+    ///
+    /// `LOG_LEVEL=off` suppresses all messages.
+    /// `GG_STRICT_WARNINGS=1` makes logger [strict](Self::strict).
+    pub fn from_env() -> Self {
+        let logger = match std::env::var("LOG_LEVEL").as_deref() {
+            Ok("off") => Self::new(None),
+            Ok(level) => Self::new(Some(level.parse().unwrap_or(Level::Warn))),
+            Err(_) => Self::new(Some(Level::Warn)),
+        };
+        logger.strict(matches!(
+            std::env::var("GG_STRICT_WARNINGS").as_deref(),
+            Ok("1" | "true")
+        ))
+    }
+
+    /// Number of warnings and errors written so far, including suppressed ones.
+    pub fn warnings(&self) -> usize {
+        self.warnings.load(Ordering::Relaxed)
+    }
+
+    /// Whether logger is strict and some warning or error was written.
+    pub fn failed(&self) -> bool {
+        self.strict && self.warnings() > 0
+    }
+
+    /// Whether messages of level are logged.
+    pub fn enabled(&self, level: Level) -> bool {
+        self.level.is_some_and(|max| level <= max)
+    }
+
+    /// Write message to writer if level is enabled.
+    ///
+    /// Warnings and errors are counted even if not enabled.
+    /// Failure to write is ignored, as logs are best effort.
+    pub fn write<W: std::io::Write>(&self, writer: &mut W, level: Level, message: Arguments) {
+        if level <= Level::Warn {
+            self.warnings.fetch_add(1, Ordering::Relaxed);
+        }
+        if self.enabled(level) {
+            let _ = writeln!(writer, "[{level:?}] {message}");
+        }
+    }
+}
+
+/// Global logger created from environment on first use.
+pub fn logger() -> &'static Logger {
+    static LOGGER: OnceLock<Logger> = OnceLock::new();
+    LOGGER.get_or_init(Logger::from_env)
+}
+
+/// Exit process with code `1` if global [logger] [failed](Logger::failed).
+///
+/// Called by nodes at shutdown, hence `GG_STRICT_WARNINGS=1` turns logged warnings
+/// into failure of the run.
+pub fn exit_on_warnings() {
+    let logger = logger();
+    if logger.failed() {
+        eprintln!("exiting with {} warnings in strict mode", logger.warnings());
+        std::process::exit(1);
+    }
+}
+
+/// Logs formatted message to standard error using global [logger].
+///
+/// # Example
+/// ```rust
+/// # use gossip_glomers::log;
+/// # use gossip_glomers::log::Level;
+/// log!(Level::Debug, "gossip {} messages to {}", 3, "n2");
+/// ```
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)+) => {
+        $crate::log::logger().write(&mut ::std::io::stderr(), $level, format_args!($($arg)+))
+    };
+}
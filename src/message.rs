@@ -7,14 +7,19 @@ use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_repr::Deserialize_repr;
 
+use crate::{log, log::Level};
+
 /// Generic Message Structure.
+///
+/// Node ids are owned by default,
+/// borrowed ids (`&str`) allow sending without allocating them per message.
 #[derive(Serialize, Deserialize, Debug)]
-pub struct Message<Payload> {
+pub struct Message<Payload, Id = String> {
     /// Source node name.
-    pub src: String,
+    pub src: Id,
     #[serde(rename = "dest")]
     /// Destination node name.
-    pub dst: String,
+    pub dst: Id,
     /// Message Body.
     pub body: Body<Payload>,
 }
@@ -39,22 +44,160 @@ pub trait Response {}
 /// Request trait to allow receive of messages.
 pub trait Request {}
 
-impl<Payload: Serialize + Response> Message<Payload> {
+/// Rpc trait to match outgoing payload with the payload acknowledging it.
+///
+/// Use [derive_rpc](crate::derive_rpc) to implement.
+pub trait Rpc<Reply> {
+    /// Check whether reply acknowledges the outgoing payload.
+    fn is_reply(&self, reply: &Reply) -> bool;
+}
+
+impl<Payload, Id> Message<Payload, Id> {
+    /// Message which expects no reply, hence has neither id nor reply id.
+    ///
+    /// Id is needed only by messages expecting reply, see [Self::request],
+    /// hence notifying does not take an id from the id counter of node.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use gossip_glomers::message::Message;
+    /// let message = Message::notify("n1", "n2", ());
+    /// assert_eq!((message.src, message.dst), ("n1", "n2"));
+    /// assert_eq!((message.body.id, message.body.reply_id), (None, None));
+    /// ```
+    pub fn notify(src: Id, dst: Id, payload: Payload) -> Self {
+        Self {
+            src,
+            dst,
+            body: Body {
+                id: None,
+                reply_id: None,
+                payload,
+            },
+        }
+    }
+
+    /// Message expecting reply to id.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use gossip_glomers::message::Message;
+    /// let message = Message::request("n1", "lin-kv", 3, ());
+    /// assert_eq!((message.src, message.dst), ("n1", "lin-kv"));
+    /// assert_eq!((message.body.id, message.body.reply_id), (Some(3), None));
+    /// ```
+    pub fn request(src: Id, dst: Id, id: usize, payload: Payload) -> Self {
+        Self::notify(src, dst, payload).with_id(id)
+    }
+
+    /// Message replying to message id, if the replied message had one.
+    ///
+    /// Use [Self::with_id] to give reply an id of its own.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use gossip_glomers::message::Message;
+    /// let message = Message::reply("n1", "c1", Some(7), ());
+    /// assert_eq!((message.src, message.dst), ("n1", "c1"));
+    /// assert_eq!((message.body.id, message.body.reply_id), (None, Some(7)));
+    /// let message = message.with_id(2);
+    /// assert_eq!((message.body.id, message.body.reply_id), (Some(2), Some(7)));
+    /// ```
+    pub fn reply(src: Id, dst: Id, in_reply_to: Option<usize>, payload: Payload) -> Self {
+        let mut message = Self::notify(src, dst, payload);
+        message.body.reply_id = in_reply_to;
+        message
+    }
+
+    /// Message with id.
+    pub fn with_id(mut self, id: usize) -> Self {
+        self.body.id = Some(id);
+        self
+    }
+}
+
+impl<Payload: Serialize + Response, Id: Serialize> Message<Payload, Id> {
     /// Sends serialized message by writing to writer.
     ///
+    /// Message and its terminating new line are written at once,
+    /// then writer is flushed,
+    /// hence buffered writer never holds back a complete message.
+    /// Use [Writer] to reuse the serialization buffer across messages.
+    ///
     /// # Panics
     ///
     /// Panics if writing to writer fails.
     pub fn send<W: std::io::Write>(self, writer: &mut W) {
-        serde_json::to_writer(&mut *writer, &self).unwrap_or_else(|_| {
+        self.send_with_buffer(writer, &mut Vec::new());
+    }
+
+    /// Sends message serialized into buffer by writing to writer.
+    fn send_with_buffer<W: std::io::Write>(&self, writer: &mut W, buffer: &mut Vec<u8>) {
+        buffer.clear();
+        serde_json::to_writer(&mut *buffer, self).unwrap_or_else(|_| {
             panic!(
                 "serialize response to {} failed",
                 std::any::type_name::<Payload>(),
             )
         });
-        writer
-            .write_all("\n".as_bytes())
-            .expect("failed to send new line");
+        buffer.push(b'\n');
+        writer.write_all(buffer).expect("failed to send message");
+        writer.flush().expect("failed to flush message");
+    }
+}
+
+/// Writer sending messages using a reused serialization buffer.
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::message::{Body, Message, Writer};
+/// # use gossip_glomers::init::InitRespone;
+/// let message = || Message {
+///     src: "n1".to_string(),
+///     dst: "c1".to_string(),
+///     body: Body {
+///         id: None,
+///         reply_id: Some(1),
+///         payload: InitRespone::InitOk,
+///     },
+/// };
+/// let mut expected = Vec::new();
+/// message().send(&mut expected);
+/// let mut writer = Writer::new(Vec::new());
+/// writer.send(message());
+/// assert_eq!(writer.into_inner(), expected);
+/// ```
+pub struct Writer<W> {
+    /// Underlying writer.
+    writer: W,
+    /// Serialization buffer.
+    buffer: Vec<u8>,
+}
+
+impl<W: std::io::Write> Writer<W> {
+    /// Create message writer over writer.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Sends serialized message, see [Message::send].
+    pub fn send<Payload: Serialize + Response, Id: Serialize>(
+        &mut self,
+        message: Message<Payload, Id>,
+    ) {
+        message.send_with_buffer(&mut self.writer, &mut self.buffer);
+    }
+
+    /// Get underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
     }
 }
 
@@ -76,12 +219,87 @@ impl<Payload: DeserializeOwned + Request> Message<Payload> {
     }
 }
 
+/// First message id of node.
+///
+/// Id is `MSG_ID_START` if set, for deterministic output,
+/// else [first_id_at] current time.
+pub fn first_id() -> usize {
+    std::env::var("MSG_ID_START")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or_else(|| {
+            first_id_at(
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .expect("clock is after unix epoch"),
+            )
+        })
+}
+
+/// First message id of node started at time since unix epoch.
+///
+/// Id is nanoseconds since unix epoch.
+/// As node sends far less than a message per nanosecond,
+/// ids of node restarted later do not overlap ids sent before restart.
+///
+/// Nanoseconds wrap around on targets where `usize` is narrower than 64 bits,
+/// hence there ids of restarted node are only unlikely to overlap.
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::message::first_id_at;
+/// # use std::time::Duration;
+/// assert_eq!(first_id_at(Duration::new(1, 5)), 1_000_000_005);
+/// assert_eq!(first_id_at(Duration::from_millis(2)), 2_000_000);
+/// ```
+pub fn first_id_at(since_epoch: std::time::Duration) -> usize {
+    (since_epoch.as_nanos() % (usize::MAX as u128 + 1)) as usize
+}
+
+/// Receives de-serialized messages from reader, one message per line.
+///
+/// Lines which fail to de-serialize are logged and skipped,
+/// reading stops when reader fails or ends.
+/// Each line is read completely before de-serializing,
+/// hence message split across reads of reader is reassembled.
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::init::InitRequest;
+/// # use gossip_glomers::message::receive;
+/// let input = r#"
+///     { "src": "c1", "dest": "n1", "body": { "type": "init" } }
+///     { "src": "c1", "dest": "n1", "body": { "type": "init", "node_id": "n1", "node_ids": [] } }
+/// "#.as_bytes();
+/// let messages = receive::<InitRequest, _>(input).collect::<Vec<_>>();
+/// assert_eq!(messages.len(), 1);
+/// ```
+pub fn receive<Payload: DeserializeOwned + Request, R: std::io::BufRead>(
+    reader: R,
+) -> impl Iterator<Item = Message<Payload>> {
+    reader
+        .lines()
+        .map_while(|line| {
+            line.map_err(|e| log!(Level::Error, "failed to read input: {e}"))
+                .ok()
+        })
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            serde_json::from_str(&line)
+                .map_err(|e| log!(Level::Error, "failed to de-serialize {line}: {e}"))
+                .ok()
+        })
+}
+
 /// Derives trait for request `enum`.
 ///
 /// The traits derived are:
 /// * [::serde::Deserialize]
 ///     * uses tag as `type`.
-///     * uses `snake_case` for de-serialize
+///     * uses `snake_case` for de-serialize, unless prefixed with `rename = "<style>",`
+///     * ignores unknown fields, unless prefixed with `strict;`
 /// * [Request]: allows receive Message with request payload.
 /// * [Debug]
 ///
@@ -114,15 +332,151 @@ impl<Payload: DeserializeOwned + Request> Message<Payload> {
 /// assert_eq!(output.body.reply_id, None, "{:?}", output);
 /// assert_eq!(output.body.payload, PingRequest::Ping, "{:?}", output);
 /// ```
+///
+/// Prefix with `unknown;` to add `Unknown` variant for unrecognized types,
+/// which allows node to reply with [ErrorCode::NodeSupported] instead of failing.
+///
+/// ```rust
+/// # use gossip_glomers::derive_request;
+/// # use gossip_glomers::message::Message;
+/// derive_request!{
+///   unknown;
+///   pub enum PingRequest {
+///     Ping
+///   }
+/// }
+/// let input = r#"
+///     {
+///         "src": "src",
+///         "dest": "dst",
+///         "body": {
+///             "msg_id": 1,
+///             "type": "pong"
+///         }
+///     }
+/// "#.as_bytes();
+/// let mut deserializer = serde_json::Deserializer::from_reader(input);
+/// let output = Message::<PingRequest>::recv(&mut deserializer);
+/// match output.body.payload {
+///     PingRequest::Ping => panic!("pong is not ping"),
+///     PingRequest::Unknown => {}
+/// }
+/// ```
+///
+/// Prefix with `rename = "<style>",` to use other [serde rename style](https://serde.rs/container-attrs.html#rename_all).
+///
+/// ```rust
+/// # use gossip_glomers::derive_request;
+/// # use gossip_glomers::message::Message;
+/// derive_request!{
+///   rename = "kebab-case",
+///   #[derive(PartialEq)]
+///   pub enum KeyRequest {
+///     ReadKey
+///   }
+/// }
+/// let input = r#"
+///     {
+///         "src": "src",
+///         "dest": "dst",
+///         "body": {
+///             "msg_id": 1,
+///             "type": "read-key"
+///         }
+///     }
+/// "#.as_bytes();
+/// let mut deserializer = serde_json::Deserializer::from_reader(input);
+/// let output = Message::<KeyRequest>::recv(&mut deserializer);
+/// assert_eq!(output.body.payload, KeyRequest::ReadKey, "{:?}", output);
+/// ```
+///
+/// Prefix with `strict;`, after `rename = "<style>",` if any,
+/// to reject requests with unexpected fields instead of ignoring them,
+/// which catches protocol bugs during development.
+/// Strict requests can neither have [flattened](https://serde.rs/field-attrs.html#flatten) fields,
+/// as serde does not support `deny_unknown_fields` along with `flatten`,
+/// nor be combined with `unknown;`, as unrecognized types may have any field.
+///
+/// ```rust
+/// # use gossip_glomers::derive_request;
+/// # use gossip_glomers::message::Message;
+/// derive_request!{
+///   strict;
+///   pub enum StrictRequest {
+///     Add { delta: i64 }
+///   }
+/// }
+/// derive_request!{
+///   pub enum LenientRequest {
+///     Add { delta: i64 }
+///   }
+/// }
+/// let input = r#"
+///     {
+///         "src": "src",
+///         "dest": "dst",
+///         "body": {
+///             "msg_id": 1,
+///             "type": "add",
+///             "delta": 2,
+///             "detla": 3
+///         }
+///     }
+/// "#;
+/// let error = serde_json::from_str::<Message<StrictRequest>>(input).unwrap_err();
+/// assert!(error.to_string().contains("unknown field `detla`"), "{error}");
+/// let output = serde_json::from_str::<Message<LenientRequest>>(input).unwrap();
+/// assert!(matches!(output.body.payload, LenientRequest::Add { delta: 2 }));
+/// ```
 #[macro_export]
 macro_rules! derive_request {
-    ($(#[$meta:meta])* $vis:vis enum $name:ident $body:tt ) => {
+    (
+        rename = $style:literal,
+        unknown;
+        $(#[$meta:meta])* $vis:vis enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident $({ $($fields:tt)* })? $(( $($items:tt)* ))?
+            ),* $(,)?
+        }
+    ) => {
+        $crate::derive_request!(
+            rename = $style,
+            $(#[$meta])*
+            $vis enum $name {
+                $(
+                    $(#[$variant_meta])*
+                    $variant $({ $($fields)* })? $(( $($items)* ))?,
+                )*
+                /// Request of unrecognized type.
+                #[serde(other)]
+                Unknown,
+            }
+        );
+    };
+    (rename = $style:literal, strict; $(#[$meta:meta])* $vis:vis enum $name:ident $body:tt ) => {
         $(#[$meta])*
         #[derive(::serde::Deserialize, Debug)]
-        #[serde(tag = "type", rename_all = "snake_case")]
+        #[serde(tag = "type", rename_all = $style, deny_unknown_fields)]
         $vis enum $name $body
         impl $crate::message::Request for $name {}
     };
+    (rename = $style:literal, $(#[$meta:meta])* $vis:vis enum $name:ident $body:tt ) => {
+        $(#[$meta])*
+        #[derive(::serde::Deserialize, Debug)]
+        #[serde(tag = "type", rename_all = $style)]
+        $vis enum $name $body
+        impl $crate::message::Request for $name {}
+    };
+    (unknown; $($rest:tt)*) => {
+        $crate::derive_request!(rename = "snake_case", unknown; $($rest)*);
+    };
+    (strict; $($rest:tt)*) => {
+        $crate::derive_request!(rename = "snake_case", strict; $($rest)*);
+    };
+    ($(#[$meta:meta])* $vis:vis enum $name:ident $body:tt ) => {
+        $crate::derive_request!(rename = "snake_case", $(#[$meta])* $vis enum $name $body);
+    };
 }
 
 /// Derives trait for response `enum`.
@@ -130,9 +484,12 @@ macro_rules! derive_request {
 /// The traits derived are:
 /// * [`::serde::Serialize`]
 ///     * uses tag as `type`.
-///     * uses `snake_case` for serialize
+///     * uses `snake_case` for serialize, unless prefixed with `rename = "<style>",`
 /// * [Response]: allows send Message with response payload.
 /// * [Debug]
+/// * [Clone]: allows resending response, hence all fields must be [Clone].
+///
+/// Other traits like [PartialEq] can be derived by adding `#[derive(..)]` on the `enum`.
 ///
 /// # Example
 ///
@@ -173,13 +530,63 @@ macro_rules! derive_request {
 /// ```
 #[macro_export]
 macro_rules! derive_response {
-    ($(#[$meta:meta])* $vis:vis enum $name:ident $body:tt ) => {
+    (rename = $style:literal, $(#[$meta:meta])* $vis:vis enum $name:ident $body:tt ) => {
         $(#[$meta])*
-        #[derive(::serde::Serialize, Debug)]
-        #[serde(tag = "type", rename_all = "snake_case")]
+        #[derive(::serde::Serialize, Debug, Clone)]
+        #[serde(tag = "type", rename_all = $style)]
         $vis enum $name $body
         impl $crate::message::Response for $name {}
     };
+    ($(#[$meta:meta])* $vis:vis enum $name:ident $body:tt ) => {
+        $crate::derive_response!(rename = "snake_case", $(#[$meta])* $vis enum $name $body);
+    };
+}
+
+/// Derives [Rpc] for outgoing response `enum` with incoming request `enum` as reply.
+///
+/// Each outgoing variant is mapped to the variant acknowledging it,
+/// unmapped variants expect no reply.
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::{derive_request, derive_response, derive_rpc};
+/// # use gossip_glomers::message::Rpc;
+/// derive_response!{
+///   pub enum KvResponse {
+///     Read { key: String },
+///     Write { key: String, value: usize },
+///   }
+/// }
+/// derive_request!{
+///   pub enum KvRequest {
+///     ReadOk { value: usize },
+///     WriteOk,
+///   }
+/// }
+/// derive_rpc!{
+///   KvResponse => KvRequest {
+///     Read => ReadOk,
+///     Write => WriteOk,
+///   }
+/// }
+/// let read = KvResponse::Read { key: "key".into() };
+/// assert!(read.is_reply(&KvRequest::ReadOk { value: 1 }));
+/// assert!(!read.is_reply(&KvRequest::WriteOk));
+/// ```
+#[macro_export]
+macro_rules! derive_rpc {
+    ($name:ident => $reply:ident { $($variant:ident => $reply_variant:ident),* $(,)? }) => {
+        impl $crate::message::Rpc<$reply> for $name {
+            fn is_reply(&self, reply: &$reply) -> bool {
+                match self {
+                    $($name::$variant { .. } => matches!(reply, $reply::$reply_variant { .. }),)*
+                    #[allow(unreachable_patterns)]
+                    _ => false,
+                }
+            }
+        }
+    };
 }
 
 /// Error code when using services.
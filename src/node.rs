@@ -0,0 +1,129 @@
+//! Node Dispatch Implementation.
+//!
+//! Describe [Node] which routes messages to handlers by message type.
+
+use std::collections::HashMap;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::message::{Body, Message, Request, Response};
+
+/// Raw json payload can be received.
+impl Request for Value {}
+
+/// Raw json payload can be sent.
+impl Response for Value {}
+
+/// Handler type erased over json payload.
+type Handler = Box<dyn Fn(Message<Value>) -> Option<Message<Value>>>;
+
+/// Node with handlers registered per message type.
+///
+/// Alternative to a single `match` over request `enum`,
+/// allows composing handlers for nodes with many message types.
+///
+/// # Example
+/// ```rust
+/// # use gossip_glomers::{derive_request, derive_response};
+/// # use gossip_glomers::message::{Body, Message};
+/// # use gossip_glomers::node::Node;
+/// derive_request! {
+///     pub enum PingRequest {
+///         Ping
+///     }
+/// }
+/// derive_response! {
+///     pub enum PingResponse {
+///         Pong
+///     }
+/// }
+/// let mut node = Node::default();
+/// node.register("ping", |request: Message<PingRequest>| {
+///     Some(Message {
+///         src: request.dst,
+///         dst: request.src,
+///         body: Body {
+///             id: None,
+///             reply_id: request.body.id,
+///             payload: PingResponse::Pong,
+///         },
+///     })
+/// });
+/// let input = r#"{"src": "c1", "dest": "n1", "body": {"msg_id": 1, "type": "ping"}}"#;
+/// let request = serde_json::from_str(input).unwrap();
+/// let response = node.on_message(request).expect("ping is registered");
+/// assert_eq!(response.body.payload["type"], "pong");
+/// ```
+#[derive(Default)]
+pub struct Node {
+    handlers: HashMap<&'static str, Handler>,
+}
+
+impl Node {
+    /// Registers handler for message with given type.
+    ///
+    /// Handler receives message with payload parsed as `Req`
+    /// and optionally returns response message.
+    /// Registering same type again replaces the handler.
+    pub fn register<Req, Res, F>(&mut self, type_name: &'static str, handler: F) -> &mut Self
+    where
+        Req: DeserializeOwned + Request,
+        Res: Serialize + Response,
+        F: Fn(Message<Req>) -> Option<Message<Res>> + 'static,
+    {
+        let handler = move |message: Message<Value>| {
+            let payload = serde_json::from_value(message.body.payload).unwrap_or_else(|_| {
+                panic!(
+                    "de-serialize request to {} failed",
+                    std::any::type_name::<Req>(),
+                )
+            });
+            let response = handler(Message {
+                src: message.src,
+                dst: message.dst,
+                body: Body {
+                    id: message.body.id,
+                    reply_id: message.body.reply_id,
+                    payload,
+                },
+            })?;
+            let payload = serde_json::to_value(response.body.payload).unwrap_or_else(|_| {
+                panic!(
+                    "serialize response to {} failed",
+                    std::any::type_name::<Res>(),
+                )
+            });
+            Some(Message {
+                src: response.src,
+                dst: response.dst,
+                body: Body {
+                    id: response.body.id,
+                    reply_id: response.body.reply_id,
+                    payload,
+                },
+            })
+        };
+        self.handlers.insert(type_name, Box::new(handler));
+        self
+    }
+
+    /// Routes message to handler registered for its type.
+    ///
+    /// # Returns
+    /// Response from handler,
+    /// `None` if handler has no response or no handler is registered for the type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if payload does not match the handler request type.
+    pub fn on_message(&self, message: Message<Value>) -> Option<Message<Value>> {
+        let handler = message
+            .body
+            .payload
+            .get("type")
+            .and_then(Value::as_str)
+            .and_then(|type_name| self.handlers.get(type_name))?;
+        handler(message)
+    }
+}
@@ -0,0 +1,99 @@
+//! Random Number Generator Implementation.
+//!
+//! Describe seedable [Rng] so randomized choices of nodes can be replayed.
+
+use std::hash::{BuildHasher, Hasher};
+
+use crate::{log, log::Level};
+
+/// Seedable pseudo random number generator.
+///
+/// Uses SplitMix64, which is small and good enough for choosing peers.
+///
+/// # Example
+/// ```rust
+/// # use gossip_glomers::random::Rng;
+/// let peers = ["n1", "n2", "n3", "n4", "n5"];
+/// let (mut first, mut second) = (Rng::new(42), Rng::new(42));
+/// assert_eq!(first.sample(&peers, 2), second.sample(&peers, 2));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create generator from seed.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Create generator with seed from environment variable.
+    ///
+    /// If variable is not set then seed is chosen from entropy and logged,
+    /// so that the run can be replayed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if variable is not a valid seed.
+    pub fn from_env(key: &str) -> Self {
+        let seed = match std::env::var(key) {
+            Ok(seed) => seed
+                .parse()
+                .unwrap_or_else(|_| panic!("{key} should be a number, found {seed}")),
+            Err(_) => {
+                let seed = Self::entropy();
+                log!(
+                    Level::Warn,
+                    "seeded from entropy, set {key}={seed} to replay"
+                );
+                seed
+            }
+        };
+        Self::new(seed)
+    }
+
+    /// Create generator with seed from entropy.
+    pub fn from_entropy() -> Self {
+        Self::new(Self::entropy())
+    }
+
+    /// Seed from entropy.
+    fn entropy() -> u64 {
+        std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish()
+    }
+
+    /// Next random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Random number below bound.
+    ///
+    /// # Panics
+    ///
+    /// Panics if bound is zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Choose `amount` distinct items in random order.
+    ///
+    /// All items are chosen if there are fewer than `amount`.
+    pub fn sample<'a, T>(&mut self, items: &'a [T], amount: usize) -> Vec<&'a T> {
+        let mut items = items.iter().collect::<Vec<_>>();
+        let amount = amount.min(items.len());
+        for index in 0..amount {
+            let chosen = index + self.below(items.len() - index);
+            items.swap(index, chosen);
+        }
+        items.truncate(amount);
+        items
+    }
+}
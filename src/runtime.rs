@@ -0,0 +1,221 @@
+//! Async Runtime Implementation.
+//!
+//! Available with `async` feature, the default runtime is thread based, see [event](crate::event).
+//! Describe [Runtime] which reads and writes messages using [tokio].
+//! Provides [Runtime::next_event] to wait on both ticks and input using [tokio::select],
+//! and [Runtime::rpc] to await reply of a message with timeout.
+
+use std::{collections::VecDeque, time::Duration};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, Lines},
+    time::Interval,
+};
+
+use crate::{
+    init::{InitRequest, InitRespone},
+    log,
+    log::Level,
+    message::{Body, Message, Request, Response},
+};
+
+/// Event for node running on [Runtime].
+#[derive(Debug)]
+pub enum Event<Payload> {
+    /// Tick of interval.
+    Tick,
+    /// Input message.
+    Input(Message<Payload>),
+}
+
+/// Async runtime for node.
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::runtime::Runtime;
+/// # use gossip_glomers::init::InitRequest;
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let input = r#"
+///     { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1"] } }
+/// "#.as_bytes();
+/// let mut output = Vec::new();
+/// let mut runtime = Runtime::new(input, &mut output);
+/// let InitRequest::Init { node_id, .. } = runtime.init().await.unwrap();
+/// assert_eq!(node_id, "n1");
+/// drop(runtime);
+/// assert!(String::from_utf8(output).unwrap().contains("init_ok"));
+/// # });
+/// ```
+pub struct Runtime<R, W> {
+    /// Input lines.
+    lines: Lines<R>,
+    /// Output writer.
+    writer: W,
+    /// Serialization buffer.
+    buffer: Vec<u8>,
+    /// Input received while waiting for reply.
+    backlog: VecDeque<Message<Value>>,
+}
+
+impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> Runtime<R, W> {
+    /// Create runtime reading input from reader and writing output to writer.
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            lines: reader.lines(),
+            writer,
+            buffer: Vec::new(),
+            backlog: VecDeque::new(),
+        }
+    }
+
+    /// Handle Initialization Protocol.
+    ///
+    /// Returns [None] if input is closed before initialization.
+    pub async fn init(&mut self) -> Option<InitRequest> {
+        let init_msg = self.recv::<InitRequest>().await?;
+        self.send(Message {
+            src: init_msg.dst,
+            dst: init_msg.src,
+            body: Body {
+                id: None,
+                reply_id: init_msg.body.id,
+                payload: InitRespone::InitOk,
+            },
+        })
+        .await;
+        let InitRequest::Init { node_id, node_ids } = &init_msg.body.payload;
+        log!(
+            Level::Info,
+            "initialized node {node_id} of {} nodes",
+            node_ids.len()
+        );
+        Some(init_msg.body.payload)
+    }
+
+    /// Send message as single line and flush.
+    ///
+    /// # Panics
+    ///
+    /// Panics if message cannot be written.
+    pub async fn send<Payload: Serialize + Response, Id: Serialize>(
+        &mut self,
+        message: Message<Payload, Id>,
+    ) {
+        self.buffer.clear();
+        serde_json::to_writer(&mut self.buffer, &message).expect("failed to serialize");
+        self.buffer.push(b'\n');
+        self.writer
+            .write_all(&self.buffer)
+            .await
+            .expect("failed to write");
+        self.writer.flush().await.expect("failed to flush");
+    }
+
+    /// Receive next message from input.
+    ///
+    /// Malformed input is logged and skipped.
+    /// Returns [None] when input is closed.
+    async fn recv_value(&mut self) -> Option<Message<Value>> {
+        if let Some(message) = self.backlog.pop_front() {
+            return Some(message);
+        }
+        loop {
+            let line = match self.lines.next_line().await {
+                Ok(line) => line?,
+                Err(e) => {
+                    log!(Level::Error, "failed to read input: {e}");
+                    return None;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(message) => return Some(message),
+                Err(e) => log!(Level::Error, "failed to de-serialize {line}: {e}"),
+            }
+        }
+    }
+
+    /// Receive next message with request payload.
+    ///
+    /// Messages with unknown payload are logged and skipped.
+    /// Returns [None] when input is closed.
+    pub async fn recv<Payload: DeserializeOwned + Request>(&mut self) -> Option<Message<Payload>> {
+        loop {
+            let message = self.recv_value().await?;
+            match decode(message) {
+                Ok(message) => return Some(message),
+                Err(e) => log!(Level::Error, "failed to de-serialize payload: {e}"),
+            }
+        }
+    }
+
+    /// Wait for next tick of interval or input message.
+    ///
+    /// Returns [None] when input is closed.
+    pub async fn next_event<Payload: DeserializeOwned + Request>(
+        &mut self,
+        ticks: &mut Interval,
+    ) -> Option<Event<Payload>> {
+        tokio::select! {
+            _ = ticks.tick() => Some(Event::Tick),
+            message = self.recv() => message.map(Event::Input),
+        }
+    }
+
+    /// Send message and wait for its reply.
+    ///
+    /// Reply is matched by both `src` and `in_reply_to`,
+    /// hence requests to multiple services can be outstanding at once.
+    /// Other messages received meanwhile are kept for [Runtime::recv] and later rpc.
+    /// Returns [None] if reply does not arrive within timeout or input is closed.
+    pub async fn rpc<Payload: Serialize + Response, Reply: DeserializeOwned + Request>(
+        &mut self,
+        message: Message<Payload>,
+        timeout: Duration,
+    ) -> Option<Message<Reply>> {
+        let (id, dst) = (message.body.id, message.dst.clone());
+        self.send(message).await;
+        let mut skipped = VecDeque::new();
+        let reply = tokio::time::timeout(timeout, async {
+            loop {
+                let message = self.recv_value().await?;
+                if id.is_some() && message.body.reply_id == id && message.src == dst {
+                    return Some(message);
+                }
+                skipped.push_back(message);
+            }
+        })
+        .await
+        .ok()
+        .flatten();
+        skipped.append(&mut self.backlog);
+        self.backlog = skipped;
+        match decode(reply?) {
+            Ok(reply) => Some(reply),
+            Err(e) => {
+                log!(Level::Error, "failed to de-serialize reply: {e}");
+                None
+            }
+        }
+    }
+}
+
+/// Decode payload of message.
+fn decode<Payload: DeserializeOwned>(
+    message: Message<Value>,
+) -> serde_json::Result<Message<Payload>> {
+    Ok(Message {
+        src: message.src,
+        dst: message.dst,
+        body: Body {
+            id: message.body.id,
+            reply_id: message.body.reply_id,
+            payload: serde_json::from_value(message.body.payload)?,
+        },
+    })
+}
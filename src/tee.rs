@@ -0,0 +1,86 @@
+//! Tee Writer Implementation.
+//!
+//! Describe [Tee] writer which duplicates output.
+//! Provides function to get output writer of node.
+
+use std::{
+    fs::OpenOptions,
+    io::{stdout, BufWriter, Write},
+};
+
+/// Writer which writes to both writers.
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::tee::Tee;
+/// # use gossip_glomers::message::{Body, Message};
+/// # use gossip_glomers::init::InitRespone;
+/// let (mut first, mut second) = (Vec::new(), Vec::new());
+/// let mut writer = Tee::new(&mut first, &mut second);
+/// let message = Message {
+///     src: "n1".to_string(),
+///     dst: "c1".to_string(),
+///     body: Body {
+///         id: None,
+///         reply_id: Some(1),
+///         payload: InitRespone::InitOk,
+///     }
+/// };
+/// message.send(&mut writer);
+/// assert!(!first.is_empty());
+/// assert_eq!(first, second);
+/// ```
+pub struct Tee<A, B> {
+    /// First writer.
+    first: A,
+    /// Second writer.
+    second: B,
+}
+
+impl<A: Write, B: Write> Tee<A, B> {
+    /// Create writer which writes to both writers.
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<A: Write, B: Write> Write for Tee<A, B> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.first.write_all(buf)?;
+        self.second.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.first.flush()?;
+        self.second.flush()
+    }
+}
+
+/// Output writer for node.
+///
+/// Writes to standard output through a buffer,
+/// [Message::send](crate::message::Message::send) flushes after every message.
+/// If `GG_TEE_OUT` is set then output is also appended to file at that path,
+/// which allows replay or analysis of node output.
+/// Maelstrom starts every node with the same path,
+/// hence file is appended to instead of truncated and keeps output of all nodes.
+///
+/// # Panics
+///
+/// Panics if file cannot be opened.
+pub fn output() -> Box<dyn Write> {
+    let stdout = stdout().lock();
+    match std::env::var_os("GG_TEE_OUT") {
+        Some(path) => Box::new(BufWriter::new(Tee::new(
+            stdout,
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .unwrap_or_else(|e| panic!("failed to open {path:?} with error {e:?}")),
+        ))),
+        None => Box::new(BufWriter::new(stdout)),
+    }
+}
@@ -0,0 +1,165 @@
+//! Message Transport Implementation.
+//!
+//! Describe [Transport] which receives and sends messages of node,
+//! decoupling node logic from standard input and output.
+//! Provides [StdioTransport] used by binaries and [MemoryTransport] to drive nodes in-process.
+
+use std::{
+    collections::VecDeque,
+    io::{stdin, BufRead, StdinLock, Write},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{
+    log,
+    log::Level,
+    message::{receive, Message, Request, Response, Writer},
+    tee::output,
+};
+
+/// Transport of messages for node.
+pub trait Transport {
+    /// Receive next message, messages which fail to de-serialize are logged and skipped.
+    ///
+    /// Returns [None] when input is closed.
+    fn recv<Req: DeserializeOwned + Request>(&mut self) -> Option<Message<Req>>;
+    /// Send message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if message cannot be sent.
+    fn send<Res: Serialize + Response>(&mut self, message: Message<Res>);
+}
+
+/// Transport reading one message per line and writing one message per line.
+///
+/// Defaults to standard input and [output], see [StdioTransport::stdio].
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::init::{InitRequest, InitRespone};
+/// # use gossip_glomers::message::Message;
+/// # use gossip_glomers::transport::{StdioTransport, Transport};
+/// let input = r#"
+///     { "src": "c1", "dest": "n1", "body": { "type": "init" } }
+///     { "src": "c1", "dest": "n1", "body": { "type": "init", "node_id": "n1", "node_ids": [] } }
+/// "#.as_bytes();
+/// let mut transport = StdioTransport::new(input, Vec::new());
+/// let init = transport.recv::<InitRequest>().unwrap();
+/// transport.send(Message::reply(init.dst, init.src, init.body.id, InitRespone::InitOk));
+/// assert!(transport.recv::<InitRequest>().is_none());
+/// let output = String::from_utf8(transport.into_inner()).unwrap();
+/// assert!(output.ends_with("\"type\":\"init_ok\"}}\n"), "{output}");
+/// ```
+pub struct StdioTransport<R = StdinLock<'static>, W = Box<dyn Write>> {
+    /// Input reader.
+    reader: R,
+    /// Output writer.
+    writer: Writer<W>,
+}
+
+impl<R: BufRead, W: Write> StdioTransport<R, W> {
+    /// Create transport reading messages from reader and writing messages to writer.
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            reader,
+            writer: Writer::new(writer),
+        }
+    }
+
+    /// Get underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl StdioTransport {
+    /// Create transport over standard input and [output] of node.
+    pub fn stdio() -> Self {
+        Self::new(stdin().lock(), output())
+    }
+}
+
+impl<R: BufRead, W: Write> Transport for StdioTransport<R, W> {
+    /// Receive next message, see [receive].
+    ///
+    /// Lines are read one at a time, hence input after the message stays in reader.
+    fn recv<Req: DeserializeOwned + Request>(&mut self) -> Option<Message<Req>> {
+        receive(&mut self.reader).next()
+    }
+
+    fn send<Res: Serialize + Response>(&mut self, message: Message<Res>) {
+        self.writer.send(message);
+    }
+}
+
+/// Transport passing messages in memory, without serializing to text.
+///
+/// Messages pushed to inbox are received in order, sent messages are collected in outbox.
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::init::{InitRequest, InitRespone};
+/// # use gossip_glomers::message::Message;
+/// # use gossip_glomers::transport::{MemoryTransport, Transport};
+/// let mut transport = MemoryTransport::default();
+/// transport.push(serde_json::from_str(
+///     r#"{ "src": "c1", "dest": "n1", "body": { "type": "init", "node_id": "n1", "node_ids": [] } }"#,
+/// ).unwrap());
+/// let init = transport.recv::<InitRequest>().unwrap();
+/// transport.send(Message::reply(init.dst, init.src, init.body.id, InitRespone::InitOk));
+/// assert!(transport.recv::<InitRequest>().is_none());
+/// assert_eq!(transport.sent()[0].body.payload["type"], "init_ok");
+/// ```
+#[derive(Default)]
+pub struct MemoryTransport {
+    /// Messages to be received.
+    inbox: VecDeque<Message<Value>>,
+    /// Messages sent.
+    outbox: Vec<Message<Value>>,
+}
+
+impl MemoryTransport {
+    /// Push message to be received.
+    pub fn push(&mut self, message: Message<Value>) {
+        self.inbox.push_back(message);
+    }
+
+    /// Messages sent so far.
+    pub fn sent(&self) -> &[Message<Value>] {
+        &self.outbox
+    }
+
+    /// Take messages sent so far.
+    pub fn take_sent(&mut self) -> Vec<Message<Value>> {
+        std::mem::take(&mut self.outbox)
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn recv<Req: DeserializeOwned + Request>(&mut self) -> Option<Message<Req>> {
+        while let Some(message) = self.inbox.pop_front() {
+            let value = serde_json::to_value(&message).expect("message is json");
+            match serde_json::from_value(value) {
+                Ok(message) => return Some(message),
+                Err(e) => log!(Level::Error, "failed to de-serialize {message:?}: {e}"),
+            }
+        }
+        None
+    }
+
+    fn send<Res: Serialize + Response>(&mut self, message: Message<Res>) {
+        let value = serde_json::to_value(&message).unwrap_or_else(|_| {
+            panic!(
+                "serialize response to {} failed",
+                std::any::type_name::<Res>(),
+            )
+        });
+        self.outbox
+            .push(serde_json::from_value(value).expect("message is json"));
+    }
+}
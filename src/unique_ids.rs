@@ -0,0 +1,534 @@
+//! Unique Id Generation Node Implementation.
+//!
+//! Describe [GenRequest] and [GenRespone] payloads of unique id generation node.
+//! Provides [Generator] which serves requests over any [Transport],
+//! and [BlockAllocator] which runs on events, see [Event].
+
+use std::{collections::VecDeque, sync::mpsc::Sender, time::Duration};
+
+use serde::Serialize;
+
+use crate::{
+    derive_request, derive_response,
+    event::{tick_after, Event},
+    init::InitRequest,
+    log,
+    log::Level,
+    message::{ErrorCode, Message, Writer},
+    transport::Transport,
+};
+
+derive_request!(
+    /// Request payload for unique id generation node.
+    pub enum GenRequest {
+        /// Generate Id request.
+        ///
+        /// This message requests node to generate unique id.
+        /// ```json
+        /// { "type": "generate" }
+        /// ```
+        Generate,
+        /// Block value request.
+        ///
+        /// This message acknowledge [GenRespone::ReadBlock].
+        /// ```json
+        /// {
+        ///     "type": "read_ok",
+        ///     "value": 1000
+        /// }
+        /// ```
+        #[serde(rename = "read_ok")]
+        ReadBlockOk {
+            /// end of last reserved block.
+            value: usize,
+        },
+        /// Block reserved request.
+        ///
+        /// This message acknowledge [GenRespone::ReserveBlock].
+        /// ```json
+        /// { "type": "cas_ok" }
+        /// ```
+        #[serde(rename = "cas_ok")]
+        BlockReserved,
+        /// Error request.
+        ///
+        /// This message acknowledge error in operation.
+        /// ```json
+        /// {
+        ///     "type": "error"
+        ///     "code": 22,
+        ///     "text": "Some messsage"
+        /// }
+        /// ```
+        Error {
+            /// error code.
+            code: ErrorCode,
+            /// error message.
+            text: String,
+        },
+    }
+);
+
+derive_response!(
+    /// Response payload for unique id generation node.
+    pub enum GenRespone {
+        /// Generate ok response.
+        ///
+        /// This message acknowledges Generate request.
+        /// It includes the uniquely generated Id.
+        /// ```json
+        /// {
+        ///     "type": "generate_ok",
+        ///     "id": 123
+        /// }
+        /// ```
+        GenerateOk {
+            /// Newly generated id.
+            id: GeneratedId,
+        },
+        /// Read block response.
+        ///
+        /// This message to read end of last reserved block from key store.
+        /// ```json
+        /// {
+        ///     "type": "read",
+        ///     "key": "ID_BLOCK"
+        /// }
+        /// ```
+        #[serde(rename = "read")]
+        ReadBlock {
+            /// Key of block from key store.
+            key: String,
+        },
+        /// Reserve block response.
+        ///
+        /// This message to reserve block by moving its end in key store.
+        /// ```json
+        /// {
+        ///     "type": "cas",
+        ///     "key": "ID_BLOCK",
+        ///     "from": 1000,
+        ///     "to": 2000,
+        ///     "create_if_not_exists": true
+        /// }
+        /// ```
+        #[serde(rename = "cas")]
+        ReserveBlock {
+            /// Block key in store.
+            key: String,
+            /// Start of the block.
+            #[serde(rename = "from")]
+            start: usize,
+            /// End of the block.
+            #[serde(rename = "to")]
+            end: usize,
+            /// Create key if not exists.
+            #[serde(rename = "create_if_not_exists")]
+            create: bool,
+        },
+    }
+);
+
+/// Generated id, as number or rendered as string.
+///
+/// [Snowflake] ids are rendered as string,
+/// as clients may not represent every `u64` as a number.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum GeneratedId {
+    /// Id as number.
+    Number(usize),
+    /// Id rendered as string.
+    Text(String),
+}
+
+/// Node id for key store.
+const KV_NODE: &str = "lin-kv";
+/// Key of the end of last reserved block in store.
+const KEY: &str = "ID_BLOCK";
+/// Number of ids reserved at once.
+const BLOCK_SIZE: usize = 1000;
+
+/// Ticks to wait for reply of key store, before request is taken as lost.
+///
+/// Request may be sent just before a tick, hence a full tick interval is at least waited.
+pub const REPLY_TICKS: usize = 2;
+/// Maximum jitter in milliseconds before retrying reservation.
+const MAX_JITTER: u64 = 50;
+
+/// Random delay to avoid nodes retrying reservation in lock step.
+fn jitter() -> Duration {
+    use std::hash::{BuildHasher, Hasher};
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    Duration::from_millis(random % MAX_JITTER)
+}
+
+/// Milliseconds since unix epoch of `2024-01-01T00:00:00Z`, start of snowflake timestamps.
+pub const EPOCH: u64 = 1_704_067_200_000;
+/// Number of bits for node index in snowflake id.
+pub const NODE_BITS: u32 = 10;
+/// Number of bits for per millisecond sequence in snowflake id.
+pub const SEQUENCE_BITS: u32 = 12;
+
+/// Time since [EPOCH].
+///
+/// # Panics
+///
+/// Panics if clock is before unix epoch.
+fn since_epoch() -> Duration {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("clock before unix epoch")
+        .saturating_sub(Duration::from_millis(EPOCH))
+}
+
+/// Generates Snowflake style ids.
+///
+/// Id is `timestamp << 22 | node << 12 | sequence` where
+/// * timestamp is milliseconds since [EPOCH].
+/// * node is index of node among sorted node ids.
+/// * sequence is counter within the millisecond.
+///
+/// If sequence overflows or clock goes backwards then timestamp is bumped past the last one,
+/// hence ids of a node are strictly increasing.
+pub struct Snowflake {
+    /// Node index.
+    node: u64,
+    /// Timestamp of last id.
+    last: u64,
+    /// Sequence of last id.
+    sequence: u64,
+}
+
+impl Snowflake {
+    /// Create generator for node index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if node index does not fit in [NODE_BITS].
+    pub fn new(node: usize) -> Self {
+        let node = node as u64;
+        assert!(
+            node < 1 << NODE_BITS,
+            "node index {node} too large for snowflake"
+        );
+        Self {
+            node,
+            last: 0,
+            sequence: 0,
+        }
+    }
+
+    /// Generate next id.
+    pub fn next_id(&mut self) -> u64 {
+        let now = since_epoch().as_millis() as u64;
+        if now > self.last {
+            (self.last, self.sequence) = (now, 0);
+        } else if self.sequence + 1 < 1 << SEQUENCE_BITS {
+            self.sequence += 1;
+        } else {
+            (self.last, self.sequence) = (self.last + 1, 0);
+        }
+        self.last << (NODE_BITS + SEQUENCE_BITS) | self.node << SEQUENCE_BITS | self.sequence
+    }
+}
+
+/// Generates ids from node index, or [Snowflake] ids.
+///
+/// `unique_id = node_index + node_count * (start + counter)`
+///
+/// Hence
+/// * `unique_id % node_count = node_index`
+///   which means the ids generated by two node cannot collide
+/// * `unique_id - node_index = node_count * (start + counter)`
+///   as counter is updated after each message sent, id generated by a node don't collide.
+///
+/// The `start` is microseconds since [EPOCH] at startup,
+/// hence restarted node does not reissue ids unless previous run
+/// generated more than one id per microsecond.
+///
+/// # Example
+///
+/// ```rust
+/// # use gossip_glomers::init::InitRequest;
+/// # use gossip_glomers::transport::StdioTransport;
+/// # use gossip_glomers::unique_ids::Generator;
+/// let input = r#"
+///     { "src": "c1", "dest": "n2", "body": { "type": "generate", "msg_id": 1 } }
+/// "#.as_bytes();
+/// let mut transport = StdioTransport::new(input, Vec::new());
+/// let init = InitRequest::Init {
+///     node_id: "n2".into(),
+///     node_ids: vec!["n1".into(), "n2".into()],
+/// };
+/// Generator::new(init).serve(&mut transport);
+/// let output = String::from_utf8(transport.into_inner()).unwrap();
+/// assert!(output.contains(r#""type":"generate_ok""#), "{output}");
+/// ```
+pub struct Generator {
+    /// Index of node among sorted node ids.
+    node_index: usize,
+    /// Number of nodes.
+    node_count: usize,
+    /// Microseconds since [EPOCH] at startup.
+    start: usize,
+    /// Snowflake generator, if generating snowflake ids.
+    snowflake: Option<Snowflake>,
+}
+
+impl Generator {
+    /// Create generator from initialization message.
+    pub fn new(init_request: InitRequest) -> Self {
+        let InitRequest::Init {
+            node_id,
+            mut node_ids,
+        } = init_request;
+        node_ids.sort();
+        let node_index = node_ids.iter().position(|n| n.eq(&node_id)).unwrap();
+        Self {
+            node_index,
+            node_count: node_ids.len(),
+            start: since_epoch().as_micros() as usize,
+            snowflake: None,
+        }
+    }
+    /// Generate [Snowflake] ids instead, rendered as string.
+    pub fn snowflake(mut self, snowflake: bool) -> Self {
+        self.snowflake = snowflake.then(|| Snowflake::new(self.node_index));
+        self
+    }
+    /// Reply to generate requests until input is closed.
+    ///
+    /// Replies take message ids from 0 in order of requests,
+    /// key store replies are ignored as they are only expected by [BlockAllocator].
+    pub fn serve<T: Transport>(&mut self, transport: &mut T) {
+        let mut counter = 0;
+        while let Some(request) = transport.recv::<GenRequest>() {
+            let GenRequest::Generate = request.body.payload else {
+                continue;
+            };
+            let id = match self.snowflake.as_mut() {
+                Some(snowflake) => GeneratedId::Text(snowflake.next_id().to_string()),
+                None => {
+                    GeneratedId::Number(self.node_index + (self.start + counter) * self.node_count)
+                }
+            };
+            transport.send(
+                Message::reply(
+                    request.dst,
+                    request.src,
+                    request.body.id,
+                    GenRespone::GenerateOk { id },
+                )
+                .with_id(counter),
+            );
+            counter += 1;
+        }
+    }
+}
+
+/// Hands out ids from blocks reserved in key store.
+///
+/// * Key store holds end of last reserved block.
+/// * Node reserves `[end, end + block size)` by compare and swap of the end.
+/// * Ids are handed out locally until block is exhausted.
+/// * On compare and swap failure read the end after jitter and retry,
+///   without blocking other requests meanwhile.
+/// * Only reply to the outstanding key store request is applied,
+///   if it is not replied within [REPLY_TICKS] ticks the end is read again.
+///
+/// Hence ids are globally sortable by reservation order of blocks.
+/// If node crashes mid block then the unused ids of the block are skipped.
+pub struct BlockAllocator {
+    /// Message id counter.
+    id: usize,
+    /// Node id.
+    node: String,
+    /// Next id to be handed out.
+    next: usize,
+    /// End of the current block.
+    end: usize,
+    /// Last known end of reserved blocks in store.
+    known: usize,
+    /// Block whose reservation is in flight.
+    reserving: Option<(usize, usize)>,
+    /// Generate requests waiting for a block.
+    pending: VecDeque<Message<GenRequest>>,
+    /// Read block end on next tick, as reservation failed.
+    retry: bool,
+    /// Message id of outstanding key store request, with ticks it has waited for reply.
+    in_flight: Option<(usize, usize)>,
+}
+
+impl BlockAllocator {
+    /// Create new allocator from initialization message.
+    pub fn new(init_request: InitRequest) -> Self {
+        Self {
+            id: 0,
+            node: match init_request {
+                InitRequest::Init { node_id, .. } => node_id,
+            },
+            next: 0,
+            end: 0,
+            known: 0,
+            reserving: None,
+            pending: VecDeque::new(),
+            retry: false,
+            in_flight: None,
+        }
+    }
+
+    /// Send payload to key store.
+    fn send_kv<W: std::io::Write>(&mut self, payload: GenRespone, writer: &mut Writer<W>) {
+        self.in_flight = Some((self.id, 0));
+        writer.send(Message::request(
+            self.node.clone(),
+            KV_NODE.into(),
+            self.id,
+            payload,
+        ));
+        self.id += 1;
+    }
+
+    /// Reserve the block after last known block.
+    fn reserve<W: std::io::Write>(&mut self, writer: &mut Writer<W>) {
+        let (start, end) = (self.known, self.known + BLOCK_SIZE);
+        self.reserving = Some((start, end));
+        self.send_kv(
+            GenRespone::ReserveBlock {
+                key: KEY.into(),
+                start,
+                end,
+                create: true,
+            },
+            writer,
+        );
+    }
+
+    /// Reply pending requests from current block and reserve new block if exhausted.
+    fn drain<W: std::io::Write>(&mut self, writer: &mut Writer<W>) {
+        while self.next < self.end {
+            let Some(request) = self.pending.pop_front() else {
+                return;
+            };
+            writer.send(
+                Message::reply(
+                    request.dst,
+                    request.src,
+                    request.body.id,
+                    GenRespone::GenerateOk {
+                        id: GeneratedId::Number(self.next),
+                    },
+                )
+                .with_id(self.id),
+            );
+            self.id += 1;
+            self.next += 1;
+        }
+        if !self.pending.is_empty() && self.reserving.is_none() {
+            self.reserve(writer);
+        }
+    }
+
+    /// Handle events.
+    ///
+    /// Handle events in following ways:
+    /// * [close](Event::Close): close the loop.
+    /// * [tick](Event::Tick):
+    ///     * read block end if reservation failed since last tick.
+    ///     * read block end if key store request was not replied within [REPLY_TICKS],
+    ///       dropping block of lost reservation.
+    /// * [input](Event::Input) of:
+    ///     * [Generate](GenRequest::Generate):
+    ///         * queue request and reply from current block.
+    ///         * reserve next block if current block is exhausted.
+    ///     * replies other than to outstanding key store request are ignored,
+    ///       hence late or duplicate reply does not hand out a block twice.
+    ///     * [Block reserved](GenRequest::BlockReserved):
+    ///         * switch to reserved block and reply queued requests.
+    ///     * [Read block ok](GenRequest::ReadBlockOk):
+    ///         * retry reservation after the read block end.
+    ///     * [Error](GenRequest::Error):
+    ///         * [key does not exist](ErrorCode::KeyDoesNotExist):
+    ///             * no block is reserved yet, retry reservation of first block.
+    ///         * [precondition failed](ErrorCode::PreconditionFailed) or [timeout](ErrorCode::Timeout):
+    ///             * force tick after jitter to read block end.
+    ///         * other errors are logged, then handled as timeout.
+    ///
+    /// # Arguments
+    /// * events: Events to handle, usually receiver channel.
+    /// * tick_tx: Tick sender to allow force ticking.
+    /// * writer: Output response via writer.
+    pub fn handle_events<W: std::io::Write>(
+        &mut self,
+        events: impl IntoIterator<Item = Event<GenRequest>>,
+        tick_tx: Sender<()>,
+        writer: &mut W,
+    ) {
+        let mut writer = Writer::new(writer);
+        for event in events {
+            let request = match event {
+                Event::Close => break,
+                Event::Tick(_) => {
+                    let lost = self.in_flight.as_mut().is_some_and(|(_, ticks)| {
+                        *ticks += 1;
+                        *ticks > REPLY_TICKS
+                    });
+                    if lost {
+                        log!(Level::Info, "key store request {:?} lost", self.in_flight);
+                        self.reserving = None;
+                    }
+                    if std::mem::take(&mut self.retry) || lost {
+                        self.send_kv(GenRespone::ReadBlock { key: KEY.into() }, &mut writer);
+                    }
+                    continue;
+                }
+                Event::Input(request) => request,
+            };
+            if !matches!(request.body.payload, GenRequest::Generate) {
+                let outstanding = self.in_flight.map(|(id, _)| id);
+                if request.body.reply_id.is_none() || request.body.reply_id != outstanding {
+                    log!(
+                        Level::Debug,
+                        "ignoring reply to {:?}, waiting for {outstanding:?}",
+                        request.body.reply_id
+                    );
+                    continue;
+                }
+                self.in_flight = None;
+            }
+            match request.body.payload {
+                GenRequest::Generate => self.pending.push_back(request),
+                GenRequest::BlockReserved => {
+                    if let Some((start, end)) = self.reserving.take() {
+                        (self.next, self.end, self.known) = (start, end, end);
+                    }
+                }
+                GenRequest::ReadBlockOk { value } => {
+                    self.known = value;
+                    self.reserve(&mut writer);
+                    continue;
+                }
+                GenRequest::Error { code, text } => {
+                    match code {
+                        ErrorCode::KeyDoesNotExist => {
+                            self.known = 0;
+                            self.reserve(&mut writer);
+                        }
+                        code => {
+                            if !matches!(code, ErrorCode::PreconditionFailed | ErrorCode::Timeout) {
+                                log!(Level::Warn, "block reservation failed: {code:?} {text}");
+                            }
+                            self.retry = true;
+                            tick_after(tick_tx.clone(), jitter());
+                        }
+                    }
+                    continue;
+                }
+            }
+            self.drain(&mut writer);
+        }
+    }
+}
@@ -0,0 +1,546 @@
+use gossip_glomers::{
+    broadcast::{
+        BroadcastRequest, BroadcastRespone, BroadcastState, EventHandler, GossipMode, GossipStats,
+    },
+    event::{inputs, AdaptiveTick, Event},
+    init::InitRequest,
+    message::Message,
+    random::Rng,
+};
+use quickcheck::QuickCheck;
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::mpsc::channel,
+    time::Duration,
+};
+use testutil::FaultyTransport;
+
+mod testutil;
+
+/// Run broadcast node in-process on events and return output messages.
+fn handle(
+    node: &mut EventHandler,
+    events: impl IntoIterator<Item = Event<BroadcastRequest>>,
+) -> Vec<Message<Value>> {
+    let (tick_tx, _tick_rx) = channel();
+    let mut output = Vec::new();
+    node.handle_events(events, tick_tx, &mut output);
+    String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+/// Client request events to node.
+fn request(node_id: &str, body: Value) -> Vec<Event<BroadcastRequest>> {
+    let message = json!({ "src": "c1", "dest": node_id, "body": body }).to_string();
+    inputs(message.as_bytes()).collect()
+}
+
+/// Nodes of cluster with given topology and gossip mode, in order of node id.
+fn cluster(
+    topology: &HashMap<String, Vec<String>>,
+    mode: GossipMode,
+) -> Vec<(String, EventHandler)> {
+    let mut node_ids = topology.keys().cloned().collect::<Vec<_>>();
+    node_ids.sort();
+    node_ids
+        .iter()
+        .map(|node_id| {
+            let mut node = EventHandler::new(InitRequest::Init {
+                node_id: node_id.clone(),
+                node_ids: node_ids.clone(),
+            })
+            .gossip(mode);
+            let topology = json!({ "type": "topology", "msg_id": 1, "topology": topology });
+            handle(&mut node, request(node_id, topology));
+            (node_id.clone(), node)
+        })
+        .collect()
+}
+
+/// Deliver due messages and tick every node once.
+///
+/// # Returns
+/// Number of messages sent by nodes.
+fn round(nodes: &mut [(String, EventHandler)], transport: &mut FaultyTransport) -> usize {
+    let delivered = transport.deliver();
+    let mut sent = 0;
+    for (node_id, node) in nodes.iter_mut() {
+        let events = delivered
+            .iter()
+            .filter(|message| message.dst == *node_id)
+            .flat_map(|message| {
+                let message = serde_json::to_string(message).unwrap();
+                inputs(message.as_bytes()).collect::<Vec<_>>()
+            })
+            .chain([Event::tick()]);
+        for message in handle(node, events) {
+            transport.send(message);
+            sent += 1;
+        }
+    }
+    sent
+}
+
+/// Messages read from node.
+fn read(node_id: &str, node: &mut EventHandler) -> HashSet<u64> {
+    let output = handle(
+        node,
+        request(node_id, json!({ "type": "read", "msg_id": 3 })),
+    );
+    output[0].body.payload["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message.as_u64().unwrap())
+        .collect()
+}
+
+/// test broadcast converges to all messages when half of gossip is dropped.
+#[test]
+fn test_broadcast_faulty_transport() {
+    let node_ids = ["n1", "n2", "n3"].map(String::from);
+    let topology = node_ids
+        .iter()
+        .map(|node_id| {
+            let peers = node_ids.iter().filter(|n| *n != node_id).cloned().collect();
+            (node_id.clone(), peers)
+        })
+        .collect();
+    let mut nodes = cluster(&topology, GossipMode::Push);
+    let mut transport = FaultyTransport::new(7).drop(50).duplicate(10).reorder(2);
+    for (message, (node_id, node)) in nodes.iter_mut().enumerate() {
+        let broadcast = json!({ "type": "broadcast", "msg_id": 2, "message": message });
+        handle(node, request(node_id, broadcast));
+    }
+    for _ in 0..50 {
+        round(&mut nodes, &mut transport);
+    }
+    for (node_id, node) in nodes.iter_mut() {
+        assert_eq!(read(node_id, node), HashSet::from([0, 1, 2]), "{node_id}");
+    }
+}
+
+/// test pull recovers messages which no peer pushes, when gossip is dropped.
+///
+/// `n1` is not a peer of any node, hence it only receives messages by pulling.
+#[test]
+fn test_broadcast_pull() {
+    let topology = [("n1", ["n2"]), ("n2", ["n3"]), ("n3", ["n2"])]
+        .map(|(node_id, peers)| (node_id.to_string(), peers.map(String::from).to_vec()))
+        .into_iter()
+        .collect();
+    for (mode, expected) in [
+        (GossipMode::Push, HashSet::new()),
+        (GossipMode::Pull, HashSet::from([0, 1])),
+        (GossipMode::Both, HashSet::from([0, 1])),
+    ] {
+        let mut nodes = cluster(&topology, mode);
+        let mut transport = FaultyTransport::new(7).drop(50).duplicate(10).reorder(2);
+        for (message, (node_id, node)) in nodes.iter_mut().skip(1).enumerate() {
+            let broadcast = json!({ "type": "broadcast", "msg_id": 2, "message": message });
+            handle(node, request(node_id, broadcast));
+        }
+        for _ in 0..50 {
+            round(&mut nodes, &mut transport);
+        }
+        let (node_id, node) = &mut nodes[0];
+        assert_eq!(read(node_id, node), expected, "{mode:?}");
+        for (node_id, node) in nodes.iter_mut().skip(1) {
+            assert_eq!(
+                read(node_id, node),
+                HashSet::from([0, 1]),
+                "{mode:?} {node_id}"
+            );
+        }
+    }
+}
+
+/// Random connected topology of up to 5 nodes.
+///
+/// Each node is connected to a random earlier node, and a few random extra links are added.
+fn random_topology(rng: &mut Rng) -> HashMap<String, Vec<String>> {
+    let count = 1 + rng.below(5);
+    let mut links = HashSet::new();
+    for node in 1..count {
+        links.insert((rng.below(node), node));
+    }
+    for _ in 0..count {
+        let (a, b) = (rng.below(count), rng.below(count));
+        if a != b {
+            links.insert((a.min(b), a.max(b)));
+        }
+    }
+    let mut topology: HashMap<_, Vec<_>> = (0..count)
+        .map(|node| (format!("n{node}"), Vec::new()))
+        .collect();
+    for (a, b) in links {
+        topology
+            .get_mut(&format!("n{a}"))
+            .unwrap()
+            .push(format!("n{b}"));
+        topology
+            .get_mut(&format!("n{b}"))
+            .unwrap()
+            .push(format!("n{a}"));
+    }
+    topology
+}
+
+/// Check every node of random cluster sees every broadcast once gossip is quiescent.
+fn converges(seed: u64, broadcasts: Vec<u8>) -> bool {
+    let mut rng = Rng::new(seed);
+    let mut nodes = cluster(&random_topology(&mut rng), GossipMode::Push);
+    let mut transport = FaultyTransport::new(seed).drop(20).duplicate(10).reorder(2);
+    let broadcasts = &broadcasts[..broadcasts.len().min(10)];
+    for (message, node) in broadcasts.iter().enumerate() {
+        let count = nodes.len();
+        let (node_id, node) = &mut nodes[*node as usize % count];
+        let broadcast = json!({ "type": "broadcast", "msg_id": 2, "message": message });
+        handle(node, request(node_id, broadcast));
+    }
+    let mut quiet = false;
+    for _ in 0..200 {
+        if round(&mut nodes, &mut transport) == 0 && transport.is_empty() {
+            quiet = true;
+            break;
+        }
+    }
+    let expected = (0..broadcasts.len() as u64).collect::<HashSet<_>>();
+    quiet
+        && nodes
+            .iter_mut()
+            .all(|(node_id, node)| read(node_id, node) == expected)
+}
+
+/// test broadcast converges for random clusters, topologies and broadcasts.
+#[test]
+fn test_broadcast_converges() {
+    QuickCheck::new()
+        .tests(50)
+        .quickcheck(converges as fn(u64, Vec<u8>) -> bool);
+}
+
+/// Seen and acknowledged messages of consensus, sorted.
+fn consensus(payload: Option<BroadcastRespone>) -> Option<(Vec<usize>, Vec<usize>)> {
+    match payload? {
+        BroadcastRespone::Consensus {
+            mut seen,
+            mut seen_ack,
+        } => {
+            seen.sort();
+            seen_ack.sort();
+            Some((seen, seen_ack))
+        }
+        payload => panic!("expected consensus, got {payload:?}"),
+    }
+}
+
+/// test gossip sends only messages unknown to peer and acknowledges peer once.
+#[test]
+fn test_gossip_messages() {
+    let mut state = BroadcastState::new("n1", ["n1", "n2", "n3"].map(String::from));
+    assert_eq!(consensus(state.gossip_messages("n2")), None);
+    assert!(state.on_broadcast([1, 2]));
+    assert!(!state.on_broadcast([2]));
+    assert_eq!(
+        consensus(state.gossip_messages("n2")),
+        Some((vec![1, 2], vec![]))
+    );
+    assert!(state.on_consensus("n2", [3].into_iter().collect(), vec![1]));
+    assert_eq!(
+        consensus(state.gossip_messages("n2")),
+        Some((vec![2, 3], vec![3]))
+    );
+    assert_eq!(
+        consensus(state.gossip_messages("n3")),
+        Some((vec![1, 2, 3], vec![]))
+    );
+    assert!(!state.on_consensus("n2", Default::default(), vec![2, 3]));
+    assert_eq!(consensus(state.gossip_messages("n2")), None);
+}
+
+/// test every gossip of messages peer has sent is counted as redundant until peer acknowledges them.
+#[test]
+fn test_gossip_stats() {
+    let mut state = BroadcastState::new("n1", ["n1", "n2"].map(String::from));
+    state.on_broadcast([1]);
+    state.gossip_messages("n2");
+    assert_eq!(
+        *state.stats(),
+        GossipStats {
+            consensus: 1,
+            values: 1,
+            redundant: 0
+        }
+    );
+    // peer sends 2 which it has, and acknowledges 1.
+    state.on_consensus("n2", [2].into_iter().collect(), vec![1]);
+    state.gossip_messages("n2");
+    assert_eq!(
+        *state.stats(),
+        GossipStats {
+            consensus: 2,
+            values: 2,
+            redundant: 1
+        }
+    );
+    assert!(state.gossip_messages("n2").is_some());
+    assert_eq!(state.stats().redundant, 2);
+    state.on_consensus("n2", Default::default(), vec![2]);
+    assert!(state.gossip_messages("n2").is_none());
+    state.on_broadcast([3]);
+    state.gossip_messages("n2");
+    assert_eq!(state.stats().redundant, 2);
+}
+
+/// test burst of pending messages shrinks adaptive tick interval and idle ticks grow it.
+#[test]
+fn test_adaptive_tick() {
+    let ms = Duration::from_millis;
+    let tick = AdaptiveTick::new(ms(200), ms(20), ms(800));
+    let node_id = "n1";
+    let mut node = EventHandler::new(InitRequest::Init {
+        node_id: node_id.into(),
+        node_ids: vec!["n1".into(), "n2".into()],
+    })
+    .adaptive(tick.clone());
+    let topology = json!({ "type": "topology", "msg_id": 1, "topology": { "n1": ["n2"] } });
+    handle(&mut node, request(node_id, topology));
+    let broadcast =
+        json!({ "type": "broadcast", "msg_id": 2, "messages": (0..100).collect::<Vec<_>>() });
+    handle(&mut node, request(node_id, broadcast));
+    assert_eq!(tick.interval(), ms(200));
+    handle(&mut node, [Event::tick()]);
+    assert_eq!(tick.interval(), ms(100));
+    // peer acknowledges everything, hence there is nothing to gossip.
+    let ack = json!({ "type": "consensus", "seen": [], "seen_ack": (0..100).collect::<Vec<_>>() });
+    let ack = json!({ "src": "n2", "dest": node_id, "body": ack }).to_string();
+    handle(&mut node, inputs(ack.as_bytes()));
+    handle(&mut node, [Event::tick(), Event::tick()]);
+    assert!(tick.interval() > ms(100), "{:?}", tick.interval());
+}
+
+/// test node with same seed gossips to same random peers, hence runs are reproducible.
+#[test]
+fn test_gossip_seeded() {
+    let run = |seed| {
+        let node_ids = ["n1", "n2", "n3", "n4", "n5"].map(String::from);
+        let mut node = EventHandler::new(InitRequest::Init {
+            node_id: "n1".into(),
+            node_ids: node_ids.to_vec(),
+        })
+        .fanout(Some(2))
+        .seed(seed)
+        .start_id(0);
+        let topology =
+            json!({ "type": "topology", "msg_id": 1, "topology": { "n1": node_ids[1..] } });
+        handle(&mut node, request("n1", topology));
+        (0..5)
+            .map(|message| {
+                let broadcast = json!({ "type": "broadcast", "msg_id": 2, "message": message });
+                handle(&mut node, request("n1", broadcast));
+                handle(&mut node, [Event::tick()])
+                    .into_iter()
+                    .map(|message| serde_json::to_string(&message).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    let sent = run(7);
+    assert!(sent.iter().all(|tick| tick.len() == 2), "{sent:?}");
+    assert_eq!(sent, run(7));
+}
+
+/// test state is dirty until gossip to every peer has nothing to send.
+#[test]
+fn test_gossip_dirty() {
+    let mut state = BroadcastState::new("n1", ["n1", "n2"].map(String::from));
+    assert!(!state.is_dirty());
+    state.on_broadcast([1]);
+    assert!(state.is_dirty());
+    state.on_consensus("n2", Default::default(), vec![1]);
+    assert_eq!(consensus(state.gossip_messages("n2")), None);
+    state.mark_clean();
+    assert!(!state.on_broadcast([1]));
+    state.on_consensus("n2", Default::default(), vec![1]);
+    assert!(!state.is_dirty());
+    // seen message is acknowledged on next gossip, even if known.
+    state.on_consensus("n2", [1].into_iter().collect(), vec![]);
+    assert!(state.is_dirty());
+}
+
+/// test tick sends nothing when nothing changed since last tick.
+#[test]
+fn test_tick_unchanged() {
+    let node_id = "n1";
+    let mut node = EventHandler::new(InitRequest::Init {
+        node_id: node_id.into(),
+        node_ids: vec!["n1".into(), "n2".into()],
+    });
+    let topology = json!({ "type": "topology", "msg_id": 1, "topology": { "n1": ["n2"] } });
+    handle(&mut node, request(node_id, topology));
+    assert!(handle(&mut node, [Event::tick()]).is_empty());
+    let broadcast = json!({ "type": "broadcast", "msg_id": 2, "message": 1 });
+    handle(&mut node, request(node_id, broadcast));
+    assert_eq!(handle(&mut node, [Event::tick(), Event::tick()]).len(), 2);
+    let ack = json!({ "type": "consensus", "seen": [], "seen_ack": [1] });
+    let ack = json!({ "src": "n2", "dest": node_id, "body": ack }).to_string();
+    handle(&mut node, inputs(ack.as_bytes()));
+    assert!(handle(&mut node, [Event::tick(), Event::tick()]).is_empty());
+    let seen = json!({ "type": "consensus", "seen": [1], "seen_ack": [] });
+    let seen = json!({ "src": "n2", "dest": node_id, "body": seen }).to_string();
+    handle(&mut node, inputs(seen.as_bytes()));
+    assert_eq!(handle(&mut node, [Event::tick(), Event::tick()]).len(), 1);
+}
+
+/// test full state reconciliation converges nodes when gossip does not, even if it is dropped.
+///
+/// Nodes are not peers of each other, hence they exchange messages only by reconciling.
+#[test]
+fn test_broadcast_reconcile() {
+    let topology = ["n1", "n2"]
+        .map(|node_id| (node_id.to_string(), Vec::new()))
+        .into_iter()
+        .collect();
+    for (reconcile, expected) in [
+        (None, [HashSet::from([0]), HashSet::from([1])]),
+        (Some(3), [HashSet::from([0, 1]), HashSet::from([0, 1])]),
+    ] {
+        let mut nodes = cluster(&topology, GossipMode::Push)
+            .into_iter()
+            .map(|(node_id, node)| (node_id, node.reconcile(reconcile)))
+            .collect::<Vec<_>>();
+        let mut transport = FaultyTransport::new(7).drop(50).duplicate(10).reorder(2);
+        for (message, (node_id, node)) in nodes.iter_mut().enumerate() {
+            let broadcast = json!({ "type": "broadcast", "msg_id": 2, "message": message });
+            handle(node, request(node_id, broadcast));
+        }
+        for _ in 0..60 {
+            round(&mut nodes, &mut transport);
+        }
+        for ((node_id, node), expected) in nodes.iter_mut().zip(expected) {
+            assert_eq!(read(node_id, node), expected, "{reconcile:?} {node_id}");
+        }
+    }
+}
+
+/// test full state reconciliation converges peers when acknowledgements are dropped.
+///
+/// Consensus carrying `seen_ack` is dropped, hence peer never learns that its message arrived,
+/// keeps re-sending it and every consensus sent back carries an acknowledgement again.
+/// Messages only known to the acknowledging node spread only by reconciling.
+#[test]
+fn test_broadcast_reconcile_dropped_ack() {
+    let topology = [("n1", "n2"), ("n2", "n1")]
+        .map(|(node_id, peer)| (node_id.to_string(), vec![peer.to_string()]))
+        .into_iter()
+        .collect();
+    for (reconcile, expected) in [
+        (None, [HashSet::from([0]), HashSet::from([0, 1])]),
+        (Some(3), [HashSet::from([0, 1]), HashSet::from([0, 1])]),
+    ] {
+        let mut nodes = cluster(&topology, GossipMode::Push)
+            .into_iter()
+            .map(|(node_id, node)| (node_id, node.reconcile(reconcile)))
+            .collect::<Vec<_>>();
+        let mut transport = FaultyTransport::new(7).drop_matching(|message| {
+            let ack = &message.body.payload["seen_ack"];
+            ack.as_array().is_some_and(|ack| !ack.is_empty())
+        });
+        let broadcast = json!({ "type": "broadcast", "msg_id": 2, "message": 0 });
+        handle(&mut nodes[0].1, request("n1", broadcast));
+        round(&mut nodes, &mut transport);
+        round(&mut nodes, &mut transport);
+        let broadcast = json!({ "type": "broadcast", "msg_id": 2, "message": 1 });
+        handle(&mut nodes[1].1, request("n2", broadcast));
+        for _ in 0..30 {
+            round(&mut nodes, &mut transport);
+        }
+        for ((node_id, node), expected) in nodes.iter_mut().zip(expected) {
+            assert_eq!(read(node_id, node), expected, "{reconcile:?} {node_id}");
+        }
+    }
+}
+
+/// test reconcile remembers messages and marks them known to peer.
+#[test]
+fn test_on_reconcile() {
+    let mut state = BroadcastState::new("n1", ["n1", "n2"].map(String::from));
+    state.on_broadcast([1, 2]);
+    state.mark_clean();
+    assert!(state.on_reconcile("n2", [2, 3].into_iter().collect()));
+    assert!(state.is_dirty());
+    assert_eq!(
+        consensus(state.gossip_messages("n2")),
+        Some((vec![1], vec![]))
+    );
+    assert!(!state.on_reconcile("n2", [1, 2, 3].into_iter().collect()));
+    assert_eq!(consensus(state.gossip_messages("n2")), None);
+}
+
+/// test nodes created at different times, like a restarted node, send non overlapping ids.
+#[test]
+fn test_message_ids_restart() {
+    let ids = |node: &mut EventHandler| {
+        let reads = (0..100).flat_map(|id| request("n1", json!({ "type": "read", "msg_id": id })));
+        handle(node, reads)
+            .iter()
+            .map(|message| message.body.id.unwrap())
+            .collect::<Vec<_>>()
+    };
+    let init = || InitRequest::Init {
+        node_id: "n1".into(),
+        node_ids: vec!["n1".into()],
+    };
+    let mut node = EventHandler::new(init());
+    let before = ids(&mut node);
+    let after = ids(&mut node);
+    std::thread::sleep(Duration::from_millis(1));
+    let restarted = ids(&mut EventHandler::new(init()));
+    assert!(before.windows(2).all(|ids| ids[0] < ids[1]), "{before:?}");
+    assert!(before.last() < after.first());
+    assert!(after.last() < restarted.first());
+}
+
+/// test checkpoint is written only once checkpoint was read, failed reads are retried.
+#[test]
+fn test_checkpoint_after_read() {
+    let node_id = "n1";
+    let mut node = EventHandler::new(InitRequest::Init {
+        node_id: node_id.into(),
+        node_ids: vec!["n1".into()],
+    })
+    .persist(true);
+    let kv = |body: Value| {
+        let message = json!({ "src": "lin-kv", "dest": node_id, "body": body }).to_string();
+        inputs(message.as_bytes()).collect::<Vec<_>>()
+    };
+    let types = |output: Vec<Message<Value>>| {
+        output
+            .iter()
+            .map(|message| message.body.payload["type"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    let broadcast = json!({ "type": "broadcast", "msg_id": 2, "message": 1 });
+    assert_eq!(
+        types(handle(&mut node, request(node_id, broadcast))),
+        ["read", "broadcast_ok"]
+    );
+    assert!(handle(&mut node, [Event::tick()]).is_empty());
+    let unavailable = json!({ "type": "error", "in_reply_to": 0, "code": 11, "text": "busy" });
+    assert!(handle(&mut node, kv(unavailable)).is_empty());
+    assert_eq!(types(handle(&mut node, [Event::tick()])), ["read"]);
+    assert!(handle(&mut node, [Event::tick()]).is_empty());
+    let read_ok = json!({ "type": "read_ok", "in_reply_to": 1, "value": [5] });
+    handle(&mut node, kv(read_ok));
+    let output = handle(&mut node, [Event::tick()]);
+    assert_eq!(output[0].body.payload["type"], "write");
+    let mut value =
+        serde_json::from_value::<Vec<u64>>(output[0].body.payload["value"].clone()).unwrap();
+    value.sort();
+    assert_eq!(value, [1, 5]);
+}
@@ -0,0 +1,78 @@
+use gossip_glomers::config::Config;
+use std::path::Path;
+
+/// path of config fixture.
+fn fixture() -> &'static Path {
+    Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/gossip.toml"
+    ))
+}
+
+/// test knobs of config file override defaults, unset knobs keep fallback.
+#[test]
+fn test_config_fixture() {
+    let config = Config::read(fixture()).unwrap();
+    assert_eq!(config.tick_time, Some(50));
+    assert_eq!(config.tick_jitter, Some(10));
+    assert_eq!(config.force_tick, Some(false));
+    assert_eq!(config.gossip_mode.as_deref(), Some("both"));
+    assert_eq!(config.gossip_fanout, Some(3));
+    assert_eq!(config.counter_kv.as_deref(), Some("lin-kv"));
+    assert_eq!(config.cas_attempts, Some(1000));
+    assert_eq!(config.echo_extra, Some(true));
+    assert_eq!(config.reconcile_ticks, None);
+
+    let fallback = Config {
+        tick_time: Some(200),
+        reconcile_ticks: Some(5),
+        ..Config::default()
+    };
+    let config = config.or(fallback);
+    assert_eq!(config.tick_time, Some(50));
+    assert_eq!(config.reconcile_ticks, Some(5));
+}
+
+/// test invalid config is rejected with line of error.
+#[test]
+fn test_config_invalid() {
+    for (toml, line, error) in [
+        (
+            "tick_time = 1\ntick_speed = 1",
+            2,
+            "unknown field `tick_speed`",
+        ),
+        ("tick_time = \"fast\"", 1, "invalid type: string \"fast\""),
+        ("tick_time = -1", 1, "invalid value: integer `-1`"),
+        ("force_tick = 1", 1, "invalid type: integer `1`"),
+        ("[broadcast]", 1, "unknown field `broadcast`"),
+        ("gossip_mode = \"push", 1, "invalid basic string"),
+    ] {
+        let result = Config::parse(toml).unwrap_err();
+        assert!(
+            result.contains(&format!("line {line},")) && result.contains(error),
+            "{toml:?}: {result}"
+        );
+    }
+    assert!(Config::read(Path::new("missing.toml")).is_err());
+}
+
+/// test flags of environment ignore values other than `0`, `1`, `false` and `true`.
+#[test]
+fn test_config_env_flags() {
+    std::env::set_var("FORCE_TICK", "yes");
+    std::env::set_var("TICK_ADAPTIVE", "0");
+    std::env::set_var("ECHO_EXTRA", "true");
+    let config = Config::from_env();
+    assert_eq!(config.force_tick, None);
+    assert_eq!(config.tick_adaptive, Some(false));
+    assert_eq!(config.echo_extra, Some(true));
+}
+
+/// test comments and escapes of strings.
+#[test]
+fn test_config_strings() {
+    let config = Config::parse("# knobs\ncounter_kv = \"a#b\\\"c\" # trailing").unwrap();
+    assert_eq!(config.counter_kv.as_deref(), Some("a#b\"c"));
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+}
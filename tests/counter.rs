@@ -0,0 +1,361 @@
+use gossip_glomers::{
+    counter::{CounterRequest, CounterResponse, CounterState, EventHandler},
+    event::{inputs, Event},
+    init::InitRequest,
+    message::{ErrorCode, Message},
+};
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use std::sync::mpsc::channel;
+use testutil::MockKv;
+
+mod testutil;
+
+/// Counter node of two node cluster.
+fn node(node_id: &str) -> EventHandler {
+    EventHandler::new(InitRequest::Init {
+        node_id: node_id.into(),
+        node_ids: vec!["n1".into(), "n2".into()],
+    })
+    .start_id(0)
+}
+
+/// Run counter node in-process on events and return output.
+fn handle(
+    node: &mut EventHandler,
+    events: impl IntoIterator<Item = Event<CounterRequest>>,
+) -> String {
+    let (tick_tx, _tick_rx) = channel();
+    let mut output = Vec::new();
+    node.handle_events(events, tick_tx, &mut output);
+    String::from_utf8(output).unwrap()
+}
+
+/// test counter read, compare and swap cycle with ticks fed in order.
+#[test]
+fn test_counter_cycle() {
+    let events = inputs(
+        r#"
+        { "src": "c1", "dest": "n1", "body": { "type": "add", "delta": 10, "msg_id": 1 } }
+        "#
+        .as_bytes(),
+    )
+    .chain([Event::tick()])
+    .chain(inputs(
+        r#"
+        { "src": "seq-kv", "dest": "n1", "body": { "type": "read_ok", "msg_id": 7, "in_reply_to": 1, "value": 5 } }
+        { "src": "seq-kv", "dest": "n1", "body": { "type": "cas_ok", "msg_id": 8, "in_reply_to": 2 } }
+        { "src": "c1", "dest": "n1", "body": { "type": "read", "msg_id": 2 } }
+        "#
+        .as_bytes(),
+    ))
+    .chain([Event::tick()]);
+    let output = concat!(
+        r#"{"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"add_ok"}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":1,"in_reply_to":null,"type":"read","key":"COUNTER"}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":2,"in_reply_to":7,"type":"cas","key":"COUNTER","from":5,"to":15,"create_if_not_exists":false}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"c1","body":{"msg_id":3,"in_reply_to":2,"type":"read_ok","value":15}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":4,"in_reply_to":null,"type":"read","key":"COUNTER"}}"#,
+        "\n",
+    );
+    assert_eq!(handle(&mut node("n1"), events), output);
+}
+
+/// test two counter nodes incrementing through key store converge to the sum.
+#[test]
+fn test_counter_mock_kv() {
+    let mut kv = MockKv::default();
+    let mut nodes = [node("n1"), node("n2")];
+    let mut pending: [Vec<_>; 2] = ["n1", "n2"].map(|node_id| {
+        let add = format!(
+            r#"{{ "src": "c1", "dest": "{node_id}", "body": {{ "type": "add", "delta": 3, "msg_id": 1 }} }}"#
+        );
+        inputs(add.as_bytes()).collect()
+    });
+    for _ in 0..10 {
+        for (node, pending) in nodes.iter_mut().zip(pending.iter_mut()) {
+            let events: Vec<_> = pending.drain(..).chain([Event::tick()]).collect();
+            for line in handle(node, events).lines() {
+                let message: Message<Value> = serde_json::from_str(line).unwrap();
+                if MockKv::is_kv(&message.dst) {
+                    let reply = serde_json::to_string(&kv.handle(message)).unwrap();
+                    pending.extend(inputs(reply.as_bytes()));
+                }
+            }
+        }
+    }
+    for (node, node_id) in nodes.iter_mut().zip(["n1", "n2"]) {
+        let read = format!(
+            r#"{{ "src": "c1", "dest": "{node_id}", "body": {{ "type": "read", "msg_id": 2 }} }}"#
+        );
+        let output = handle(node, inputs(read.as_bytes()));
+        let reply: Message<Value> = serde_json::from_str(&output).unwrap();
+        assert_eq!(reply.body.payload["value"], 6, "{node_id}");
+    }
+}
+
+/// test counter node reads fresh value when compare and swap fails.
+#[test]
+fn test_counter_reread() {
+    let events = inputs(
+        r#"
+        { "src": "c1", "dest": "n1", "body": { "type": "add", "delta": 10, "msg_id": 1 } }
+        "#
+        .as_bytes(),
+    )
+    .chain([Event::tick()])
+    .chain(inputs(
+        r#"
+        { "src": "seq-kv", "dest": "n1", "body": { "type": "read_ok", "msg_id": 1, "in_reply_to": 1, "value": 5 } }
+        { "src": "seq-kv", "dest": "n1", "body": { "type": "error", "msg_id": 2, "in_reply_to": 2, "code": 22, "text": "expected 5" } }
+        { "src": "seq-kv", "dest": "n1", "body": { "type": "read_ok", "msg_id": 3, "in_reply_to": 3, "value": 7 } }
+        "#
+        .as_bytes(),
+    ));
+    let output = concat!(
+        r#"{"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"add_ok"}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":1,"in_reply_to":null,"type":"read","key":"COUNTER"}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":2,"in_reply_to":1,"type":"cas","key":"COUNTER","from":5,"to":15,"create_if_not_exists":false}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":3,"in_reply_to":2,"type":"read","key":"COUNTER"}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":4,"in_reply_to":3,"type":"cas","key":"COUNTER","from":7,"to":17,"create_if_not_exists":false}}"#,
+        "\n",
+    );
+    assert_eq!(handle(&mut node("n1"), events), output);
+}
+
+/// test counter node reverts decrementing update on failure.
+#[test]
+fn test_counter_revert() {
+    let events = inputs(
+        r#"
+        { "src": "c1", "dest": "n1", "body": { "type": "add", "delta": -5, "msg_id": 1 } }
+        "#
+        .as_bytes(),
+    )
+    .chain([Event::tick()])
+    .chain(inputs(
+        r#"
+        { "src": "seq-kv", "dest": "n1", "body": { "type": "read_ok", "msg_id": 1, "in_reply_to": 1, "value": 10 } }
+        { "src": "seq-kv", "dest": "n1", "body": { "type": "error", "msg_id": 2, "in_reply_to": 2, "code": 22, "text": "expected 10" } }
+        { "src": "c1", "dest": "n1", "body": { "type": "read", "msg_id": 2 } }
+        "#
+        .as_bytes(),
+    ));
+    let output = concat!(
+        r#"{"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"add_ok"}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":1,"in_reply_to":null,"type":"read","key":"COUNTER"}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":2,"in_reply_to":1,"type":"cas","key":"COUNTER","from":10,"to":5,"create_if_not_exists":false}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":3,"in_reply_to":2,"type":"read","key":"COUNTER"}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"c1","body":{"msg_id":4,"in_reply_to":2,"type":"read_ok","value":5}}"#,
+        "\n",
+    );
+    assert_eq!(handle(&mut node("n1"), events), output);
+}
+
+/// test counter node ignores replies from other services.
+#[test]
+fn test_counter_other_service() {
+    let events = [Event::tick()].into_iter().chain(inputs(
+        r#"
+        { "src": "lin-kv", "dest": "n1", "body": { "type": "read_ok", "in_reply_to": 0, "value": 3 } }
+        { "src": "seq-kv", "dest": "n1", "body": { "type": "read_ok", "in_reply_to": 0, "value": 5 } }
+        { "src": "c1", "dest": "n1", "body": { "type": "read", "msg_id": 1 } }
+        "#
+        .as_bytes(),
+    ));
+    let output = concat!(
+        r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":0,"in_reply_to":null,"type":"read","key":"COUNTER"}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"c1","body":{"msg_id":1,"in_reply_to":1,"type":"read_ok","value":5}}"#,
+        "\n",
+    );
+    assert_eq!(handle(&mut node("n1"), events), output);
+}
+
+/// Value read from counter state.
+fn value(state: &CounterState) -> i64 {
+    match state.on_read() {
+        CounterResponse::ReadOk { value } => value,
+        response => panic!("expected read ok, got {response:?}"),
+    }
+}
+
+/// test failed update is moved back to delta and retried from fresh value.
+#[test]
+fn test_counter_state_revert() {
+    let mut state = CounterState::default();
+    state.on_add(10);
+    state.read(0);
+    assert!(matches!(
+        state.on_read_ok(5, Some(0), 1),
+        Some(CounterResponse::UpdateCounter {
+            old: 5,
+            new: 15,
+            ..
+        })
+    ));
+    assert_eq!(value(&state), 15);
+    let (response, reverted) = state.on_error(ErrorCode::PreconditionFailed, Some(1), 2);
+    assert!(reverted);
+    assert!(matches!(
+        response,
+        Some(CounterResponse::ReadCounter { .. })
+    ));
+    assert_eq!(value(&state), 15);
+    assert!(matches!(
+        state.on_read_ok(7, Some(2), 3),
+        Some(CounterResponse::UpdateCounter {
+            old: 7,
+            new: 17,
+            ..
+        })
+    ));
+    state.on_cas_ok(Some(3));
+    assert_eq!(value(&state), 17);
+    assert!(state.pending_update().is_none());
+}
+
+/// test error of other request does not revert pending update, which would count it twice.
+#[test]
+fn test_counter_state_unrelated_error() {
+    let mut state = CounterState::default();
+    state.on_add(10);
+    state.read(0);
+    state.on_read_ok(5, Some(0), 1);
+    let (_, reverted) = state.on_error(ErrorCode::Timeout, Some(7), 2);
+    assert!(!reverted);
+    assert!(matches!(state.pending_update(), Some((1, _))));
+    state.on_cas_ok(Some(1));
+    state.read(3);
+    assert!(state.on_read_ok(15, Some(3), 4).is_none());
+    assert_eq!(value(&state), 15);
+}
+
+/// test stale replies to superseded requests leave state unchanged.
+#[test]
+fn test_counter_state_stale_replies() {
+    let mut state = CounterState::default();
+    state.on_add(10);
+    state.read(0);
+    state.read(1);
+    state.on_read_ok(5, Some(1), 2);
+    state.on_cas_ok(Some(1));
+    state.on_cas_ok(None);
+    assert!(matches!(state.pending_update(), Some((2, _))));
+    assert!(state.on_read_ok(3, Some(0), 4).is_none());
+    assert!(state.on_read_ok(3, None, 4).is_none());
+    assert!(matches!(
+        state.pending_update(),
+        Some((
+            2,
+            CounterResponse::UpdateCounter {
+                old: 5,
+                new: 15,
+                ..
+            }
+        ))
+    ));
+    assert_eq!(value(&state), 15);
+}
+
+/// test reverting update which overflows delta drops the update.
+#[test]
+fn test_counter_state_revert_overflow() {
+    let mut state = CounterState::default();
+    state.on_add(i64::MAX);
+    state.read(0);
+    state.on_read_ok(0, Some(0), 1);
+    state.on_add(1);
+    let (_, reverted) = state.on_error(ErrorCode::PreconditionFailed, Some(1), 2);
+    assert!(reverted);
+    assert_eq!(value(&state), 1);
+}
+
+/// test verification read detects key store not holding acknowledged update.
+#[test]
+fn test_counter_state_verify() {
+    let mut state = CounterState::default();
+    state.on_add(10);
+    state.read(0);
+    state.on_read_ok(5, Some(0), 1);
+    assert_eq!(state.on_cas_ok(Some(1)), Some(15));
+    assert!(matches!(
+        state.verify(15, 2),
+        CounterResponse::ReadCounter { .. }
+    ));
+    assert_eq!(state.on_verify_ok(15, Some(0)), None);
+    assert_eq!(state.on_verify_ok(16, Some(2)), Some(false));
+    assert_eq!(state.on_verify_ok(16, Some(2)), None);
+    assert_eq!(state.discrepancies(), 1);
+    state.verify(15, 3);
+    assert_eq!(state.on_verify_ok(15, Some(3)), Some(true));
+    assert_eq!(state.discrepancies(), 1);
+}
+
+/// test verifying node detects key store acknowledging update it did not store.
+#[test]
+fn test_counter_verify_cas() {
+    for (corrupt, detected) in [(0, false), (1, true)] {
+        let mut kv = MockKv::default().corrupt_cas(corrupt);
+        let mut node = node("n1").verify(true);
+        let add =
+            r#"{ "src": "c1", "dest": "n1", "body": { "type": "add", "delta": 3, "msg_id": 1 } }"#;
+        let mut pending: Vec<_> = inputs(add.as_bytes()).collect();
+        for _ in 0..5 {
+            let events: Vec<_> = pending.drain(..).chain([Event::tick()]).collect();
+            for line in handle(&mut node, events).lines() {
+                let message: Message<Value> = serde_json::from_str(line).unwrap();
+                if MockKv::is_kv(&message.dst) {
+                    let reply = serde_json::to_string(&kv.handle(message)).unwrap();
+                    pending.extend(inputs(reply.as_bytes()));
+                }
+            }
+        }
+        assert_eq!(node.discrepancies() > 0, detected, "corrupt {corrupt}");
+    }
+}
+
+/// test sharded node keeps only latest read of each shard, dropping reply to older read.
+#[test]
+fn test_counter_sharded_stale_read() {
+    let mut node = node("n1").sharded(&["n1", "n2"]);
+    let events = [Event::tick(), Event::tick()].into_iter().chain(inputs(
+        r#"
+        { "src": "seq-kv", "dest": "n1", "body": { "type": "read_ok", "in_reply_to": 1, "value": 3 } }
+        { "src": "c1", "dest": "n1", "body": { "type": "read", "msg_id": 1 } }
+        { "src": "seq-kv", "dest": "n1", "body": { "type": "read_ok", "in_reply_to": 2, "value": 4 } }
+        { "src": "c1", "dest": "n1", "body": { "type": "read", "msg_id": 2 } }
+        "#
+        .as_bytes(),
+    ));
+    let output = handle(&mut node, events);
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(
+        lines[..3],
+        [
+            r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":0,"in_reply_to":null,"type":"write","key":"COUNTER/n1","value":0}}"#,
+            r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":1,"in_reply_to":null,"type":"read","key":"COUNTER/n2"}}"#,
+            r#"{"src":"n1","dest":"seq-kv","body":{"msg_id":2,"in_reply_to":null,"type":"read","key":"COUNTER/n2"}}"#,
+        ]
+    );
+    assert!(
+        lines[3].contains(r#""type":"read_ok","value":0"#),
+        "{output}"
+    );
+    assert!(
+        lines[4].contains(r#""type":"read_ok","value":4"#),
+        "{output}"
+    );
+}
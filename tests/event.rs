@@ -0,0 +1,132 @@
+use gossip_glomers::event::{
+    input_recv, tick_after, ticker, AdaptiveTick, Backoff, Event, MIN_INTERVAL,
+};
+use std::{
+    sync::mpsc::{channel, sync_channel},
+    time::Duration,
+};
+
+/// Interval long enough for ticks in test to be only force ticks.
+const NEVER: u64 = 60_000;
+
+/// test rapid force ticks collapse into one tick and the last one is not lost.
+#[test]
+fn test_force_ticks_coalesce() {
+    let (event_tx, event_rx) = sync_channel::<Event<()>>(16);
+    let (tick_tx, tick_rx) = channel();
+    std::thread::spawn(move || ticker(event_tx, tick_rx, NEVER));
+    for _ in 0..3 {
+        tick_tx.send(()).unwrap();
+    }
+    std::thread::sleep(Duration::from_millis(100));
+    let ticks = event_rx.try_iter().collect::<Vec<_>>();
+    assert_eq!(ticks.len(), 1);
+    assert!(matches!(ticks[0], Event::Tick(_)));
+    drop(ticks);
+
+    tick_tx.send(()).unwrap();
+    assert!(matches!(
+        event_rx.recv_timeout(Duration::from_secs(1)),
+        Ok(Event::Tick(_))
+    ));
+}
+
+/// test closing input stops handler and ticker without panic.
+#[test]
+fn test_shutdown() {
+    let (event_tx, event_rx) = sync_channel::<Event<()>>(16);
+    let (tick_tx, tick_rx) = channel();
+    let ticker = std::thread::spawn({
+        let event_tx = event_tx.clone();
+        move || ticker(event_tx, tick_rx, 1)
+    });
+    let handler = std::thread::spawn(move || {
+        let _tick_tx = tick_tx;
+        for event in event_rx.iter() {
+            if let Event::Close = event {
+                break;
+            }
+        }
+    });
+    std::thread::sleep(Duration::from_millis(20));
+    // input is closed.
+    event_tx.send(Event::Close).unwrap();
+    drop(event_tx);
+    handler.join().expect("handler should not panic");
+    ticker.join().expect("ticker should not panic");
+}
+
+/// test ticker returns once event receiver is dropped.
+#[test]
+fn test_ticker_receiver_dropped() {
+    let (event_tx, event_rx) = sync_channel::<Event<()>>(16);
+    let (tick_tx, tick_rx) = channel();
+    drop(event_rx);
+    let ticker = std::thread::spawn(move || ticker(event_tx, tick_rx, NEVER));
+    tick_tx.send(()).unwrap();
+    ticker.join().expect("ticker should not panic");
+}
+
+/// test input reader blocks on full channel instead of queueing all input.
+#[test]
+fn test_input_backpressure() {
+    let input = r#"{ "src": "c1", "dest": "n1", "body": { "type": "ping", "msg_id": 1 } }
+"#
+    .repeat(100);
+    let (event_tx, event_rx) = sync_channel::<Event<serde_json::Value>>(2);
+    let reader = std::thread::spawn(move || input_recv(input.as_bytes(), event_tx));
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(!reader.is_finished());
+    let events = event_rx.iter().collect::<Vec<_>>();
+    assert!(matches!(events.last(), Some(Event::Close)));
+    assert_eq!(events.len(), 100 + 1);
+    reader.join().unwrap();
+}
+
+/// test backoff delays double up to max factor and stop after max attempts.
+#[test]
+fn test_backoff_cap() {
+    let base = Duration::from_millis(1);
+    let mut backoff = Backoff::new(base, 10);
+    let delays = std::iter::from_fn(|| backoff.next_delay()).collect::<Vec<_>>();
+    assert_eq!(delays.len(), 10);
+    assert!(delays.windows(2).all(|delay| delay[0] <= delay[1]));
+    assert_eq!(
+        delays[..7],
+        [1, 2, 4, 8, 16, 32, 64].map(Duration::from_millis)
+    );
+    assert!(delays[7..]
+        .iter()
+        .all(|delay| *delay == base * Backoff::MAX_FACTOR));
+
+    let mut backoff = Backoff::new(Duration::MAX, 2);
+    assert_eq!(backoff.next_delay(), Some(Duration::MAX));
+    assert_eq!(backoff.next_delay(), Some(Duration::MAX));
+    assert_eq!(backoff.next_delay(), None);
+
+    assert_eq!(Backoff::new(base, 0).next_delay(), None);
+}
+
+/// test force tick is sent after delay.
+#[test]
+fn test_tick_after() {
+    let (tick_tx, tick_rx) = channel();
+    tick_after(tick_tx, Duration::from_millis(20));
+    assert!(tick_rx.try_recv().is_err());
+    assert!(tick_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+}
+
+/// test adaptive interval with zero min stays above floor and grows back.
+#[test]
+fn test_adaptive_tick_floor() {
+    let tick = AdaptiveTick::new(
+        Duration::from_millis(8),
+        Duration::ZERO,
+        Duration::from_secs(1),
+    );
+    for _ in 0..10 {
+        tick.observe(10, 1);
+    }
+    assert_eq!(tick.interval(), MIN_INTERVAL);
+    assert!(tick.observe(0, 0) > MIN_INTERVAL);
+}
@@ -1,37 +1,41 @@
 use pretty_assertions::assert_eq;
 use std::{
-    env::var,
     io::Write,
     ops::Not,
     process::{Command, Stdio},
+    time::Duration,
 };
-
-/// Builds the binary using cargo for testing.
-fn build(release: bool, bin_name: &str) -> String {
-    let mut args = vec!["build", "--bin", bin_name];
-    let profile = if release {
-        args.push("--release");
-        "release"
-    } else {
-        "debug"
-    };
-    let status = Command::new("cargo")
-        .args(&args)
-        .status()
-        .expect("failed to build!");
-    assert!(status.success());
-    format!(
-        "{}/{}/{}",
-        var("CARGO_TARGET_DIR").unwrap_or("target".to_string()),
-        profile,
-        bin_name
-    )
-}
+use xtask::challange::build;
 
 /// Build and run binary with input and assert output.
 pub fn run_test(bin: &str, input: &str, output: &str) {
-    let path = build(false, bin);
-    let expected_output: String = output
+    run_test_with_env(bin, &[], input, output);
+}
+
+/// Build and run binary with environment variables and input and assert output.
+pub fn run_test_with_env(bin: &str, envs: &[(&str, &str)], input: &str, output: &str) {
+    let expected_output = trim_lines(output);
+    let output = run(bin, envs, input);
+    assert_eq!(output, expected_output, "{input}");
+}
+
+/// Build and run binary with environment variables and input and assert output.
+///
+/// Messages which are not reply to any request are ignored,
+/// as they are sent on timer and race with input.
+pub fn run_test_ignoring_ticks(bin: &str, envs: &[(&str, &str)], input: &str, output: &str) {
+    let expected_output = trim_lines(output);
+    let output: String = run(bin, envs, input)
+        .lines()
+        .filter(|line| !line.contains(r#""in_reply_to":null"#))
+        .map(|line| format!("{line}\n"))
+        .collect();
+    assert_eq!(output, expected_output, "{input}");
+}
+
+/// Trim each line and remove empty lines.
+fn trim_lines(output: &str) -> String {
+    output
         .lines()
         .filter_map(|x| {
             x.trim()
@@ -39,21 +43,80 @@ pub fn run_test(bin: &str, input: &str, output: &str) {
                 .not()
                 .then_some(format!("{}\n", x.trim()))
         })
-        .collect();
+        .collect()
+}
+
+/// Build and run binary with environment variables and input and return output.
+fn run(bin: &str, envs: &[(&str, &str)], input: &str) -> String {
+    run_paused(bin, envs, &[input], Duration::ZERO)
+}
+
+/// Build and run binary with environment variables and inputs and return output.
+///
+/// The binary is paused for given duration between inputs to allow timer based events.
+/// Message ids start from `0`, see [first_id](gossip_glomers::message::first_id).
+fn run_paused(bin: &str, envs: &[(&str, &str)], inputs: &[&str], pause: Duration) -> String {
+    let path = build(false, bin).expect("failed to build!");
     let mut child = Command::new(path)
+        .env("MSG_ID_START", "0")
+        .envs(envs.iter().copied())
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .spawn()
         .expect("Failed to execute command");
-    child
-        .stdin
-        .as_mut()
-        .unwrap()
-        .write_all(input.as_bytes())
-        .unwrap();
+    let stdin = child.stdin.as_mut().unwrap();
+    for (i, input) in inputs.iter().enumerate() {
+        if i > 0 {
+            std::thread::sleep(pause);
+        }
+        stdin.write_all(input.as_bytes()).unwrap();
+        stdin.flush().unwrap();
+    }
     let stdout = child.wait_with_output().unwrap().stdout;
-    let output = String::from_utf8_lossy(&stdout);
-    assert_eq!(output, expected_output, "{input}");
+    String::from_utf8_lossy(&stdout).into_owned()
+}
+
+/// test echo node skips malformed input and processes valid input.
+#[test]
+fn test_echo_malformed() {
+    let input = r#"
+    { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }
+    { "src": "c1", "dest": "n1", "body": { "type": "echo", "msg_id": 1
+    { "src": "c1", "dest": "n1", "body": { "type": "echo", "msg_id": 2, "echo": "Please echo 35" } }
+    "#;
+    let output = r#"
+    {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":2,"type":"echo_ok","echo":"Please echo 35"}}
+    "#;
+    run_test("echo", input, output);
+}
+
+/// test logged warning fails node at shutdown only in strict mode.
+#[test]
+fn test_strict_warnings() {
+    let input = r#"
+    { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1"] } }
+    { "src": "c1", "dest": "n1", "body": { "type": "echo", "msg_id": 1
+    "#;
+    let status = |envs: &[(&str, &str)]| {
+        let path = build(false, "echo").expect("failed to build!");
+        let mut child = Command::new(path)
+            .envs(envs.iter().copied())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to execute command");
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        child.wait().unwrap()
+    };
+    assert!(status(&[]).success());
+    assert_eq!(status(&[("GG_STRICT_WARNINGS", "1")]).code(), Some(1));
 }
 
 /// test echo node input and output.
@@ -70,6 +133,87 @@ fn test_echo() {
     run_test("echo", input, output);
 }
 
+/// test nodes sharing tee output path both keep their output.
+#[test]
+fn test_tee_shared_output() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("tee-shared.jsonl");
+    let _ = std::fs::remove_file(&path);
+    let envs = [("GG_TEE_OUT", path.to_str().unwrap())];
+    let init = |node| {
+        format!(
+            r#"{{ "src": "c1", "dest": "{node}", "body": {{ "msg_id": 1, "type": "init", "node_id": "{node}", "node_ids": ["n1", "n2"] }} }}"#
+        ) + "\n"
+    };
+    let outputs = ["n1", "n2"].map(|node| run("echo", &envs, &init(node)));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        outputs.concat(),
+        "output of a node was overwritten"
+    );
+}
+
+/// test echo node replies after delay when set.
+#[test]
+fn test_echo_delay() {
+    let input = r#"
+    { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1"] } }
+    { "src": "c1", "dest": "n1", "body": { "type": "echo", "msg_id": 1, "echo": "slow" } }
+    { "src": "c1", "dest": "n1", "body": { "type": "echo", "msg_id": 2, "echo": "slower" } }
+    "#;
+    let output = r#"
+    {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"echo_ok","echo":"slow"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":1,"in_reply_to":2,"type":"echo_ok","echo":"slower"}}
+    "#;
+    let start = std::time::Instant::now();
+    run_test_with_env("echo", &[("ECHO_DELAY_MS", "150")], input, output);
+    assert!(
+        start.elapsed() >= Duration::from_millis(300),
+        "{:?}",
+        start.elapsed()
+    );
+}
+
+/// test echo node echoes extra fields when enabled.
+#[test]
+fn test_echo_extra() {
+    let input = r#"
+    { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }
+    { "src": "c1", "dest": "n1", "body": { "type": "echo", "msg_id": 1, "echo": "hi", "custom": 42 } }
+    "#;
+    let output = r#"
+    {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"echo_ok","echo":"hi","custom":42}}
+    "#;
+    run_test_with_env("echo", &[("ECHO_EXTRA", "1")], input, output);
+    let output = r#"
+    {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"echo_ok","echo":"hi"}}
+    "#;
+    run_test("echo", input, output);
+}
+
+/// test knobs of config file take precedence over environment.
+#[test]
+fn test_echo_config() {
+    let input = r#"
+    { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }
+    { "src": "c1", "dest": "n1", "body": { "type": "echo", "msg_id": 1, "echo": "hi", "custom": 42 } }
+    "#;
+    let output = r#"
+    {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"echo_ok","echo":"hi","custom":42}}
+    "#;
+    let config = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/gossip.toml");
+    let envs = [("GOSSIP_CONFIG", config), ("ECHO_EXTRA", "0")];
+    run_test_with_env("echo", &envs, input, output);
+    let output = r#"
+    {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"echo_ok","echo":"hi"}}
+    "#;
+    run_test("echo", input, output);
+}
+
 /// test unique id node input and output.
 #[test]
 fn test_unique_id() {
@@ -77,11 +221,119 @@ fn test_unique_id() {
     { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }
     { "src": "c1", "dest": "n1", "body": { "type": "generate", "msg_id": 1 } }
     "#;
+    let output = run("unique_ids", &[], input);
+    let mut lines = output.lines();
+    assert_eq!(
+        lines.next(),
+        Some(r#"{"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}"#)
+    );
+    let ids = generated_ids(lines);
+    assert_eq!(ids.len(), 1);
+    assert_eq!(ids[0] % 2, 0);
+}
+
+/// Parse generated ids from output lines, ids rendered as string are parsed too.
+fn generated_ids<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<u64> {
+    lines
+        .map(|line| {
+            let message: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(message["body"]["type"], "generate_ok");
+            let id = &message["body"]["id"];
+            id.as_u64()
+                .or_else(|| id.as_str().and_then(|id| id.parse().ok()))
+                .unwrap()
+        })
+        .collect()
+}
+
+/// test restarted unique id node does not reissue ids.
+#[test]
+fn test_unique_id_restart() {
+    let mut input = String::from(
+        r#"{ "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }"#,
+    );
+    for msg_id in 0..100 {
+        input += &format!(
+            r#"
+            {{ "src": "c1", "dest": "n1", "body": {{ "type": "generate", "msg_id": {msg_id} }} }}"#
+        );
+    }
+    let first = generated_ids(run("unique_ids", &[], &input).lines().skip(1));
+    let restarted = generated_ids(run("unique_ids", &[], &input).lines().skip(1));
+    assert_eq!(first.len(), 100);
+    assert!(
+        restarted.iter().all(|id| !first.contains(id)),
+        "{first:?} {restarted:?}"
+    );
+}
+
+/// test snowflake ids are strictly increasing within a node and carry node index.
+#[test]
+fn test_unique_id_snowflake() {
+    let mut input = String::from(
+        r#"{ "src": "c1", "dest": "n2", "body": { "msg_id": 1, "type": "init", "node_id": "n2", "node_ids": ["n1", "n2"] } }"#,
+    );
+    for msg_id in 0..100 {
+        input += &format!(
+            r#"
+            {{ "src": "c1", "dest": "n2", "body": {{ "type": "generate", "msg_id": {msg_id} }} }}"#
+        );
+    }
+    let output = run("unique_ids", &[("ID_FORMAT", "snowflake")], &input);
+    assert!(
+        output.contains(r#""type":"generate_ok","id":""#),
+        "{output}"
+    );
+    let ids = generated_ids(output.lines().skip(1));
+    assert_eq!(ids.len(), 100);
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]), "{ids:?}");
+    assert!(ids.iter().all(|id| (id >> 12) & 0x3ff == 1), "{ids:?}");
+}
+
+/// test unique id nodes reserve non overlapping blocks from key store.
+#[test]
+fn test_unique_id_kv() {
+    let input = r#"
+    { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }
+    { "src": "c1", "dest": "n1", "body": { "type": "generate", "msg_id": 1 } }
+    { "src": "lin-kv", "dest": "n1", "body": { "type": "cas_ok", "in_reply_to": 0 } }
+    "#;
     let output = r#"
     {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
-    {"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"generate_ok","id":0}}
+    {"src":"n1","dest":"lin-kv","body":{"msg_id":0,"in_reply_to":null,"type":"cas","key":"ID_BLOCK","from":0,"to":1000,"create_if_not_exists":true}}
+    {"src":"n1","dest":"c1","body":{"msg_id":1,"in_reply_to":1,"type":"generate_ok","id":0}}
     "#;
-    run_test("unique_ids", input, output);
+    run_test_with_env("unique_ids", &[("ID_MODE", "kv")], input, output);
+    // failed reservation is retried after jitter, hence key store replies are paused.
+    for (code, text) in [(22, "expected 0"), (11, "unavailable")] {
+        let inputs = [
+            r#"
+            { "src": "c1", "dest": "n2", "body": { "msg_id": 1, "type": "init", "node_id": "n2", "node_ids": ["n1", "n2"] } }
+            { "src": "c1", "dest": "n2", "body": { "type": "generate", "msg_id": 1 } }
+            "#.to_string(),
+            format!(
+                r#"{{ "src": "lin-kv", "dest": "n2", "body": {{ "type": "error", "in_reply_to": 0, "code": {code}, "text": "{text}" }} }}"#
+            ),
+            r#"{ "src": "lin-kv", "dest": "n2", "body": { "type": "read_ok", "in_reply_to": 1, "value": 1000 } }"#.to_string(),
+            r#"{ "src": "lin-kv", "dest": "n2", "body": { "type": "cas_ok", "in_reply_to": 2 } }"#.to_string(),
+        ]
+        .map(|input| input + "\n");
+        let inputs = inputs.iter().map(String::as_str).collect::<Vec<_>>();
+        let output = r#"
+        {"src":"n2","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
+        {"src":"n2","dest":"lin-kv","body":{"msg_id":0,"in_reply_to":null,"type":"cas","key":"ID_BLOCK","from":0,"to":1000,"create_if_not_exists":true}}
+        {"src":"n2","dest":"lin-kv","body":{"msg_id":1,"in_reply_to":null,"type":"read","key":"ID_BLOCK"}}
+        {"src":"n2","dest":"lin-kv","body":{"msg_id":2,"in_reply_to":null,"type":"cas","key":"ID_BLOCK","from":1000,"to":2000,"create_if_not_exists":true}}
+        {"src":"n2","dest":"c1","body":{"msg_id":3,"in_reply_to":1,"type":"generate_ok","id":1000}}
+        "#;
+        let envs = [("ID_MODE", "kv")];
+        let pause = Duration::from_millis(200);
+        assert_eq!(
+            run_paused("unique_ids", &envs, &inputs, pause),
+            trim_lines(output),
+            "{code}"
+        );
+    }
 }
 
 /// test broadcast node input and output.
@@ -102,25 +354,122 @@ fn test_broadcast() {
     run_test("broadcast", input, output);
 }
 
-/// test g-counter node input and output.
+/// test broadcast node with multiple messages input and output.
 #[test]
-#[ignore = "This has race condition"]
-fn test_g_counter() {
+fn test_broadcast_messages() {
     let input = r#"
     { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }
-    { "src": "c1", "dest": "n1", "body": { "type": "add", "delta": 10, "msg_id": 1 } }
+    { "src": "c1", "dest": "n1", "body": { "type": "broadcast", "messages": [1, 2, 3], "msg_id": 1 } }
     { "src": "c1", "dest": "n1", "body": { "type": "read", "msg_id": 2 } }
-    { "src": "seq-kv", "dest": "n1", "body": { "type": "read_ok", "msg_id": 1, "value": 10 } }
-    { "src": "seq-kv", "dest": "n1", "body": { "type": "cas_ok", "msg_id": 2 } }
+    "#;
+    let output = r#"
+    {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"broadcast_ok"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":1,"in_reply_to":2,"type":"read_ok","messages":[1,2,3]}}
+    "#;
+    run_test("broadcast", input, output);
+}
+
+/// test broadcast node reads sorted messages when enabled.
+#[test]
+fn test_broadcast_sorted() {
+    let input = r#"
+    { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1"] } }
+    { "src": "c1", "dest": "n1", "body": { "type": "broadcast", "messages": [30, 5, 1000, 17, 2], "msg_id": 1 } }
+    { "src": "c1", "dest": "n1", "body": { "type": "read", "msg_id": 2 } }
+    "#;
+    let output = r#"
+    {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"broadcast_ok"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":1,"in_reply_to":2,"type":"read_ok","messages":[2,5,17,30,1000]}}
+    "#;
+    run_test_with_env("broadcast", &[("BROADCAST_SORTED", "1")], input, output);
+}
+
+/// test broadcast node recovers messages from checkpoint.
+#[test]
+fn test_broadcast_persist() {
+    let input = r#"
+    { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }
+    { "src": "lin-kv", "dest": "n1", "body": { "type": "read_ok", "in_reply_to": 0, "value": [1, 2] } }
+    { "src": "c1", "dest": "n1", "body": { "type": "read", "msg_id": 1 } }
+    "#;
+    let output = r#"
+    {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
+    {"src":"n1","dest":"lin-kv","body":{"msg_id":0,"in_reply_to":null,"type":"read","key":"broadcast/n1"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":1,"in_reply_to":1,"type":"read_ok","messages":[1,2]}}
+    "#;
+    run_test_with_env("broadcast", &[("BROADCAST_PERSIST", "1")], input, output);
+    let input = r#"
+    { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }
+    { "src": "lin-kv", "dest": "n1", "body": { "type": "error", "in_reply_to": 0, "code": 20, "text": "not found" } }
+    { "src": "c1", "dest": "n1", "body": { "type": "read", "msg_id": 1 } }
+    "#;
+    let output = r#"
+    {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
+    {"src":"n1","dest":"lin-kv","body":{"msg_id":0,"in_reply_to":null,"type":"read","key":"broadcast/n1"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":1,"in_reply_to":1,"type":"read_ok","messages":[]}}
+    "#;
+    run_test_with_env("broadcast", &[("BROADCAST_PERSIST", "1")], input, output);
+}
+
+/// test counter node decrements on negative delta.
+#[test]
+fn test_counter_decrement() {
+    let input = r#"
+    { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }
+    { "src": "c1", "dest": "n1", "body": { "type": "add", "delta": 10, "msg_id": 1 } }
+    { "src": "c1", "dest": "n1", "body": { "type": "add", "delta": -3, "msg_id": 2 } }
     { "src": "c1", "dest": "n1", "body": { "type": "read", "msg_id": 3 } }
     "#;
     let output = r#"
     {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
     {"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"add_ok"}}
-    {"src":"n1","dest":"c1","body":{"msg_id":1,"in_reply_to":2,"type":"read_ok","value":10}}
-    {"src":"n1","dest":"seq-kv","body":{"msg_id":2,"in_reply_to":null,"type":"read","key":"COUNTER"}}
-    {"src":"n1","dest":"seq-kv","body":{"msg_id":3,"in_reply_to":1,"type":"cas","key":"COUNTER","from":10,"to":20,"create_if_not_exists":false}}
-    {"src":"n1","dest":"c1","body":{"msg_id":4,"in_reply_to":3,"type":"read_ok","value":20}}
+    {"src":"n1","dest":"c1","body":{"msg_id":1,"in_reply_to":2,"type":"add_ok"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":2,"in_reply_to":3,"type":"read_ok","value":7}}
+    "#;
+    run_test_ignoring_ticks("g_counter", &[], input, output);
+}
+
+/// test counter node uses linearizable key store when configured.
+#[test]
+fn test_counter_lin_kv() {
+    let inputs = [
+        r#"
+        { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }
+        "#,
+        "",
+    ];
+    let envs = [("COUNTER_KV", "lin-kv"), ("TICK_TIME", "100")];
+    let output = run_paused("g_counter", &envs, &inputs, Duration::from_millis(250));
+    assert!(
+        output.contains(r#"{"src":"n1","dest":"lin-kv","body":{"msg_id":0,"in_reply_to":null,"type":"read","key":"COUNTER"}}"#),
+        "{output}"
+    );
+}
+
+/// test sharded counter node sums shards of all nodes.
+#[test]
+fn test_counter_sharded() {
+    let inputs = [
+        r#"
+        { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }
+        { "src": "c1", "dest": "n1", "body": { "type": "add", "delta": 5, "msg_id": 1 } }
+        "#,
+        r#"
+        { "src": "seq-kv", "dest": "n1", "body": { "type": "write_ok", "in_reply_to": 1 } }
+        { "src": "seq-kv", "dest": "n1", "body": { "type": "read_ok", "in_reply_to": 2, "value": 3 } }
+        { "src": "c1", "dest": "n1", "body": { "type": "read", "msg_id": 2 } }
+        "#,
+    ];
+    let output = r#"
+    {"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":1,"type":"add_ok"}}
+    {"src":"n1","dest":"seq-kv","body":{"msg_id":1,"in_reply_to":null,"type":"write","key":"COUNTER/n1","value":5}}
+    {"src":"n1","dest":"seq-kv","body":{"msg_id":2,"in_reply_to":null,"type":"read","key":"COUNTER/n2"}}
+    {"src":"n1","dest":"c1","body":{"msg_id":3,"in_reply_to":2,"type":"read_ok","value":8}}
     "#;
-    run_test("g_counter", input, output);
+    let envs = [("COUNTER_SHARDED", "1"), ("TICK_TIME", "500")];
+    let actual_output = run_paused("g_counter", &envs, &inputs, Duration::from_millis(750));
+    assert_eq!(actual_output, trim_lines(output), "{inputs:?}");
 }
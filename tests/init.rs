@@ -0,0 +1,95 @@
+use gossip_glomers::init::{init, init_with, Cluster, InitRequest};
+use serde::Deserialize;
+
+/// Cluster of four nodes from point of view of `n1`.
+fn cluster() -> Cluster {
+    Cluster::from(InitRequest::Init {
+        node_id: "n1".into(),
+        node_ids: ["n0", "n1", "n2", "n3"].map(String::from).to_vec(),
+    })
+}
+
+/// test ring neighbours wrap around on both ends.
+#[test]
+fn test_ring_wraparound() {
+    let cluster = cluster();
+    let successors = ["n0", "n1", "n2", "n3"].map(|id| cluster.successor(id));
+    assert_eq!(successors, ["n1", "n2", "n3", "n0"]);
+    let predecessors = ["n0", "n1", "n2", "n3"].map(|id| cluster.predecessor(id));
+    assert_eq!(predecessors, ["n3", "n0", "n1", "n2"]);
+    assert_eq!(
+        cluster.successor(cluster.predecessor(&cluster.node_id)),
+        "n1"
+    );
+}
+
+/// test binary tree children are within cluster.
+#[test]
+fn test_binary_tree_children() {
+    let cluster = cluster();
+    assert_eq!(cluster.children_in_binary_tree("n0"), ["n1", "n2"]);
+    assert_eq!(cluster.children_in_binary_tree("n1"), ["n3"]);
+    assert!(cluster.children_in_binary_tree("n2").is_empty());
+}
+
+/// test single node is its own ring neighbour.
+#[test]
+fn test_single_node_ring() {
+    let cluster = Cluster::from(InitRequest::Init {
+        node_id: "n1".into(),
+        node_ids: vec!["n1".into()],
+    });
+    assert_eq!(cluster.node_index("n1"), 0);
+    assert_eq!(cluster.successor("n1"), "n1");
+    assert_eq!(cluster.predecessor("n1"), "n1");
+}
+
+const INIT_WITH_CONFIG: &str = r#"
+    {
+        "src": "c1",
+        "dest": "n1",
+        "body": {
+            "msg_id": 1,
+            "type": "init",
+            "node_id": "n1",
+            "node_ids": ["n1", "n2"],
+            "config": { "replicas": 2, "mode": "pull" }
+        }
+    }
+"#;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Config {
+    replicas: usize,
+    mode: String,
+}
+
+#[derive(Deserialize)]
+struct ConfigExtension {
+    config: Config,
+}
+
+/// test extra init fields are read back by extension and ignored by default.
+#[test]
+fn test_init_extension() {
+    let mut deseralizer = serde_json::Deserializer::from_reader(INIT_WITH_CONFIG.as_bytes());
+    let mut writer = Vec::new();
+    let init_ext = init_with::<ConfigExtension, _, _>(&mut writer, &mut deseralizer);
+    assert_eq!(
+        init_ext.extension.config,
+        Config {
+            replicas: 2,
+            mode: "pull".into()
+        }
+    );
+    let InitRequest::Init { node_id, node_ids } = init_ext.init;
+    assert_eq!(node_id, "n1");
+    assert_eq!(node_ids, ["n1", "n2"]);
+    let reply: serde_json::Value = serde_json::from_slice(&writer).unwrap();
+    assert_eq!(reply["body"]["type"], "init_ok");
+    assert_eq!(reply["body"]["in_reply_to"], 1);
+
+    let mut deseralizer = serde_json::Deserializer::from_reader(INIT_WITH_CONFIG.as_bytes());
+    let InitRequest::Init { node_id, .. } = init(&mut Vec::new(), &mut deseralizer);
+    assert_eq!(node_id, "n1");
+}
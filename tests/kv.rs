@@ -0,0 +1,122 @@
+#![cfg(feature = "async")]
+use gossip_glomers::{
+    kv::{Kv, Tso},
+    message::{Body, Message},
+    runtime::Runtime,
+};
+use serde_json::{json, Value};
+use tokio::io::{
+    duplex, split, AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream, Lines, ReadHalf,
+    WriteHalf,
+};
+
+mod testutil;
+use testutil::MockKv;
+
+/// Key store side of connection to node.
+struct Link {
+    /// Messages sent by node.
+    lines: Lines<BufReader<ReadHalf<DuplexStream>>>,
+    /// Writer of replies to node.
+    writer: WriteHalf<DuplexStream>,
+}
+
+/// Connect runtime of node to key store.
+fn connect() -> (
+    Runtime<BufReader<ReadHalf<DuplexStream>>, WriteHalf<DuplexStream>>,
+    Link,
+) {
+    let (node, kv) = duplex(4096);
+    let (node_read, node_write) = split(node);
+    let (kv_read, kv_write) = split(kv);
+    let link = Link {
+        lines: BufReader::new(kv_read).lines(),
+        writer: kv_write,
+    };
+    (Runtime::new(BufReader::new(node_read), node_write), link)
+}
+
+/// Reply to messages of node from key store.
+///
+/// # Returns
+/// Number of compare and swap requests, once node disconnects.
+async fn serve(kv: &mut MockKv, mut link: Link) -> usize {
+    let mut cas = 0;
+    while let Some(line) = link.lines.next_line().await.expect("read message") {
+        let message = serde_json::from_str::<Message<Value>>(&line).unwrap();
+        if message.body.payload["type"] == "cas" {
+            cas += 1;
+        }
+        let line = serde_json::to_string(&kv.handle(message)).unwrap() + "\n";
+        link.writer.write_all(line.as_bytes()).await.unwrap();
+    }
+    cas
+}
+
+/// test update conflicting with concurrent write retries and keeps both.
+#[tokio::test]
+async fn test_update_no_lost_update() {
+    let (mut runtime, link) = connect();
+    let mut kv = MockKv::default().interfere(json!(2));
+    let update = async {
+        let value = Kv::new("n1", "seq-kv")
+            .update(&mut runtime, "COUNTER", |value: Option<i64>| {
+                value.unwrap_or(0) + 1
+            })
+            .await
+            .expect("update commits");
+        drop(runtime);
+        value
+    };
+    let (value, cas) = tokio::join!(update, serve(&mut kv, link));
+    assert_eq!(value, 3);
+    assert_eq!(cas, 2, "conflicting cas is retried once");
+
+    let read = kv.handle(Message {
+        src: "c1".into(),
+        dst: "seq-kv".into(),
+        body: Body {
+            id: Some(1),
+            reply_id: None,
+            payload: json!({ "type": "read", "key": "COUNTER" }),
+        },
+    });
+    assert_eq!(read.body.payload["value"], 3);
+}
+
+/// test timestamps from oracle increase.
+#[tokio::test]
+async fn test_tso_next() {
+    let (mut runtime, mut link) = connect();
+    let tso = async {
+        let mut ts = 0;
+        while let Some(line) = link.lines.next_line().await.unwrap() {
+            let request = serde_json::from_str::<Message<Value>>(&line).unwrap();
+            assert_eq!(request.dst, "lin-tso");
+            assert_eq!(request.body.payload, json!({ "type": "ts" }));
+            ts += 3;
+            let reply = Message {
+                src: request.dst,
+                dst: request.src,
+                body: Body {
+                    id: None,
+                    reply_id: request.body.id,
+                    payload: json!({ "type": "ts_ok", "ts": ts }),
+                },
+            };
+            let line = serde_json::to_string(&reply).unwrap() + "\n";
+            link.writer.write_all(line.as_bytes()).await.unwrap();
+        }
+    };
+    let client = async {
+        let mut tso = Tso::new("n1");
+        let mut timestamps = Vec::new();
+        for _ in 0..3 {
+            timestamps.push(tso.next(&mut runtime).await.expect("timestamp"));
+        }
+        drop(runtime);
+        timestamps
+    };
+    let (timestamps, _) = tokio::join!(client, tso);
+    assert_eq!(timestamps, [3, 6, 9]);
+}
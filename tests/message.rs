@@ -0,0 +1,201 @@
+use gossip_glomers::{
+    derive_request, derive_response, derive_rpc,
+    init::{InitRequest, InitRespone},
+    message::{receive, Body, Message, Rpc},
+};
+use std::{
+    collections::HashSet,
+    io::{BufReader, BufWriter, Read},
+};
+
+/// test messages sent through buffered writer are complete lines.
+#[test]
+fn test_send_buffered() {
+    let mut writer = BufWriter::new(Vec::new());
+    for id in 0..3 {
+        Message {
+            src: "n1".to_string(),
+            dst: "c1".to_string(),
+            body: Body {
+                id: Some(id),
+                reply_id: Some(id),
+                payload: InitRespone::InitOk,
+            },
+        }
+        .send(&mut writer);
+        let output = String::from_utf8_lossy(writer.get_ref()).into_owned();
+        assert!(output.ends_with('\n'), "{output}");
+        assert_eq!(output.lines().count(), id + 1, "{output}");
+    }
+    let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    for (id, line) in output.lines().enumerate() {
+        let message: serde_json::Value = serde_json::from_str(line).expect("complete message");
+        assert_eq!(message["body"]["msg_id"], id);
+    }
+}
+
+/// test notify message is serialized without msg id or reply id.
+#[test]
+fn test_notify_serialize() {
+    let mut writer = Vec::new();
+    Message::notify("n1", "n2", InitRespone::InitOk).send(&mut writer);
+    let message: serde_json::Value = serde_json::from_slice(&writer).unwrap();
+    assert_eq!(
+        message,
+        serde_json::json!({
+            "src": "n1",
+            "dest": "n2",
+            "body": { "type": "init_ok", "msg_id": null, "in_reply_to": null }
+        })
+    );
+}
+
+/// Reader returning input in chunks of cycling sizes.
+struct ChunkedReader<'a> {
+    input: &'a [u8],
+    sizes: std::iter::Cycle<std::slice::Iter<'a, usize>>,
+}
+
+impl Read for ChunkedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = (*self.sizes.next().unwrap())
+            .min(buf.len())
+            .min(self.input.len());
+        let (chunk, rest) = self.input.split_at(size);
+        buf[..size].copy_from_slice(chunk);
+        self.input = rest;
+        Ok(size)
+    }
+}
+
+/// test messages split across reads, even mid token or character, are all received.
+#[test]
+fn test_receive_chunked() {
+    let input = [
+        r#"{ "src": "c1", "dest": "n1", "body": { "type": "init", "node_id": "n1", "node_ids": ["n1"] } }"#,
+        r#"{"src":"c1","dest":"né","body":{"type":"init","msg_id":12345,"node_id":"né","node_ids":["né","n2"]}}"#,
+        r#"{ "src": "c1", "dest": "n2", "body": { "type": "init", "node_id": "n2", "node_ids": [] } }"#,
+    ]
+    .join("\n");
+    for sizes in [[1, 1, 1], [7, 3, 64], [2, 5, 11], [128, 1, 200]] {
+        let reader = ChunkedReader {
+            input: input.as_bytes(),
+            sizes: sizes.iter().cycle(),
+        };
+        let messages = receive::<InitRequest, _>(BufReader::with_capacity(4, reader))
+            .map(|message| {
+                let InitRequest::Init { node_id, .. } = message.body.payload;
+                (message.body.id, node_id)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                (None, "n1".to_string()),
+                (Some(12345), "né".to_string()),
+                (None, "n2".to_string())
+            ],
+            "{sizes:?}"
+        );
+    }
+}
+
+/// Writer recording every write.
+#[derive(Default)]
+struct RecordingWriter(Vec<Vec<u8>>);
+
+impl std::io::Write for RecordingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.push(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// test message is sent in one write terminated by one new line.
+#[test]
+fn test_send_single_write() {
+    let mut writer = RecordingWriter::default();
+    Message {
+        src: "n1".to_string(),
+        dst: "c1".to_string(),
+        body: Body {
+            id: None,
+            reply_id: Some(1),
+            payload: InitRespone::InitOk,
+        },
+    }
+    .send(&mut writer);
+    assert_eq!(writer.0.len(), 1);
+    let output = String::from_utf8(writer.0.remove(0)).unwrap();
+    assert_eq!(
+        output,
+        "{\"src\":\"n1\",\"dest\":\"c1\",\"body\":{\"msg_id\":null,\"in_reply_to\":1,\"type\":\"init_ok\"}}\n"
+    );
+}
+
+derive_response!(
+    #[derive(PartialEq)]
+    enum ReadResponse {
+        ReadOk { messages: HashSet<usize> },
+    }
+);
+
+/// test response payload can be cloned and compared.
+#[test]
+fn test_response_clone() {
+    let response = ReadResponse::ReadOk {
+        messages: HashSet::from([1, 2, 3]),
+    };
+    assert_eq!(response.clone(), response);
+}
+
+derive_response!(
+    enum CounterResponse {
+        ReadCounter { key: String },
+        UpdateCounter { key: String, old: usize, new: usize },
+    }
+);
+
+derive_request!(
+    enum CounterRequest {
+        ReadCounterOk { value: usize },
+        CounterUpdated,
+    }
+);
+
+derive_rpc!(CounterResponse => CounterRequest {
+    ReadCounter => ReadCounterOk,
+    UpdateCounter => CounterUpdated,
+});
+
+/// test outgoing payloads match only the reply acknowledging them.
+#[test]
+fn test_rpc_reply() {
+    let read = CounterResponse::ReadCounter { key: "key".into() };
+    let update = CounterResponse::UpdateCounter {
+        key: "key".into(),
+        old: 1,
+        new: 2,
+    };
+    let reply = |json: &str| serde_json::from_str::<Message<CounterRequest>>(json).unwrap();
+    let read_ok = reply(
+        r#"{ "src": "n2", "dest": "n1", "body": { "type": "read_counter_ok", "in_reply_to": 1, "value": 1 } }"#,
+    );
+    let updated = reply(
+        r#"{ "src": "n2", "dest": "n1", "body": { "type": "counter_updated", "in_reply_to": 2 } }"#,
+    );
+    assert_eq!(read_ok.body.reply_id, Some(1));
+    assert!(matches!(
+        read_ok.body.payload,
+        CounterRequest::ReadCounterOk { value: 1 }
+    ));
+    let (read_ok, updated) = (read_ok.body.payload, updated.body.payload);
+    assert!(read.is_reply(&read_ok));
+    assert!(!read.is_reply(&updated));
+    assert!(update.is_reply(&updated));
+    assert!(!update.is_reply(&read_ok));
+}
@@ -0,0 +1,83 @@
+use gossip_glomers::{
+    derive_request, derive_response,
+    message::{Body, Message},
+    node::Node,
+};
+
+derive_request!(
+    enum EchoRequest {
+        Echo { echo: String },
+    }
+);
+
+derive_request!(
+    enum AddRequest {
+        Add { delta: usize },
+    }
+);
+
+derive_response!(
+    enum TestResponse {
+        EchoOk { echo: String },
+        AddOk { delta: usize },
+    }
+);
+
+/// Reply to message with payload.
+fn reply<Req>(request: Message<Req>, payload: TestResponse) -> Option<Message<TestResponse>> {
+    Some(Message {
+        src: request.dst,
+        dst: request.src,
+        body: Body {
+            id: None,
+            reply_id: request.body.id,
+            payload,
+        },
+    })
+}
+
+/// Parse message with raw payload.
+fn message(input: &str) -> Message<serde_json::Value> {
+    serde_json::from_str(input).expect("valid message")
+}
+
+/// test messages are routed to handler by type.
+#[test]
+fn test_node_dispatch() {
+    let mut node = Node::default();
+    node.register("echo", |request: Message<EchoRequest>| {
+        let EchoRequest::Echo { echo } = &request.body.payload;
+        let echo = echo.clone();
+        reply(request, TestResponse::EchoOk { echo })
+    })
+    .register("add", |request: Message<AddRequest>| {
+        let AddRequest::Add { delta } = request.body.payload;
+        reply(request, TestResponse::AddOk { delta })
+    });
+
+    let response = node
+        .on_message(message(
+            r#"{"src":"c1","dest":"n1","body":{"msg_id":1,"type":"echo","echo":"hi"}}"#,
+        ))
+        .expect("echo is registered");
+    assert_eq!(response.dst, "c1");
+    assert_eq!(response.body.reply_id, Some(1));
+    assert_eq!(response.body.payload["type"], "echo_ok");
+    assert_eq!(response.body.payload["echo"], "hi");
+
+    let response = node
+        .on_message(message(
+            r#"{"src":"c2","dest":"n1","body":{"msg_id":2,"type":"add","delta":3}}"#,
+        ))
+        .expect("add is registered");
+    assert_eq!(response.dst, "c2");
+    assert_eq!(response.body.reply_id, Some(2));
+    assert_eq!(response.body.payload["type"], "add_ok");
+    assert_eq!(response.body.payload["delta"], 3);
+
+    assert!(node
+        .on_message(message(
+            r#"{"src":"c1","dest":"n1","body":{"msg_id":3,"type":"read"}}"#,
+        ))
+        .is_none());
+}
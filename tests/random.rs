@@ -0,0 +1,27 @@
+use gossip_glomers::random::Rng;
+
+/// test generators with same seed choose same peers.
+#[test]
+fn test_same_seed_same_peers() {
+    let peers = (0..25).map(|n| format!("n{n}")).collect::<Vec<_>>();
+    let (mut first, mut second) = (Rng::new(7), Rng::new(7));
+    for _ in 0..10 {
+        assert_eq!(first.sample(&peers, 3), second.sample(&peers, 3));
+    }
+    let mut other = Rng::new(8);
+    assert!((0..10).any(|_| first.sample(&peers, 3) != other.sample(&peers, 3)));
+}
+
+/// test sample chooses distinct peers.
+#[test]
+fn test_sample_distinct() {
+    let peers = (0..5).collect::<Vec<_>>();
+    let mut rng = Rng::new(1);
+    for amount in 0..=6 {
+        let mut sample = rng.sample(&peers, amount);
+        assert_eq!(sample.len(), amount.min(peers.len()));
+        sample.sort();
+        sample.dedup();
+        assert_eq!(sample.len(), amount.min(peers.len()));
+    }
+}
@@ -0,0 +1,142 @@
+#![cfg(feature = "async")]
+use gossip_glomers::{
+    derive_request, derive_response,
+    echo::Echo,
+    message::{Body, Message},
+    runtime::Runtime,
+};
+use pretty_assertions::assert_eq;
+use std::time::Duration;
+use tokio::io::{duplex, split, AsyncReadExt, AsyncWriteExt, BufReader};
+
+derive_request!(
+    enum RpcRequest {
+        Echo { echo: String },
+        ReadOk { value: usize },
+    }
+);
+
+derive_response!(
+    enum RpcResponse {
+        Read { key: String },
+    }
+);
+
+/// Run async echo node over in memory duplex with input and return output.
+async fn echo(input: &str) -> String {
+    let (client, server) = duplex(4096);
+    let (server_read, server_write) = split(server);
+    let (mut client_read, mut client_write) = split(client);
+    client_write.write_all(input.as_bytes()).await.unwrap();
+    client_write.shutdown().await.unwrap();
+    let mut runtime = Runtime::new(BufReader::new(server_read), server_write);
+    runtime.init().await.expect("initialized");
+    Echo::new()
+        .extra(false)
+        .delay(None)
+        .serve_async(&mut runtime)
+        .await;
+    drop(runtime);
+    drop(client_write);
+    let mut output = String::new();
+    client_read.read_to_string(&mut output).await.unwrap();
+    output
+}
+
+/// test async echo node input and output.
+#[tokio::test]
+async fn test_echo_async() {
+    let input = r#"
+    { "src": "c1", "dest": "n1", "body": { "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1", "n2"] } }
+    { "src": "c1", "dest": "n1", "body": { "type": "echo", "msg_id": 1
+    { "src": "c1", "dest": "n1", "body": { "type": "echo", "msg_id": 2, "echo": "Please echo 35" } }
+    "#;
+    let output = concat!(
+        r#"{"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}"#,
+        "\n",
+        r#"{"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":2,"type":"echo_ok","echo":"Please echo 35"}}"#,
+        "\n",
+    );
+    assert_eq!(echo(input).await, output);
+}
+
+/// test rpc returns reply and keeps other messages for later.
+#[tokio::test]
+async fn test_rpc_reply() {
+    let input = r#"
+    { "src": "c1", "dest": "n1", "body": { "type": "echo", "msg_id": 1, "echo": "later" } }
+    { "src": "lin-kv", "dest": "n1", "body": { "type": "read_ok", "in_reply_to": 7, "value": 3 } }
+    "#;
+    let mut output = Vec::new();
+    let mut runtime = Runtime::new(input.as_bytes(), &mut output);
+    let read = Message {
+        src: "n1".to_string(),
+        dst: "lin-kv".to_string(),
+        body: Body {
+            id: Some(7),
+            reply_id: None,
+            payload: RpcResponse::Read { key: "key".into() },
+        },
+    };
+    let reply = runtime
+        .rpc::<_, RpcRequest>(read, Duration::from_secs(1))
+        .await;
+    assert!(matches!(
+        reply.map(|reply| reply.body.payload),
+        Some(RpcRequest::ReadOk { value: 3 })
+    ));
+    let request = runtime.recv::<RpcRequest>().await;
+    assert!(matches!(
+        request.map(|request| request.body.payload),
+        Some(RpcRequest::Echo { echo }) if echo == "later"
+    ));
+}
+
+/// test rpc gives up after timeout.
+#[tokio::test]
+async fn test_rpc_timeout() {
+    let (_client, server) = duplex(4096);
+    let (server_read, server_write) = split(server);
+    let mut runtime = Runtime::new(BufReader::new(server_read), server_write);
+    let read = Message {
+        src: "n1".to_string(),
+        dst: "lin-kv".to_string(),
+        body: Body {
+            id: Some(7),
+            reply_id: None,
+            payload: RpcResponse::Read { key: "key".into() },
+        },
+    };
+    let reply = runtime
+        .rpc::<_, RpcRequest>(read, Duration::from_millis(10))
+        .await;
+    assert!(reply.is_none());
+}
+
+/// test rpc replies from different services are routed by source.
+#[tokio::test]
+async fn test_rpc_services() {
+    let input = r#"
+    { "src": "lin-kv", "dest": "n1", "body": { "type": "read_ok", "in_reply_to": 7, "value": 1 } }
+    { "src": "seq-kv", "dest": "n1", "body": { "type": "read_ok", "in_reply_to": 7, "value": 2 } }
+    "#;
+    let mut output = Vec::new();
+    let mut runtime = Runtime::new(input.as_bytes(), &mut output);
+    let read = |kv: &str| Message {
+        src: "n1".to_string(),
+        dst: kv.to_string(),
+        body: Body {
+            id: Some(7),
+            reply_id: None,
+            payload: RpcResponse::Read { key: "key".into() },
+        },
+    };
+    for (kv, expected) in [("seq-kv", 2), ("lin-kv", 1)] {
+        let reply = runtime
+            .rpc::<_, RpcRequest>(read(kv), Duration::from_secs(1))
+            .await
+            .expect("reply");
+        assert_eq!(reply.src, kv);
+        assert!(matches!(reply.body.payload, RpcRequest::ReadOk { value } if value == expected));
+    }
+}
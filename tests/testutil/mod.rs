@@ -0,0 +1,318 @@
+//! Utilities shared by tests.
+// not every test uses every utility.
+#![allow(dead_code)]
+
+use bincode::Options;
+use gossip_glomers::{
+    log,
+    log::Level,
+    message::{Body, Message, Request, Response},
+    random::Rng,
+    transport::Transport,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+
+/// Key store answering `read`, `write` and `cas` like `seq-kv` and `lin-kv`.
+#[derive(Default)]
+pub struct MockKv {
+    /// Values by key.
+    values: HashMap<String, Value>,
+    /// Message id counter.
+    id: usize,
+    /// Offset added to value stored by `cas`, which still replies `cas_ok`.
+    corrupt: i64,
+    /// Value written to key right before next `cas`, as by a concurrent writer.
+    interfere: Option<Value>,
+}
+
+impl MockKv {
+    /// Check whether node is a key store.
+    pub fn is_kv(node: &str) -> bool {
+        matches!(node, "seq-kv" | "lin-kv")
+    }
+
+    /// Store `to` shifted by offset on `cas`, while still replying `cas_ok`.
+    pub fn corrupt_cas(mut self, offset: i64) -> Self {
+        self.corrupt = offset;
+        self
+    }
+
+    /// Overwrite key with value right before next `cas`, which then conflicts.
+    pub fn interfere(mut self, value: Value) -> Self {
+        self.interfere = Some(value);
+        self
+    }
+
+    /// Apply operation of message and return reply.
+    ///
+    /// Missing key is [KeyDoesNotExist](gossip_glomers::message::ErrorCode::KeyDoesNotExist)
+    /// and stale `from` is [PreconditionFailed](gossip_glomers::message::ErrorCode::PreconditionFailed).
+    pub fn handle(&mut self, message: Message<Value>) -> Message<Value> {
+        let payload = &message.body.payload;
+        let key = payload["key"].as_str().expect("key").to_string();
+        let payload = match payload["type"].as_str() {
+            Some("read") => match self.values.get(&key) {
+                Some(value) => json!({ "type": "read_ok", "value": value }),
+                None => Self::error(20, "key does not exist"),
+            },
+            Some("write") => {
+                self.values.insert(key, payload["value"].clone());
+                json!({ "type": "write_ok" })
+            }
+            Some("cas") => {
+                if let Some(value) = self.interfere.take() {
+                    self.values.insert(key.clone(), value);
+                }
+                let create = payload["create_if_not_exists"].as_bool().unwrap_or(false);
+                match self.values.get(&key) {
+                    None if !create => Self::error(20, "key does not exist"),
+                    Some(value) if *value != payload["from"] => {
+                        Self::error(22, "current value does not match from")
+                    }
+                    _ => {
+                        let to = match payload["to"].as_i64() {
+                            Some(to) => json!(to + self.corrupt),
+                            None => payload["to"].clone(),
+                        };
+                        self.values.insert(key, to);
+                        json!({ "type": "cas_ok" })
+                    }
+                }
+            }
+            other => panic!("unexpected key store operation {other:?}"),
+        };
+        self.id += 1;
+        Message {
+            src: message.dst,
+            dst: message.src,
+            body: Body {
+                id: Some(self.id),
+                reply_id: message.body.id,
+                payload,
+            },
+        }
+    }
+
+    /// Error payload with code.
+    fn error(code: u8, text: &str) -> Value {
+        json!({ "type": "error", "code": code, "text": text })
+    }
+}
+
+/// Transport between nodes which drops, delays, reorders and duplicates messages.
+///
+/// Faults are chosen by generator, hence same seed gives same faults.
+pub struct FaultyTransport {
+    /// Generator choosing faults.
+    rng: Rng,
+    /// Percent of messages dropped.
+    drop: usize,
+    /// Percent of messages duplicated.
+    duplicate: usize,
+    /// Maximum number of rounds a message is delayed, which reorders messages.
+    reorder: usize,
+    /// Messages matching are always dropped.
+    drop_matching: Option<fn(&Message<Value>) -> bool>,
+    /// Messages in flight with rounds left until delivery.
+    in_flight: Vec<(usize, Message<Value>)>,
+}
+
+impl FaultyTransport {
+    /// Create transport without faults.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            drop: 0,
+            duplicate: 0,
+            reorder: 0,
+            drop_matching: None,
+            in_flight: Vec::new(),
+        }
+    }
+
+    /// Drop given percent of messages.
+    pub fn drop(mut self, percent: usize) -> Self {
+        self.drop = percent;
+        self
+    }
+
+    /// Duplicate given percent of messages.
+    pub fn duplicate(mut self, percent: usize) -> Self {
+        self.duplicate = percent;
+        self
+    }
+
+    /// Always drop messages matching predicate.
+    pub fn drop_matching(mut self, predicate: fn(&Message<Value>) -> bool) -> Self {
+        self.drop_matching = Some(predicate);
+        self
+    }
+
+    /// Delay messages up to given number of rounds.
+    pub fn reorder(mut self, window: usize) -> Self {
+        self.reorder = window;
+        self
+    }
+
+    /// Send message through transport.
+    pub fn send(&mut self, message: Message<Value>) {
+        if self
+            .drop_matching
+            .is_some_and(|predicate| predicate(&message))
+        {
+            return;
+        }
+        if self.rng.below(100) < self.drop {
+            return;
+        }
+        if self.rng.below(100) < self.duplicate {
+            let duplicate = serde_json::from_value(serde_json::to_value(&message).unwrap());
+            let delay = self.rng.below(self.reorder + 1);
+            self.in_flight.push((delay, duplicate.unwrap()));
+        }
+        let delay = self.rng.below(self.reorder + 1);
+        self.in_flight.push((delay, message));
+    }
+
+    /// Check whether no message is in flight.
+    pub fn is_empty(&self) -> bool {
+        self.in_flight.is_empty()
+    }
+
+    /// Deliver messages due in this round, in random order.
+    pub fn deliver(&mut self) -> Vec<Message<Value>> {
+        let (due, in_flight) = std::mem::take(&mut self.in_flight)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(delay, _)| *delay == 0);
+        self.in_flight = in_flight
+            .into_iter()
+            .map(|(delay, message)| (delay - 1, message))
+            .collect();
+        let mut due: Vec<_> = due.into_iter().map(|(_, message)| message).collect();
+        for i in (1..due.len()).rev() {
+            due.swap(i, self.rng.below(i + 1));
+        }
+        due
+    }
+}
+
+/// JSON value in form bincode can encode.
+///
+/// Bincode is not self describing, hence it cannot encode [Value] or tagged payloads directly.
+#[derive(Serialize, Deserialize)]
+enum Compact {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(String),
+    Array(Vec<Compact>),
+    Object(Vec<(String, Compact)>),
+}
+
+impl From<Value> for Compact {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => Compact::Null,
+            Value::Bool(value) => Compact::Bool(value),
+            Value::Number(number) => match (number.as_u64(), number.as_i64()) {
+                (Some(value), _) => Compact::UInt(value),
+                (_, Some(value)) => Compact::Int(value),
+                _ => Compact::Float(number.as_f64().unwrap_or_default()),
+            },
+            Value::String(value) => Compact::String(value),
+            Value::Array(values) => Compact::Array(values.into_iter().map(Into::into).collect()),
+            Value::Object(map) => Compact::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<Compact> for Value {
+    fn from(value: Compact) -> Self {
+        match value {
+            Compact::Null => Value::Null,
+            Compact::Bool(value) => json!(value),
+            Compact::Int(value) => json!(value),
+            Compact::UInt(value) => json!(value),
+            Compact::Float(value) => json!(value),
+            Compact::String(value) => Value::String(value),
+            Compact::Array(values) => Value::Array(values.into_iter().map(Into::into).collect()),
+            Compact::Object(entries) => Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Transport passing messages in memory encoded with bincode instead of JSON.
+///
+/// Used to compare size of encoded messages, see [Self::sent_bytes].
+#[derive(Default)]
+pub struct BincodeTransport {
+    /// Encoded messages to be received.
+    inbox: VecDeque<Vec<u8>>,
+    /// Encoded messages sent.
+    outbox: Vec<Vec<u8>>,
+}
+
+impl BincodeTransport {
+    /// Encode message with bincode, integers are variable length.
+    pub fn encode<T: Serialize>(message: &Message<T>) -> Vec<u8> {
+        let value = serde_json::to_value(message).expect("message is json");
+        bincode::DefaultOptions::new()
+            .serialize(&Compact::from(value))
+            .expect("compact value is encodable")
+    }
+
+    /// Decode message encoded by [Self::encode].
+    pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<Message<T>, String> {
+        let value = bincode::DefaultOptions::new()
+            .deserialize::<Compact>(bytes)
+            .map_err(|e| e.to_string())?;
+        serde_json::from_value(value.into()).map_err(|e| e.to_string())
+    }
+
+    /// Push message to be received.
+    pub fn push(&mut self, message: Message<Value>) {
+        self.inbox.push_back(Self::encode(&message));
+    }
+
+    /// Number of bytes of messages sent so far.
+    pub fn sent_bytes(&self) -> usize {
+        self.outbox.iter().map(Vec::len).sum()
+    }
+
+    /// Take messages sent so far, decoded.
+    pub fn take_sent(&mut self) -> Vec<Message<Value>> {
+        std::mem::take(&mut self.outbox)
+            .iter()
+            .map(|bytes| Self::decode(bytes).expect("sent message decodes"))
+            .collect()
+    }
+}
+
+impl Transport for BincodeTransport {
+    fn recv<Req: DeserializeOwned + Request>(&mut self) -> Option<Message<Req>> {
+        while let Some(bytes) = self.inbox.pop_front() {
+            match Self::decode(&bytes) {
+                Ok(message) => return Some(message),
+                Err(e) => log!(Level::Error, "failed to decode {} bytes: {e}", bytes.len()),
+            }
+        }
+        None
+    }
+
+    fn send<Res: Serialize + Response>(&mut self, message: Message<Res>) {
+        self.outbox.push(Self::encode(&message));
+    }
+}
@@ -0,0 +1,112 @@
+use gossip_glomers::{
+    broadcast::{BroadcastRequest, BroadcastRespone},
+    echo::Echo,
+    init::init_transport,
+    message::Message,
+    transport::{MemoryTransport, Transport},
+    unique_ids::Generator,
+};
+use serde_json::{json, Value};
+
+mod testutil;
+use testutil::BincodeTransport;
+
+/// Message from client to node with payload.
+fn message(payload: Value) -> Message<Value> {
+    serde_json::from_value(json!({ "src": "c1", "dest": "n1", "body": payload })).unwrap()
+}
+
+/// test echo node driven by in-memory transport, without standard input and output.
+#[test]
+fn test_echo_memory_transport() {
+    let mut transport = MemoryTransport::default();
+    transport.push(message(
+        json!({ "msg_id": 1, "type": "init", "node_id": "n1", "node_ids": ["n1"] }),
+    ));
+    transport.push(message(
+        json!({ "msg_id": 2, "type": "echo", "echo": "hi", "x": 1 }),
+    ));
+    transport.push(message(json!({ "msg_id": 3, "type": "unknown" })));
+    transport.push(message(
+        json!({ "msg_id": 4, "type": "echo", "echo": "bye" }),
+    ));
+    assert!(init_transport(&mut transport).is_some());
+    Echo::new().extra(true).serve(&mut transport);
+    let sent = transport
+        .take_sent()
+        .into_iter()
+        .map(|message| serde_json::to_value(message).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sent,
+        [
+            json!({ "src": "n1", "dest": "c1", "body": { "msg_id": null, "in_reply_to": 1, "type": "init_ok" } }),
+            json!({ "src": "n1", "dest": "c1", "body": { "msg_id": 0, "in_reply_to": 2, "type": "echo_ok", "echo": "hi", "x": 1 } }),
+            json!({ "src": "n1", "dest": "c1", "body": { "msg_id": 1, "in_reply_to": 4, "type": "echo_ok", "echo": "bye" } }),
+        ]
+    );
+    assert!(transport.recv::<Value>().is_none());
+}
+
+/// test unique id node driven by in-memory transport, ignoring key store replies.
+#[test]
+fn test_unique_ids_memory_transport() {
+    let mut transport = MemoryTransport::default();
+    transport.push(message(
+        json!({ "msg_id": 1, "type": "init", "node_id": "n2", "node_ids": ["n2", "n1"] }),
+    ));
+    transport.push(message(json!({ "msg_id": 2, "type": "generate" })));
+    transport.push(message(json!({ "in_reply_to": 0, "type": "cas_ok" })));
+    transport.push(message(json!({ "msg_id": 3, "type": "generate" })));
+    let init = init_transport(&mut transport).unwrap();
+    Generator::new(init).serve(&mut transport);
+    let sent = transport.take_sent();
+    assert_eq!(sent.len(), 3);
+    let ids = sent[1..]
+        .iter()
+        .map(|message| message.body.payload["id"].as_u64().unwrap())
+        .collect::<Vec<_>>();
+    assert!(ids.iter().all(|id| id % 2 == 1), "{ids:?}");
+    assert!(ids[0] < ids[1], "{ids:?}");
+    assert_eq!(sent[2].body.reply_id, Some(3));
+}
+
+/// test gossip message round trips through bincode transport, encoded smaller than JSON.
+#[test]
+fn test_bincode_transport() {
+    let seen = (0..100).collect::<Vec<usize>>();
+    let gossip = Message::notify(
+        "n1".to_string(),
+        "n2".to_string(),
+        BroadcastRespone::Consensus {
+            seen: seen.clone(),
+            seen_ack: vec![7],
+        },
+    );
+    let json_bytes = serde_json::to_vec(&gossip).unwrap().len();
+    let mut transport = BincodeTransport::default();
+    transport.send(gossip);
+    assert!(
+        transport.sent_bytes() < json_bytes,
+        "{} bincode bytes, {json_bytes} json bytes",
+        transport.sent_bytes()
+    );
+    for message in transport.take_sent() {
+        transport.push(message);
+    }
+    let received = transport
+        .recv::<BroadcastRequest>()
+        .expect("gossip decodes");
+    assert_eq!((received.src.as_str(), received.dst.as_str()), ("n1", "n2"));
+    match received.body.payload {
+        BroadcastRequest::Consensus {
+            seen: received,
+            seen_ack,
+        } => {
+            assert_eq!(received, seen.into_iter().collect());
+            assert_eq!(seen_ack, [7]);
+        }
+        payload => panic!("unexpected payload {payload:?}"),
+    }
+    assert!(transport.recv::<BroadcastRequest>().is_none());
+}
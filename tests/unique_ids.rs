@@ -0,0 +1,90 @@
+use gossip_glomers::{
+    event::{inputs, Event},
+    init::InitRequest,
+    unique_ids::{BlockAllocator, GenRequest, REPLY_TICKS},
+};
+use serde_json::Value;
+use std::sync::mpsc::channel;
+
+/// Run block allocator in-process on events and return output messages.
+fn handle(events: impl IntoIterator<Item = Event<GenRequest>>) -> Vec<Value> {
+    let mut node = BlockAllocator::new(InitRequest::Init {
+        node_id: "n1".into(),
+        node_ids: vec!["n1".into()],
+    });
+    let (tick_tx, _tick_rx) = channel();
+    let mut output = Vec::new();
+    node.handle_events(events, tick_tx, &mut output);
+    serde_json::Deserializer::from_slice(&output)
+        .into_iter()
+        .map(Result::unwrap)
+        .collect()
+}
+
+/// Types of output messages in order.
+fn types(output: &[Value]) -> Vec<&str> {
+    output
+        .iter()
+        .map(|message| message["body"]["type"].as_str().unwrap())
+        .collect()
+}
+
+/// test late and unmatched key store replies do not hand out a block not reserved.
+#[test]
+fn test_block_stale_reply() {
+    let events = inputs(
+        r#"
+        { "src": "c1", "dest": "n1", "body": { "type": "generate", "msg_id": 1 } }
+        { "src": "lin-kv", "dest": "n1", "body": { "type": "error", "msg_id": 1, "in_reply_to": 0, "code": 22, "text": "mismatch" } }
+        { "src": "lin-kv", "dest": "n1", "body": { "type": "cas_ok", "msg_id": 2, "in_reply_to": 0 } }
+        { "src": "lin-kv", "dest": "n1", "body": { "type": "cas_ok", "msg_id": 3 } }
+        "#
+        .as_bytes(),
+    )
+    .chain([Event::tick()])
+    .chain(inputs(
+        r#"
+        { "src": "lin-kv", "dest": "n1", "body": { "type": "read_ok", "msg_id": 4, "in_reply_to": 1, "value": 1000 } }
+        { "src": "lin-kv", "dest": "n1", "body": { "type": "cas_ok", "msg_id": 5, "in_reply_to": 0 } }
+        { "src": "lin-kv", "dest": "n1", "body": { "type": "cas_ok", "msg_id": 6, "in_reply_to": 2 } }
+        { "src": "lin-kv", "dest": "n1", "body": { "type": "cas_ok", "msg_id": 7, "in_reply_to": 2 } }
+        { "src": "c1", "dest": "n1", "body": { "type": "generate", "msg_id": 2 } }
+        "#
+        .as_bytes(),
+    ));
+    let output = handle(events);
+    assert_eq!(
+        types(&output),
+        ["cas", "read", "cas", "generate_ok", "generate_ok"]
+    );
+    let ids: Vec<_> = output[3..]
+        .iter()
+        .map(|message| message["body"]["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, [1000, 1001]);
+}
+
+/// test lost reservation reply is given up after ticks and block end is read again.
+#[test]
+fn test_block_lost_reply() {
+    let events = inputs(
+        r#"
+        { "src": "c1", "dest": "n1", "body": { "type": "generate", "msg_id": 1 } }
+        "#
+        .as_bytes(),
+    )
+    .chain((0..=REPLY_TICKS).map(|_| Event::tick()))
+    .chain(inputs(
+        r#"
+        { "src": "lin-kv", "dest": "n1", "body": { "type": "cas_ok", "msg_id": 1, "in_reply_to": 0 } }
+        { "src": "lin-kv", "dest": "n1", "body": { "type": "read_ok", "msg_id": 2, "in_reply_to": 1, "value": 1000 } }
+        { "src": "lin-kv", "dest": "n1", "body": { "type": "cas_ok", "msg_id": 3, "in_reply_to": 2 } }
+        "#
+        .as_bytes(),
+    ));
+    let output = handle(events);
+    assert_eq!(types(&output), ["cas", "read", "cas", "generate_ok"]);
+    assert_eq!(output[2]["body"]["from"], 1000);
+    assert_eq!(output[3]["body"]["in_reply_to"], 1);
+    assert_eq!(output[3]["body"]["id"], 1000);
+}
@@ -0,0 +1,153 @@
+//! Module to benchmark challange over multiple runs.
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::{
+    challange::{build, command, MaelStromCommand, MaelStromResult, RunOptions},
+    error::XtaskError,
+};
+
+/// Options to bench command.
+#[derive(Parser, Debug)]
+pub struct BenchOptions {
+    /// Number of times to run the challenge
+    #[arg(short = 'n', long, default_value_t = 5)]
+    pub runs: usize,
+
+    /// Write one row per run to CSV file at path, see [CSV_HEADER]
+    #[arg(long)]
+    pub csv: Option<PathBuf>,
+
+    /// Options of every run.
+    #[command(flatten)]
+    pub run: RunOptions,
+}
+
+/// Minimum, median and maximum of metric across runs.
+#[derive(Debug, PartialEq)]
+pub struct Distribution {
+    /// Minimum value.
+    pub min: f64,
+    /// Median value, mean of middle values for even number of values.
+    pub median: f64,
+    /// Maximum value.
+    pub max: f64,
+}
+
+impl Distribution {
+    /// Distribution of values, `None` if there are no values.
+    pub fn new(mut values: Vec<f64>) -> Option<Self> {
+        values.sort_by(f64::total_cmp);
+        let middle = values.len() / 2;
+        let median = match values.len() {
+            0 => return None,
+            len if len % 2 == 0 => (values[middle - 1] + values[middle]) / 2.0,
+            _ => values[middle],
+        };
+        Some(Self {
+            min: values[0],
+            median,
+            max: values[values.len() - 1],
+        })
+    }
+}
+
+/// Metric read from results, `None` if missing.
+pub type Metric = fn(&MaelStromResult) -> Option<f64>;
+
+/// Metrics collected from results of every run.
+pub fn metrics() -> [(&'static str, Metric); 4] {
+    [
+        ("msgs-per-op", MaelStromResult::msgs_per_op),
+        ("latency p50", |result| result.stable_latency(0.5)),
+        ("latency p99", |result| result.stable_latency(0.99)),
+        ("latency p100", |result| result.stable_latency(1.0)),
+    ]
+}
+
+/// Distribution of every metric across results.
+///
+/// Results missing a metric are skipped for that metric.
+pub fn aggregate(results: &[MaelStromResult]) -> Vec<(&'static str, Option<Distribution>)> {
+    metrics()
+        .into_iter()
+        .map(|(name, metric)| {
+            let values = results.iter().filter_map(metric).collect();
+            (name, Distribution::new(values))
+        })
+        .collect()
+}
+
+/// Header of CSV file written by bench, see [csv_row].
+pub const CSV_HEADER: &str = "run,rate,msgs-per-op,latency p50,latency p100,valid";
+
+/// Escape CSV cell, quoting it if it has a comma, quote or line break.
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// CSV row of run with its request rate and results, columns are as in [CSV_HEADER].
+///
+/// Fields missing from results, or all fields of failed run without results, are empty cells.
+pub fn csv_row(run: usize, rate: Option<usize>, result: Option<&MaelStromResult>) -> String {
+    let number = |value: Option<f64>| value.map(|value| value.to_string()).unwrap_or_default();
+    [
+        run.to_string(),
+        rate.map(|rate| rate.to_string()).unwrap_or_default(),
+        number(result.and_then(MaelStromResult::msgs_per_op)),
+        number(result.and_then(|result| result.stable_latency(0.5))),
+        number(result.and_then(|result| result.stable_latency(1.0))),
+        result
+            .and_then(MaelStromResult::valid)
+            .map(|valid| valid.to_string())
+            .unwrap_or_default(),
+    ]
+    .map(|cell| csv_cell(&cell))
+    .join(",")
+}
+
+/// Run the challenge multiple times and report distribution of metrics.
+///
+/// Failed runs are reported and skipped, while interruption stops the benchmark.
+/// Every run, including failed ones, is written as row of CSV file if asked for.
+pub fn bench(opts: BenchOptions) -> Result<(), XtaskError> {
+    let bin_path = build(opts.run.release, &opts.run.challange.get_name())?;
+    let rate = opts.run.rate.or(opts.run.challange.rate());
+    let mut results = vec![];
+    let mut rows = vec![CSV_HEADER.to_string()];
+    for run in 1..=opts.runs {
+        let result = command(&opts.run, &bin_path)
+            .execute(opts.run.rerun_on_fail)
+            .and_then(|_| MaelStromCommand::get_results(&opts.run.store()));
+        match result {
+            Ok(result) => {
+                rows.push(csv_row(run, rate, Some(&result)));
+                results.push(result);
+            }
+            Err(XtaskError::Interrupted) => return Err(XtaskError::Interrupted),
+            Err(error) => {
+                eprintln!("run {run} failed: {error}");
+                rows.push(csv_row(run, rate, None));
+            }
+        }
+    }
+    if let Some(path) = &opts.csv {
+        std::fs::write(path, rows.join("\n") + "\n")
+            .map_err(|e| XtaskError::Invoke(format!("write {}", path.display()), e))?;
+    }
+    println!("{} of {} runs succeeded", results.len(), opts.runs);
+    for (name, distribution) in aggregate(&results) {
+        match distribution {
+            Some(Distribution { min, median, max }) => {
+                println!("{name}: min {min}, median {median}, max {max}")
+            }
+            None => println!("{name}: n/a"),
+        }
+    }
+    Ok(())
+}
@@ -1,8 +1,33 @@
 //! Module to handle challenge running and list.
-use std::{env::var, path::PathBuf, process::Command};
+use std::{
+    collections::BTreeMap,
+    env::var,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
 
 use clap::{Parser, ValueEnum};
-use convert_case::{Case, Casing};
+
+use crate::{error::XtaskError, interrupt};
+
+/// Maelstrom store directory, relative to the working directory of the run.
+///
+/// Maelstrom has no option for store location, see [RunOptions::output_dir].
+pub const STORE: &str = "store";
+
+/// Time limit in seconds of runs with `--time-limit 0`, which run until interrupted.
+///
+/// Maelstrom requires a time limit, hence a year is used instead.
+pub const NO_TIME_LIMIT: usize = 365 * 24 * 60 * 60;
+
+/// Link in output directory to directory of the latest run, see [RunOptions::with_default_run_id].
+///
+/// Targets without symbolic links write a file containing identifier of the run instead.
+pub const LATEST_RUN: &str = "latest";
+
+/// Maelstrom arguments enabled by `--debug` flag of run.
+pub const DEBUG_ARGS: [&str; 3] = ["--log-stderr", "--log-net-send", "--log-net-recv"];
 
 /// Options to run command.
 #[derive(Parser, Debug)]
@@ -15,10 +40,111 @@ pub struct RunOptions {
     #[arg(short, long, env, default_value = "maelstrom")]
     pub maelstrom_bin: PathBuf,
 
+    /// Maelstrom command with leading arguments, overrides binary location
+    ///
+    /// Example: `java -jar maelstrom.jar`
+    #[arg(long, env)]
+    pub maelstrom_cmd: Option<String>,
+
     /// Build and run the release target
     #[clap(long)]
     pub release: bool,
 
+    /// Number of nodes, overrides challenge default
+    #[arg(long)]
+    pub node_count: Option<usize>,
+
+    /// Time limit in seconds, overrides challenge default
+    ///
+    /// `0` runs until interrupted by Ctrl-C, which stops maelstrom.
+    #[arg(long)]
+    pub time_limit: Option<usize>,
+
+    /// Requests per second, overrides challenge default
+    #[arg(long)]
+    pub rate: Option<usize>,
+
+    /// Number of concurrent clients, for workloads supporting it
+    ///
+    /// Either a count like `4` or a multiple of node count like `2n`.
+    #[arg(long)]
+    pub concurrency: Option<String>,
+
+    /// Message latency in milliseconds, appended after challenge default
+    #[arg(long)]
+    pub latency: Option<usize>,
+
+    /// Broadcast topology, appended after challenge default
+    ///
+    /// One of `grid`, `line`, `total`, `tree2`, `tree3` or `tree4`.
+    #[arg(long)]
+    pub topology: Option<String>,
+
+    /// Maximum messages per operation, overrides challenge default
+    #[arg(long)]
+    pub max_msgs_per_op: Option<f64>,
+
+    /// Maximum median latency in milliseconds, overrides challenge default
+    #[arg(long)]
+    pub max_median_latency: Option<f64>,
+
+    /// Maximum latency in milliseconds, overrides challenge default
+    #[arg(long)]
+    pub max_p100_latency: Option<f64>,
+
+    /// Seed for random choices of nodes, passed as `GOSSIP_SEED`
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Environment variable for the binary, may be repeated
+    ///
+    /// Example: `--extra-env TICK_TIME=100`, overrides challenge default.
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_env)]
+    pub extra_env: Vec<(String, String)>,
+
+    /// Directory to run maelstrom in, results are read from its store
+    ///
+    /// Defaults to current directory, use different directories to run in parallel.
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Identifier of the run, maelstrom runs in directory of this name inside output directory
+    ///
+    /// Runs with different identifiers do not overwrite results of each other.
+    /// Defaults to a new identifier per run when output directory is given,
+    /// see [RunOptions::with_default_run_id].
+    #[arg(long)]
+    pub run_id: Option<String>,
+
+    /// Rerun failed maelstrom run at most this many times
+    #[arg(long, default_value_t = 0)]
+    pub rerun_on_fail: usize,
+
+    /// Describe what the run will do, without running it
+    #[clap(long)]
+    pub explain: bool,
+
+    /// Print maelstrom command of the run, without building or running it
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Check results of previous run again, without building or running it
+    ///
+    /// Results are read from store of the run, see `--output-dir` and `--run-id`,
+    /// hence thresholds can be changed without running the workload again.
+    #[clap(long)]
+    pub validate_only: bool,
+
+    /// Print results of the run as JSON
+    #[clap(long)]
+    pub json: bool,
+
+    /// Enable maelstrom logging of node stderr and network messages
+    ///
+    /// Expands to [DEBUG_ARGS], merged with extra arguments.
+    #[clap(long)]
+    pub debug: bool,
+
     /// Extra arguments to be passed to maelstrom.
     ///
     /// Example: `--log-stderr`, `--log-net-send`, `--log-net-recv`
@@ -26,6 +152,74 @@ pub struct RunOptions {
     pub extra_args: Vec<String>,
 }
 
+impl RunOptions {
+    /// Give run its own identifier when output directory is given without one.
+    ///
+    /// Identifier is challenge name with start time and process id of the run,
+    /// hence runs sharing output directory do not share store.
+    /// With `--validate-only` results of [latest run](LATEST_RUN) are checked instead.
+    pub fn with_default_run_id(mut self) -> Self {
+        if self.output_dir.is_some() && self.run_id.is_none() {
+            self.run_id = Some(if self.validate_only {
+                self.output_dir
+                    .as_deref()
+                    .and_then(|dir| std::fs::read_to_string(dir.join(LATEST_RUN)).ok())
+                    .map(|run_id| run_id.trim().to_string())
+                    .unwrap_or(LATEST_RUN.to_string())
+            } else {
+                let start = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default();
+                format!(
+                    "{}-{}-{}",
+                    self.challange.get_name(),
+                    start.as_nanos(),
+                    std::process::id()
+                )
+            });
+        }
+        self
+    }
+
+    /// Directory maelstrom runs in, if not current directory.
+    ///
+    /// Directory of run identifier is inside output directory, or current directory if absent.
+    pub fn run_dir(&self) -> Option<PathBuf> {
+        run_dir(self.output_dir.as_deref(), self.run_id.as_deref())
+    }
+
+    /// Maelstrom store directory of the run, inside [run directory](Self::run_dir) if present.
+    pub fn store(&self) -> PathBuf {
+        store(self.run_dir().as_deref())
+    }
+}
+
+/// Directory maelstrom runs in, if not current directory.
+///
+/// Directory of run identifier is inside output directory, or current directory if absent.
+pub fn run_dir(output_dir: Option<&Path>, run_id: Option<&str>) -> Option<PathBuf> {
+    match (output_dir, run_id) {
+        (output_dir, Some(run_id)) => Some(output_dir.unwrap_or(Path::new(".")).join(run_id)),
+        (output_dir, None) => output_dir.map(Path::to_path_buf),
+    }
+}
+
+/// Maelstrom store directory inside run directory, or current directory if absent.
+pub fn store(run_dir: Option<&Path>) -> PathBuf {
+    match run_dir {
+        Some(dir) => dir.join(STORE),
+        None => PathBuf::from(STORE),
+    }
+}
+
+/// Parse environment variable of form `KEY=VALUE`, value may be empty.
+fn parse_env(env: &str) -> Result<(String, String), String> {
+    match env.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got `{env}`")),
+    }
+}
+
 /// Challenges from Gossip Glomers.
 #[derive(Clone, ValueEnum, Parser, Debug)]
 #[clap(rename_all = "snake_case")]
@@ -63,42 +257,409 @@ impl Challange {
         }
         .to_string()
     }
+
+    /// Get default number of nodes for the challenge.
+    pub fn node_count(&self) -> usize {
+        match self {
+            Challange::Echo => 1,
+            Challange::UniqueIds | Challange::GrowOnlyCounter => 3,
+            Challange::SingleBroadcast => 1,
+            Challange::MultiBroadcast | Challange::FaultyBroadcast => 5,
+            Challange::EfficientBroadcast | Challange::EfficientBroadcast2 => 25,
+        }
+    }
+
+    /// Get default time limit in seconds for the challenge.
+    pub fn time_limit(&self) -> usize {
+        match self {
+            Challange::Echo => 10,
+            Challange::UniqueIds => 30,
+            Challange::SingleBroadcast
+            | Challange::MultiBroadcast
+            | Challange::FaultyBroadcast
+            | Challange::EfficientBroadcast
+            | Challange::EfficientBroadcast2
+            | Challange::GrowOnlyCounter => 20,
+        }
+    }
+
+    /// Get default requests per second for the challenge.
+    pub fn rate(&self) -> Option<usize> {
+        match self {
+            Challange::Echo => None,
+            Challange::UniqueIds => Some(1000),
+            Challange::SingleBroadcast | Challange::MultiBroadcast | Challange::FaultyBroadcast => {
+                Some(10)
+            }
+            Challange::EfficientBroadcast
+            | Challange::EfficientBroadcast2
+            | Challange::GrowOnlyCounter => Some(100),
+        }
+    }
+
+    /// Get whether network is partitioned during the challenge.
+    pub fn partition(&self) -> bool {
+        matches!(
+            self,
+            Challange::UniqueIds | Challange::FaultyBroadcast | Challange::GrowOnlyCounter
+        )
+    }
+
+    /// Get whether the challenge requires total availability.
+    pub fn total_availability(&self) -> bool {
+        matches!(self, Challange::UniqueIds)
+    }
+
+    /// Get default message latency in milliseconds for the challenge.
+    pub fn latency(&self) -> Option<usize> {
+        match self {
+            Challange::EfficientBroadcast | Challange::EfficientBroadcast2 => Some(100),
+            _ => None,
+        }
+    }
+
+    /// Get default broadcast topology for the challenge.
+    pub fn topology(&self) -> Option<&'static str> {
+        match self {
+            Challange::EfficientBroadcast => Some("tree4"),
+            _ => None,
+        }
+    }
+
+    /// Get environment variables the challenge program is run with.
+    pub fn env(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Challange::EfficientBroadcast2 => &[("FORCE_TICK", "false")],
+            _ => &[],
+        }
+    }
+
+    /// Get default thresholds checked against results of the challenge.
+    pub fn thresholds(&self) -> Option<Thresholds> {
+        match self {
+            Challange::Echo
+            | Challange::UniqueIds
+            | Challange::SingleBroadcast
+            | Challange::MultiBroadcast
+            | Challange::FaultyBroadcast
+            | Challange::GrowOnlyCounter => None,
+            Challange::EfficientBroadcast => Some(Thresholds {
+                msgs_per_op: 30.0,
+                median_latency: 400.0,
+                max_latency: 600.0,
+            }),
+            Challange::EfficientBroadcast2 => Some(Thresholds {
+                msgs_per_op: 20.0,
+                median_latency: 1000.0,
+                max_latency: 2000.0,
+            }),
+        }
+    }
+
+    /// Get time taken by reads to converge after the last write of history.
+    ///
+    /// Only broadcast and counter workloads have a converging value,
+    /// see [broadcast_convergence] and [counter_convergence].
+    ///
+    /// Returns `None` for other workloads, and `Some(None)` if reads never converge.
+    pub fn convergence(&self, history: &[edn_format::Value]) -> Option<Option<Duration>> {
+        match self.workload() {
+            "broadcast" => Some(broadcast_convergence(history)),
+            "g-counter" => Some(counter_convergence(history)),
+            _ => None,
+        }
+    }
+
+    /// Get name of the maelstrom workload run against the challenge program.
+    pub fn workload(&self) -> &'static str {
+        match self {
+            Challange::Echo => "echo",
+            Challange::UniqueIds => "unique-ids",
+            Challange::SingleBroadcast
+            | Challange::MultiBroadcast
+            | Challange::FaultyBroadcast
+            | Challange::EfficientBroadcast
+            | Challange::EfficientBroadcast2 => "broadcast",
+            Challange::GrowOnlyCounter => "g-counter",
+        }
+    }
+}
+
+/// Upper limits of metrics checked against results.
+#[derive(Debug, PartialEq)]
+pub struct Thresholds {
+    /// Maximum messages per operation.
+    pub msgs_per_op: f64,
+    /// Maximum median stable latency in milliseconds.
+    pub median_latency: f64,
+    /// Maximum stable latency in milliseconds.
+    pub max_latency: f64,
+}
+
+impl Thresholds {
+    /// Get thresholds of challenge, overridden by options if present.
+    pub fn from_options(opts: &RunOptions) -> Option<Self> {
+        let defaults = opts.challange.thresholds()?;
+        Some(Self {
+            msgs_per_op: opts.max_msgs_per_op.unwrap_or(defaults.msgs_per_op),
+            median_latency: opts.max_median_latency.unwrap_or(defaults.median_latency),
+            max_latency: opts.max_p100_latency.unwrap_or(defaults.max_latency),
+        })
+    }
+
+    /// Check results against thresholds.
+    ///
+    /// # Returns
+    /// Description of every metric which is missing or not below threshold.
+    pub fn violations(&self, result: &MaelStromResult) -> Vec<String> {
+        [
+            ("msgs-per-op", result.msgs_per_op(), self.msgs_per_op),
+            (
+                "median latency",
+                result.stable_latency(0.5),
+                self.median_latency,
+            ),
+            (
+                "maximum latency",
+                result.stable_latency(1.0),
+                self.max_latency,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, value, max)| match value {
+            Some(value) if value < max => None,
+            Some(value) => Some(format!("{name} {value} is not below {max}")),
+            None => Some(format!("failed to get {name}")),
+        })
+        .collect()
+    }
+}
+
+/// Path of binary built by cargo.
+///
+/// Binaries are placed in `target_dir`, which defaults to `target`.
+pub fn binary_path(target_dir: Option<String>, release: bool, bin_name: &str) -> String {
+    format!(
+        "{}/{}/{}",
+        target_dir.unwrap_or("target".to_string()),
+        if release { "release" } else { "debug" },
+        bin_name
+    )
 }
 
 /// Builds the challenge binary using cargo.
-fn build(release: bool, bin_name: &str) -> String {
+///
+/// Target directory is read from `CARGO_TARGET_DIR` at runtime.
+///
+/// # Returns
+/// Path to the built binary.
+pub fn build(release: bool, bin_name: &str) -> Result<String, XtaskError> {
     let mut args = vec!["build", "--bin", bin_name];
-    let profile = if release {
+    if release {
         args.push("--release");
-        "release"
-    } else {
-        "debug"
-    };
+    }
     let status = Command::new("cargo")
         .args(&args)
         .status()
-        .expect("failed to build!");
-    assert!(status.success());
-    format!(
-        "{}/{}/{}",
-        var("CARGO_TARGET_DIR").unwrap_or("target".to_string()),
-        profile,
-        bin_name
-    )
+        .map_err(|e| XtaskError::Invoke("cargo".to_string(), e))?;
+    if !status.success() {
+        return Err(XtaskError::Build(bin_name.to_string()));
+    }
+    Ok(binary_path(var("CARGO_TARGET_DIR").ok(), release, bin_name))
+}
+
+/// Maelstrom program with leading arguments.
+#[derive(Debug, PartialEq)]
+pub struct Maelstrom {
+    /// Program to invoke.
+    pub program: PathBuf,
+    /// Arguments passed before the maelstrom subcommand.
+    pub args: Vec<String>,
+}
+
+impl Maelstrom {
+    /// Create maelstrom invocation from binary location or command.
+    ///
+    /// Command is split on whitespace into program and leading arguments,
+    /// binary location is used if command is not present.
+    pub fn new(maelstrom_bin: &Path, maelstrom_cmd: Option<&str>) -> Self {
+        let mut tokens = maelstrom_cmd.into_iter().flat_map(str::split_whitespace);
+        match tokens.next() {
+            Some(program) => Self {
+                program: program.into(),
+                args: tokens.map(str::to_string).collect(),
+            },
+            None => Self {
+                program: maelstrom_bin.to_path_buf(),
+                args: vec![],
+            },
+        }
+    }
+
+    /// Resolve relative program from current directory, hence it can run from another directory.
+    ///
+    /// Programs on `PATH` are left as is.
+    pub fn absolute(mut self) -> Self {
+        if self.program.components().count() > 1 {
+            self.program = absolute(&self.program);
+        }
+        self
+    }
+
+    /// Create command invoking maelstrom with leading arguments.
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+        command
+    }
 }
 
 /// Helper for running maelstrom commands.
 ///
 /// [Docs](https://github.com/jepsen-io/maelstrom/blob/main/README.md#cli-options).
-struct MaelStromCommand(Command);
+pub struct MaelStromCommand(Command);
+
+/// Shell like command line, with environment variables and directory of the run.
+///
+/// Words with characters special to shell are single quoted.
+impl std::fmt::Display for MaelStromCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let quote = |word: &str| {
+            if !word.is_empty()
+                && word
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c))
+            {
+                word.to_string()
+            } else {
+                format!("'{}'", word.replace('\'', r"'\''"))
+            }
+        };
+        if let Some(dir) = self.get_current_dir() {
+            write!(f, "cd {} && ", quote(&dir.to_string_lossy()))?;
+        }
+        let envs = self
+            .envs()
+            .into_iter()
+            .map(|(key, value)| format!("{key}={}", quote(&value)));
+        let words = envs
+            .chain([quote(&self.program())])
+            .chain(self.args().iter().map(|arg| quote(arg)))
+            .collect::<Vec<_>>();
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+/// Results of maelstrom run parsed from `results.edn`.
+pub struct MaelStromResult(edn_format::Value);
 
-struct MaelStromResult(edn_format::Value);
+impl From<edn_format::Value> for MaelStromResult {
+    fn from(value: edn_format::Value) -> Self {
+        Self(value)
+    }
+}
 
 impl MaelStromResult {
     pub fn get_value_at<'a>(&'a self, path: &[edn_format::Value]) -> Option<&'a edn_format::Value> {
         Self::get_value_at_inner(&self.0, path)
     }
 
+    /// Get numeric value at path as float, see [edn_to_f64].
+    pub fn get_number_at(&self, path: &[edn_format::Value]) -> Option<f64> {
+        edn_to_f64(self.get_value_at(path)?)
+    }
+
+    /// Get numeric value at path of keywords.
+    fn get_number_at_keywords(&self, names: &[&str]) -> Option<f64> {
+        let path = names
+            .iter()
+            .map(|name| edn_format::Keyword::from_name(name).into())
+            .collect::<Vec<_>>();
+        self.get_number_at(&path)
+    }
+
+    /// Top level `:valid?` verdict, `None` if missing or not a boolean like `:unknown`.
+    pub fn valid(&self) -> Option<bool> {
+        match self.get_value_at(&[edn_format::Keyword::from_name("valid?").into()])? {
+            edn_format::Value::Boolean(valid) => Some(*valid),
+            _ => None,
+        }
+    }
+
+    /// Messages per operation between servers, at `:net :servers :msgs-per-op`.
+    pub fn msgs_per_op(&self) -> Option<f64> {
+        self.get_number_at_keywords(&["net", "servers", "msgs-per-op"])
+    }
+
+    /// Stable latency in milliseconds at percentile, like `0.5` for median or `1.0` for maximum.
+    ///
+    /// Looked up at `:workload :stable-latencies`,
+    /// where whole percentiles like `0` and `1` are integer keys.
+    pub fn stable_latency(&self, percentile: f64) -> Option<f64> {
+        let keyword = |name| edn_format::Keyword::from_name(name).into();
+        let path = |key| [keyword("workload"), keyword("stable-latencies"), key];
+        self.get_number_at(&path(percentile.into())).or_else(|| {
+            (percentile.fract() == 0.0)
+                .then(|| self.get_number_at(&path((percentile as i64).into())))
+                .flatten()
+        })
+    }
+
+    /// Fraction of operations which succeeded, at `:availability :ok-fraction`.
+    pub fn availability(&self) -> Option<f64> {
+        self.get_number_at_keywords(&["availability", "ok-fraction"])
+    }
+
+    /// Check top level `:valid?` verdict of results.
+    ///
+    /// Maelstrom may exit successfully even when analysis fails,
+    /// hence verdict is checked separately from exit status.
+    /// A verdict other than `true` or `:unknown` is invalid.
+    pub fn verdict(&self) -> Result<(), XtaskError> {
+        match self.get_value_at(&[edn_format::Keyword::from_name("valid?").into()]) {
+            Some(edn_format::Value::Boolean(true)) => Ok(()),
+            Some(edn_format::Value::Keyword(keyword)) if keyword.name() == "unknown" => {
+                Err(XtaskError::Unknown)
+            }
+            _ => Err(XtaskError::Invalid),
+        }
+    }
+
+    /// Summary of well known metrics of the run.
+    ///
+    /// Metrics missing from results are shown as `n/a`.
+    pub fn summary(&self) -> String {
+        let show = |value: Option<String>| value.unwrap_or_else(|| "n/a".to_string());
+        [
+            ("valid?", show(self.valid().map(|valid| valid.to_string()))),
+            (
+                "msgs-per-op",
+                show(self.msgs_per_op().map(|n| n.to_string())),
+            ),
+            (
+                "stable-latency p50",
+                show(self.stable_latency(0.5).map(|n| n.to_string())),
+            ),
+            (
+                "stable-latency p99",
+                show(self.stable_latency(0.99).map(|n| n.to_string())),
+            ),
+            (
+                "availability",
+                show(self.availability().map(|n| n.to_string())),
+            ),
+        ]
+        .into_iter()
+        .map(|(name, value)| format!("{name}: {value}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+
+    /// Convert results to JSON.
+    pub fn to_json(&self) -> serde_json::Value {
+        edn_to_json(&self.0)
+    }
+
     pub fn get_value_at_inner<'a>(
         value: &'a edn_format::Value,
         path: &[edn_format::Value],
@@ -111,6 +672,13 @@ impl MaelStromResult {
                 let value = map.get(&path[0])?;
                 Self::get_value_at_inner(value, &path[1..])
             }
+            edn_format::Value::Vector(vector) => match &path[0] {
+                edn_format::Value::Integer(index) => {
+                    let value = vector.get(usize::try_from(*index).ok()?)?;
+                    Self::get_value_at_inner(value, &path[1..])
+                }
+                _ => None,
+            },
             _ => None,
         }
     }
@@ -119,24 +687,32 @@ impl MaelStromResult {
 impl MaelStromCommand {
     /// create command to execute maelstrom.
     pub fn new(
-        maelstrom_bin: &PathBuf,
+        maelstrom: &Maelstrom,
         bin_path: &str,
-        bin_name: &str,
+        workload: &str,
         node_count: usize,
         time_limit: usize,
         extra_args: &[String],
     ) -> Self {
-        let mut command = Command::new(maelstrom_bin);
+        let mut command = maelstrom.command();
         command
             .arg("test")
-            .args(["-w", &bin_name.to_case(Case::Kebab)])
+            .args(["-w", workload])
             .args(["--bin", bin_path])
             .args(["--node-count", &node_count.to_string()])
-            .args(["--time-limit", &time_limit.to_string()])
+            .args(["--time-limit", &Self::time_limit(time_limit).to_string()])
             .args(extra_args);
         Self(command)
     }
 
+    /// Time limit passed to maelstrom, `0` is [NO_TIME_LIMIT].
+    pub fn time_limit(time_limit: usize) -> usize {
+        match time_limit {
+            0 => NO_TIME_LIMIT,
+            time_limit => time_limit,
+        }
+    }
+
     /// set any environment variable required by maelstrom or binary.
     pub fn env(mut self, key: &str, value: &str) -> Self {
         self.0.env(key, value);
@@ -155,12 +731,31 @@ impl MaelStromCommand {
         self
     }
 
+    /// Run maelstrom in directory, which is created if missing.
+    ///
+    /// Maelstrom creates its store inside the directory.
+    pub fn current_dir(mut self, dir: &Path) -> Self {
+        self.0.current_dir(dir);
+        self
+    }
+
+    /// Directory maelstrom runs in, if not current directory.
+    pub fn get_current_dir(&self) -> Option<&Path> {
+        self.0.get_current_dir()
+    }
+
     /// Changes rate.
     pub fn rate(mut self, rate: usize) -> Self {
         self.0.args(["--rate", &rate.to_string()]);
         self
     }
 
+    /// Changes concurrency, count of clients or multiple of node count like `2n`.
+    pub fn concurrency(mut self, concurrency: &str) -> Self {
+        self.0.args(["--concurrency", concurrency]);
+        self
+    }
+
     /// Changes latency.
     pub fn latency(mut self, latency: usize) -> Self {
         self.0.args(["--latency", &latency.to_string()]);
@@ -173,198 +768,621 @@ impl MaelStromCommand {
         self
     }
 
-    /// Executes command and makes sure it was a success.
-    pub fn execute(self) {
+    /// Environment variables set for maelstrom and binary.
+    pub fn envs(&self) -> Vec<(String, String)> {
+        self.0
+            .get_envs()
+            .filter_map(|(key, value)| {
+                Some((
+                    key.to_string_lossy().into_owned(),
+                    value?.to_string_lossy().into_owned(),
+                ))
+            })
+            .collect()
+    }
+
+    /// Program invoked, maelstrom or its leading command.
+    pub fn program(&self) -> String {
+        self.0.get_program().to_string_lossy().into_owned()
+    }
+
+    /// Arguments passed to maelstrom.
+    pub fn args(&self) -> Vec<String> {
+        self.0
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    /// Executes command, rerunning it on failure at most `reruns` times.
+    ///
+    /// # Returns
+    /// Attempt which was a success, starting from 1.
+    pub fn execute(self, reruns: usize) -> Result<usize, XtaskError> {
         let mut command = self.0;
-        let status = command
-            .status()
-            .unwrap_or_else(|e| panic!("command invocation failed {command:?} with error {e:?}!"));
-        assert!(status.success());
+        if let Some(dir) = command.get_current_dir() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| XtaskError::Invoke(format!("mkdir {}", dir.display()), e))?;
+        }
+        let program = command.get_program().to_string_lossy().into_owned();
+        let invoke = |e| XtaskError::Invoke(program.clone(), e);
+        interrupt::install();
+        let mut code = None;
+        for attempt in 1..=reruns + 1 {
+            let mut child = command.spawn().map_err(invoke)?;
+            let Some(status) =
+                interrupt::wait(&mut child, interrupt::interrupted).map_err(invoke)?
+            else {
+                return Err(XtaskError::Interrupted);
+            };
+            if status.success() {
+                return Ok(attempt);
+            }
+            eprintln!("attempt {attempt} failed with {status}");
+            code = status.code();
+        }
+        Err(XtaskError::Maelstrom {
+            code,
+            attempts: reruns + 1,
+        })
     }
 
-    pub fn get_results() -> MaelStromResult {
-        const FILE: &str = "store/current/results.edn";
-        MaelStromResult(
-            edn_format::parse_str(&std::fs::read_to_string(FILE).expect("could not open file"))
-                .expect("failed to parse result"),
-        )
+    /// Read results of last run from maelstrom store.
+    pub fn get_results(store: &Path) -> Result<MaelStromResult, XtaskError> {
+        let file = store.join("current/results.edn");
+        let results = std::fs::read_to_string(&file)
+            .map_err(|e| XtaskError::Results(format!("could not open {file:?}: {e}")))?;
+        edn_format::parse_str(&replace_ratios(&results))
+            .map(MaelStromResult)
+            .map_err(|e| XtaskError::Results(format!("could not parse {file:?}: {e}")))
     }
 }
 
-/// build and run the challenge
-pub fn run(opts: RunOptions) {
-    let bin_name = opts.challange.get_name();
-    let bin_path = build(opts.release, &bin_name);
-    match opts.challange {
-        Challange::Echo => {
-            MaelStromCommand::new(
-                &opts.maelstrom_bin,
-                &bin_path,
-                &bin_name,
-                1,
-                10,
-                &opts.extra_args,
-            )
-            .execute();
+/// Create maelstrom command to run the challenge binary.
+///
+/// Challenge defaults are overridden by options if present, including environment variables.
+/// Latency and topology options are appended, as last one wins in maelstrom.
+pub fn command(opts: &RunOptions, bin_path: &str) -> MaelStromCommand {
+    let challange = &opts.challange;
+    let mut extra_args = opts.extra_args.clone();
+    if opts.debug {
+        for arg in DEBUG_ARGS {
+            if !extra_args.iter().any(|extra| extra == arg) {
+                extra_args.push(arg.to_string());
+            }
         }
-        Challange::UniqueIds => {
-            MaelStromCommand::new(
-                &opts.maelstrom_bin,
-                &bin_path,
-                &bin_name,
-                3,
-                30,
-                &opts.extra_args,
-            )
-            .partition()
-            .rate(1000)
-            .total_availability()
-            .execute();
+    }
+    let mut maelstrom = Maelstrom::new(&opts.maelstrom_bin, opts.maelstrom_cmd.as_deref());
+    let mut bin_path = PathBuf::from(bin_path);
+    let run_dir = opts.run_dir();
+    if run_dir.is_some() {
+        maelstrom = maelstrom.absolute();
+        bin_path = absolute(&bin_path);
+    }
+    let mut command = MaelStromCommand::new(
+        &maelstrom,
+        &bin_path.to_string_lossy(),
+        challange.workload(),
+        opts.node_count.unwrap_or(challange.node_count()),
+        opts.time_limit.unwrap_or(challange.time_limit()),
+        &extra_args,
+    );
+    if let Some(rate) = opts.rate.or(challange.rate()) {
+        command = command.rate(rate);
+    }
+    if let Some(concurrency) = &opts.concurrency {
+        command = command.concurrency(concurrency);
+    }
+    if challange.partition() {
+        command = command.partition();
+    }
+    if challange.total_availability() {
+        command = command.total_availability();
+    }
+    for (key, value) in challange.env() {
+        command = command.env(key, value);
+    }
+    if let Some(latency) = challange.latency() {
+        command = command.latency(latency);
+    }
+    if let Some(topology) = challange.topology() {
+        command = command.topology(topology);
+    }
+    if let Some(latency) = opts.latency {
+        command = command.latency(latency);
+    }
+    if let Some(topology) = &opts.topology {
+        command = command.topology(topology);
+    }
+    if let Some(seed) = opts.seed {
+        command = command.env("GOSSIP_SEED", &seed.to_string());
+    }
+    for (key, value) in &opts.extra_env {
+        command = command.env(key, value);
+    }
+    if let Some(dir) = &run_dir {
+        command = command.current_dir(dir);
+    }
+    command
+}
+
+/// Path made absolute using current directory, path is left as is if that fails.
+pub(crate) fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or(path.to_path_buf())
+}
+
+/// Convert numeric EDN value to float, losing precision if needed.
+///
+/// Integers, floats, big integers and big decimals are numeric,
+/// ratios are floats once parsed, see [replace_ratios].
+pub fn edn_to_f64(value: &edn_format::Value) -> Option<f64> {
+    match value {
+        edn_format::Value::Integer(value) => Some(*value as f64),
+        edn_format::Value::Float(value) => Some(value.into_inner()),
+        edn_format::Value::BigInt(value) => value.to_string().parse().ok(),
+        edn_format::Value::BigDec(value) => value.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Replace ratios like `3/2` by floats like `1.5` in EDN text.
+///
+/// Maelstrom may write ratios, which EDN parser rejects.
+/// Strings, comments and characters like `\"` are left as is.
+pub fn replace_ratios(edn: &str) -> String {
+    let ratio = |token: &str| {
+        let (numerator, denominator) = token.split_once('/')?;
+        let numerator = numerator.parse::<i64>().ok()?;
+        let denominator = denominator.parse::<u64>().ok().filter(|d| *d != 0)?;
+        let ratio = (numerator as f64 / denominator as f64).to_string();
+        Some(if ratio.contains('.') {
+            ratio
+        } else {
+            format!("{ratio}.0")
+        })
+    };
+    let mut output = String::with_capacity(edn.len());
+    let mut token = String::new();
+    let (mut in_string, mut in_comment, mut escaped) = (false, false, false);
+    for c in edn.chars() {
+        if in_comment {
+            output.push(c);
+            in_comment = c != '\n';
+        } else if in_string {
+            output.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if escaped {
+            // character after `\` is part of character token, even a delimiter.
+            token.push(c);
+            escaped = false;
+        } else if c == '\\' && token.is_empty() {
+            token.push(c);
+            escaped = true;
+        } else if c.is_whitespace() || ",()[]{}\";".contains(c) {
+            let token = std::mem::take(&mut token);
+            output.push_str(&ratio(&token).unwrap_or(token));
+            output.push(c);
+            in_string = c == '"';
+            in_comment = c == ';';
+        } else {
+            token.push(c);
         }
-        Challange::SingleBroadcast => {
-            MaelStromCommand::new(
-                &opts.maelstrom_bin,
-                &bin_path,
-                &bin_name,
-                1,
-                20,
-                &opts.extra_args,
-            )
-            .rate(10)
-            .execute();
+    }
+    output.push_str(&ratio(&token).unwrap_or(token));
+    output
+}
+
+/// Convert EDN value to JSON value.
+///
+/// * keywords, symbols, characters, instants and uuids become strings, without leading `:`.
+/// * lists, vectors and sets become arrays.
+/// * map keys become strings, non string keys use their JSON representation.
+/// * big integers and decimals become numbers, losing precision if needed.
+/// * tagged elements become the tagged value.
+pub fn edn_to_json(value: &edn_format::Value) -> serde_json::Value {
+    use edn_format::Value;
+    use serde_json::Value as Json;
+    let number = |number: String| serde_json::from_str(&number).unwrap_or(Json::String(number));
+    match value {
+        Value::Nil => Json::Null,
+        Value::Boolean(boolean) => Json::Bool(*boolean),
+        Value::Character(character) => Json::String(character.to_string()),
+        Value::String(string) => Json::String(string.clone()),
+        Value::Symbol(symbol) => Json::String(match symbol.namespace() {
+            Some(namespace) => format!("{namespace}/{}", symbol.name()),
+            None => symbol.name().to_string(),
+        }),
+        Value::Keyword(keyword) => Json::String(match keyword.namespace() {
+            Some(namespace) => format!("{namespace}/{}", keyword.name()),
+            None => keyword.name().to_string(),
+        }),
+        Value::Integer(integer) => Json::from(*integer),
+        Value::Float(float) => Json::from(float.into_inner()),
+        Value::BigInt(integer) => number(integer.to_string()),
+        Value::BigDec(decimal) => number(decimal.to_string()),
+        Value::List(values) | Value::Vector(values) => {
+            Json::Array(values.iter().map(edn_to_json).collect())
         }
-        Challange::MultiBroadcast => {
-            MaelStromCommand::new(
-                &opts.maelstrom_bin,
-                &bin_path,
-                &bin_name,
-                5,
-                20,
-                &opts.extra_args,
+        Value::Set(values) => Json::Array(values.iter().map(edn_to_json).collect()),
+        Value::Map(map) => Json::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let key = match edn_to_json(key) {
+                        Json::String(key) => key,
+                        key => key.to_string(),
+                    };
+                    (key, edn_to_json(value))
+                })
+                .collect(),
+        ),
+        Value::Inst(instant) => Json::String(instant.to_rfc3339()),
+        Value::Uuid(uuid) => Json::String(uuid.to_string()),
+        Value::TaggedElement(_, value) => edn_to_json(value),
+    }
+}
+
+/// Describe what running the challenge with options will do.
+pub fn explain(opts: &RunOptions) -> String {
+    let challange = &opts.challange;
+    let or_default = |value: Option<String>| value.unwrap_or("maelstrom default".to_string());
+    let mut lines = vec![
+        format!(
+            "Run `{}` binary against maelstrom `{}` workload.",
+            challange.get_name(),
+            challange.workload()
+        ),
+        format!(
+            "* nodes: {}",
+            opts.node_count.unwrap_or(challange.node_count())
+        ),
+        format!(
+            "* time limit: {}",
+            match opts.time_limit.unwrap_or(challange.time_limit()) {
+                0 => "until interrupted".to_string(),
+                time_limit => format!("{time_limit}s"),
+            }
+        ),
+        format!(
+            "* rate: {}",
+            or_default(
+                opts.rate
+                    .or(challange.rate())
+                    .map(|rate| format!("{rate} requests per second"))
             )
-            .rate(10)
-            .execute();
-        }
-        Challange::FaultyBroadcast => {
-            MaelStromCommand::new(
-                &opts.maelstrom_bin,
-                &bin_path,
-                &bin_name,
-                5,
-                20,
-                &opts.extra_args,
+        ),
+        format!("* concurrency: {}", or_default(opts.concurrency.clone())),
+        format!(
+            "* latency: {}",
+            or_default(
+                opts.latency
+                    .or(challange.latency())
+                    .map(|latency| format!("{latency}ms"))
             )
-            .rate(10)
-            .partition()
-            .execute();
-        }
-        Challange::EfficientBroadcast => {
-            MaelStromCommand::new(
-                &opts.maelstrom_bin,
-                &bin_path,
-                &bin_name,
-                25,
-                20,
-                &opts.extra_args,
+        ),
+        format!(
+            "* topology: {}",
+            or_default(
+                opts.topology
+                    .clone()
+                    .or(challange.topology().map(str::to_string))
             )
-            .rate(100)
-            .latency(100)
-            .topology("tree4")
-            .execute();
-            let result = MaelStromCommand::get_results();
-            let message_per_op = result.get_value_at(&[
-                edn_format::Keyword::from_name("net").into(),
-                edn_format::Keyword::from_name("servers").into(),
-                edn_format::Keyword::from_name("msgs-per-op").into(),
-            ]);
-            assert!(
-                message_per_op.expect("failed to get message per ops")
-                    < &edn_format::Value::Float(30.0.into())
-            );
-            let median_latency = result.get_value_at(&[
-                edn_format::Keyword::from_name("workload").into(),
-                edn_format::Keyword::from_name("stable-latencies").into(),
-                0.5.into(),
-            ]);
-            assert!(
-                median_latency.expect("failed to get median latency")
-                    < &edn_format::Value::Integer(400)
-            );
-            let maximum_latency = result.get_value_at(&[
-                edn_format::Keyword::from_name("workload").into(),
-                edn_format::Keyword::from_name("stable-latencies").into(),
-                1.into(),
-            ]);
-            assert!(
-                maximum_latency.expect("failed to get maximum latency")
-                    < &edn_format::Value::Integer(600)
-            );
+        ),
+        format!(
+            "* nemesis: {}",
+            if challange.partition() {
+                "partition"
+            } else {
+                "none"
+            }
+        ),
+        format!(
+            "* availability: {}",
+            if challange.total_availability() {
+                "total"
+            } else {
+                "maelstrom default"
+            }
+        ),
+    ];
+    for (key, value) in challange.env() {
+        lines.push(format!("* env: {key}={value}"));
+    }
+    for (key, value) in &opts.extra_env {
+        lines.push(format!("* env: {key}={value}"));
+    }
+    lines.push(format!("* results: {}", opts.store().display()));
+    match Thresholds::from_options(opts) {
+        Some(thresholds) => lines.extend([
+            "Checks results for:".to_string(),
+            format!("* msgs-per-op below {}", thresholds.msgs_per_op),
+            format!("* median latency below {}ms", thresholds.median_latency),
+            format!("* maximum latency below {}ms", thresholds.max_latency),
+        ]),
+        None => lines.push("Checks only maelstrom verdict.".to_string()),
+    }
+    lines.join("\n")
+}
+
+/// Point [LATEST_RUN] link of output directory to run identifier.
+fn link_latest(output_dir: &Path, run_id: &str) -> Result<(), XtaskError> {
+    let latest = output_dir.join(LATEST_RUN);
+    std::fs::create_dir_all(output_dir)
+        .and_then(|_| match std::fs::remove_file(&latest) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            #[cfg(unix)]
+            _ => std::os::unix::fs::symlink(run_id, &latest),
+            #[cfg(not(unix))]
+            _ => std::fs::write(&latest, run_id),
+        })
+        .map_err(|e| XtaskError::Invoke(format!("link {}", latest.display()), e))
+}
+
+/// build and run the challenge
+///
+/// Run gets a default identifier, see [RunOptions::with_default_run_id].
+pub fn run(opts: RunOptions) -> Result<(), XtaskError> {
+    let opts = opts.with_default_run_id();
+    if opts.explain {
+        println!("{}", explain(&opts));
+        return Ok(());
+    }
+    let bin_name = opts.challange.get_name();
+    if opts.dry_run {
+        let bin_path = binary_path(var("CARGO_TARGET_DIR").ok(), opts.release, &bin_name);
+        println!("{}", command(&opts, &bin_path));
+        return Ok(());
+    }
+    if !opts.validate_only {
+        let bin_path = build(opts.release, &bin_name)?;
+        if let (Some(output_dir), Some(run_id)) = (&opts.output_dir, &opts.run_id) {
+            link_latest(output_dir, run_id)?;
         }
-        Challange::EfficientBroadcast2 => {
-            MaelStromCommand::new(
-                &opts.maelstrom_bin,
-                &bin_path,
-                &bin_name,
-                25,
-                20,
-                &opts.extra_args,
-            )
-            .env("FORCE_TICK", "false")
-            .rate(100)
-            .latency(100)
-            .execute();
-            let result = MaelStromCommand::get_results();
-            let message_per_op = result.get_value_at(&[
-                edn_format::Keyword::from_name("net").into(),
-                edn_format::Keyword::from_name("servers").into(),
-                edn_format::Keyword::from_name("msgs-per-op").into(),
-            ]);
-            assert!(
-                message_per_op.expect("failed to get message per ops")
-                    < &edn_format::Value::Float(20.0.into())
-            );
-            let median_latency = result.get_value_at(&[
-                edn_format::Keyword::from_name("workload").into(),
-                edn_format::Keyword::from_name("stable-latencies").into(),
-                0.5.into(),
-            ]);
-            assert!(
-                median_latency.expect("failed to get median latency")
-                    < &edn_format::Value::Integer(1000)
-            );
-            let maximum_latency = result.get_value_at(&[
-                edn_format::Keyword::from_name("workload").into(),
-                edn_format::Keyword::from_name("stable-latencies").into(),
-                1.into(),
-            ]);
-            assert!(
-                maximum_latency.expect("failed to get maximum latency")
-                    < &edn_format::Value::Integer(2000)
-            );
+        let attempt = command(&opts, &bin_path).execute(opts.rerun_on_fail)?;
+        if opts.rerun_on_fail > 0 {
+            println!("run succeeded on attempt {attempt}");
+        }
+    }
+    validate(&opts)
+}
+
+/// Summary line of convergence for challenges with a converging value,
+/// see [Challange::convergence].
+///
+/// Returns `None` if challenge has no converging value or history of the run is missing.
+pub fn convergence_summary(opts: &RunOptions) -> Option<String> {
+    let history = std::fs::read_to_string(opts.store().join("current/history.edn")).ok()?;
+    Some(
+        match opts.challange.convergence(&parse_history(&history))? {
+            Some(time) => format!("convergence: {}ms", time.as_millis()),
+            None => "convergence: n/a".to_string(),
+        },
+    )
+}
+
+/// Check results of the run against maelstrom verdict and thresholds.
+///
+/// Summary of results is printed, and results as JSON if asked for.
+/// Convergence is added to summary for challenges with a converging value,
+/// when history of the run is present.
+pub fn validate(opts: &RunOptions) -> Result<(), XtaskError> {
+    let results = MaelStromCommand::get_results(&opts.store())?;
+    println!("{}", results.summary());
+    if let Some(convergence) = convergence_summary(opts) {
+        println!("{convergence}");
+    }
+    if opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&results.to_json()).expect("failed to serialize results")
+        );
+    }
+    results.verdict()?;
+    if let Some(thresholds) = Thresholds::from_options(opts) {
+        let violations = thresholds.violations(&results);
+        if !violations.is_empty() {
+            return Err(XtaskError::Assertion(violations));
         }
-        Challange::GrowOnlyCounter => {
-            MaelStromCommand::new(
-                &opts.maelstrom_bin,
-                &bin_path,
-                &bin_name,
-                3,
-                20,
-                &opts.extra_args,
+    }
+    Ok(())
+}
+
+/// Parse operation history of a maelstrom run.
+///
+/// The history is a sequence of operation maps, one per line.
+pub fn parse_history(history: &str) -> Vec<edn_format::Value> {
+    edn_format::Parser::from_str(history, Default::default())
+        .collect::<Result<_, _>>()
+        .expect("failed to parse history")
+}
+
+/// Read operation history of last maelstrom run from maelstrom store.
+pub fn read_history(store: &Path) -> Vec<edn_format::Value> {
+    let file = store.join("current/history.edn");
+    parse_history(&std::fs::read_to_string(file).expect("could not open file"))
+}
+
+/// Get value of key in operation of history.
+fn op_get<'a>(op: &'a edn_format::Value, key: &str) -> Option<&'a edn_format::Value> {
+    match op {
+        edn_format::Value::Map(map) => map.get(&edn_format::Keyword::from_name(key).into()),
+        _ => None,
+    }
+}
+
+/// Get integer value of key in operation of history.
+fn op_integer(op: &edn_format::Value, key: &str) -> Option<i64> {
+    match op_get(op, key)? {
+        edn_format::Value::Integer(value) => Some(*value),
+        _ => None,
+    }
+}
+
+/// Operations of history which completed with `:ok` for function `f`.
+fn ok_ops<'a>(
+    history: &'a [edn_format::Value],
+    f: &'a str,
+) -> impl Iterator<Item = &'a edn_format::Value> {
+    let keyword = |name: &str| edn_format::Value::from(edn_format::Keyword::from_name(name));
+    history.iter().filter(move |op| {
+        op_get(op, "type") == Some(&keyword("ok")) && op_get(op, "f") == Some(&keyword(f))
+    })
+}
+
+/// Time from `last_write` until every client process has read a converged value.
+///
+/// Each `:process` converges at its first `:ok` read at or after `last_write` whose value converged,
+/// processes with no such read are not considered.
+///
+/// Returns `None` if no process read after `last_write`, or some process never converged.
+fn converged_after(
+    history: &[edn_format::Value],
+    last_write: i64,
+    converged: impl Fn(&edn_format::Value) -> bool,
+) -> Option<Duration> {
+    let mut processes = BTreeMap::new();
+    for (process, time, value) in ok_ops(history, "read").filter_map(|op| {
+        Some((
+            op_get(op, "process"),
+            op_integer(op, "time")?,
+            op_get(op, "value")?,
+        ))
+    }) {
+        if time < last_write {
+            continue;
+        }
+        let first = processes.entry(process).or_insert(None);
+        if first.is_none() && converged(value) {
+            *first = Some(time);
+        }
+    }
+    processes
+        .into_values()
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .max()
+        .map(|time| Duration::from_nanos((time - last_write) as u64))
+}
+
+/// Time taken by counter reads to converge after the last write.
+///
+/// Uses the following keys of each operation in history:
+/// * `:type`: only `:ok` operations are considered.
+/// * `:f`: `:add` operations are writes and `:read` operations are reads.
+/// * `:value`: delta of `:add` and counter value of `:read`.
+/// * `:time`: nanoseconds since start of the run when operation completed.
+///
+/// The expected value is the sum of all `:ok` adds,
+/// indeterminate (`:info`) adds are hence assumed to have failed.
+///
+/// Returns `None` if some client process never reads the expected value after the last write,
+/// see [converged_after].
+pub fn counter_convergence(history: &[edn_format::Value]) -> Option<Duration> {
+    let (total, last_write) = ok_ops(history, "add").fold((0, 0), |(total, last_write), op| {
+        (
+            total + op_integer(op, "value").unwrap_or_default(),
+            last_write.max(op_integer(op, "time").unwrap_or_default()),
+        )
+    });
+    converged_after(history, last_write, |value| {
+        *value == edn_format::Value::Integer(total)
+    })
+}
+
+/// Time taken by broadcast reads to converge after the last broadcast.
+///
+/// Uses the following keys of each operation in history:
+/// * `:type`: only `:ok` operations are considered.
+/// * `:f`: `:broadcast` operations are writes and `:read` operations are reads.
+/// * `:value`: message of `:broadcast` and messages seen by `:read`.
+/// * `:time`: nanoseconds since start of the run when operation completed.
+///
+/// The expected messages are those of all `:ok` broadcasts,
+/// a read converged once it contains all of them.
+/// Every client process talks to one node, hence value reached all nodes
+/// once reads of every process converged.
+///
+/// Returns `None` if some client process never reads every message after the last broadcast,
+/// see [converged_after].
+pub fn broadcast_convergence(history: &[edn_format::Value]) -> Option<Duration> {
+    let (messages, last_write) =
+        ok_ops(history, "broadcast").fold((Vec::new(), 0), |(mut messages, last_write), op| {
+            messages.extend(op_get(op, "value").cloned());
+            (
+                messages,
+                last_write.max(op_integer(op, "time").unwrap_or_default()),
             )
-            .rate(100)
-            .partition()
-            .execute();
+        });
+    converged_after(history, last_write, |value| match value {
+        edn_format::Value::Vector(seen) | edn_format::Value::List(seen) => {
+            messages.iter().all(|message| seen.contains(message))
         }
+        edn_format::Value::Set(seen) => messages.iter().all(|message| seen.contains(message)),
+        _ => false,
+    })
+}
+
+/// Options to list command.
+#[derive(Parser, Debug)]
+pub struct ListOptions {
+    /// Show description, binary, workload and node count of challenges
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+/// List of challenges.
+///
+/// Plain list has one challenge name per line,
+/// verbose list is an aligned table with details of challenge.
+pub fn listing(verbose: bool) -> String {
+    let names = Challange::value_variants()
+        .iter()
+        .map(|var| var.to_possible_value().unwrap().get_name().to_string());
+    if !verbose {
+        return names.map(|name| format!("{name}\n")).collect();
     }
+    let rows =
+        std::iter::once(["NAME", "DESCRIPTION", "BINARY", "WORKLOAD", "NODES"].map(String::from))
+            .chain(
+                Challange::value_variants()
+                    .iter()
+                    .zip(names)
+                    .map(|(var, name)| {
+                        [
+                            name,
+                            var.to_possible_value()
+                                .unwrap()
+                                .get_help()
+                                .map(ToString::to_string)
+                                .unwrap_or_default(),
+                            var.get_name(),
+                            var.workload().to_string(),
+                            var.node_count().to_string(),
+                        ]
+                    }),
+            )
+            .collect::<Vec<_>>();
+    let widths = (0..5)
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+    rows.iter()
+        .map(|row| {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
 }
 
-/// list challenges.
-pub fn list() {
-    print!(
-        "{}",
-        Challange::value_variants()
-            .iter()
-            .map(|var| format!("{}\n", var.to_possible_value().unwrap().get_name()))
-            .collect::<String>()
-    );
+/// List all challenges.
+pub fn list(opts: ListOptions) {
+    print!("{}", listing(opts.verbose));
 }
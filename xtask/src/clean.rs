@@ -0,0 +1,61 @@
+//! Module to clean results of challange.
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+
+/// Options to clean command.
+#[derive(Parser, Debug)]
+pub struct CleanOptions {
+    /// Number of most recent runs to keep per workload
+    #[arg(long, default_value_t = 0)]
+    pub keep: usize,
+
+    /// Maelstrom store directory, relative to the project root
+    #[arg(long, default_value = "store")]
+    pub store: PathBuf,
+}
+
+/// Root directory of the project.
+fn project_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .canonicalize()
+        .expect("failed to resolve project root")
+}
+
+/// Remove maelstrom results.
+///
+/// Runs are named by their start time, hence the most recent runs sort last.
+///
+/// # Panics
+///
+/// Panics if store is not inside the project root.
+pub fn clean(opts: CleanOptions) {
+    let root = project_root();
+    let Ok(store) = root.join(&opts.store).canonicalize() else {
+        return;
+    };
+    assert!(
+        store.starts_with(&root) && store != root,
+        "refusing to clean {store:?} outside of {root:?}"
+    );
+    if opts.keep == 0 {
+        std::fs::remove_dir_all(&store).expect("failed to remove store");
+        return;
+    }
+    for workload in std::fs::read_dir(&store).expect("failed to read store") {
+        let workload = workload.expect("failed to read store").path();
+        if workload.is_symlink() || !workload.is_dir() {
+            continue;
+        }
+        let mut runs = std::fs::read_dir(&workload)
+            .expect("failed to read workload")
+            .map(|run| run.expect("failed to read workload").path())
+            .filter(|run| !run.is_symlink() && run.is_dir())
+            .collect::<Vec<_>>();
+        runs.sort();
+        for run in runs.iter().rev().skip(opts.keep) {
+            std::fs::remove_dir_all(run).expect("failed to remove run");
+        }
+    }
+}
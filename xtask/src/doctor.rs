@@ -0,0 +1,150 @@
+//! Module to check environment needed to run challange.
+use std::{
+    env::var,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use clap::Parser;
+
+use crate::{
+    challange::{build, Maelstrom},
+    error::XtaskError,
+};
+
+/// Options to doctor command.
+#[derive(Parser, Debug)]
+pub struct DoctorOptions {
+    /// Maelstrom binary location
+    #[arg(short, long, env, default_value = "maelstrom")]
+    pub maelstrom_bin: PathBuf,
+
+    /// Maelstrom command with leading arguments, overrides binary location
+    ///
+    /// Example: `java -jar maelstrom.jar`
+    #[arg(long, env)]
+    pub maelstrom_cmd: Option<String>,
+
+    /// Java binary location
+    #[arg(long, default_value = "java")]
+    pub java: PathBuf,
+
+    /// Package binary to build
+    #[arg(long, default_value = "echo")]
+    pub bin: String,
+}
+
+/// Result of single environment check.
+#[derive(Debug)]
+pub struct Check {
+    /// What was checked.
+    pub name: &'static str,
+    /// Output on success, reason on failure.
+    pub result: Result<String, String>,
+    /// How to fix failure.
+    pub hint: &'static str,
+}
+
+impl Check {
+    /// Check whether command runs successfully.
+    ///
+    /// First line of output is kept, stderr is used if stdout is empty.
+    pub fn command(name: &'static str, mut command: Command, hint: &'static str) -> Self {
+        let program = command.get_program().to_string_lossy().into_owned();
+        let result = match command.output() {
+            Ok(output) => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let line = stdout
+                    .lines()
+                    .chain(stderr.lines())
+                    .find(|line| !line.trim().is_empty())
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                if output.status.success() {
+                    Ok(line)
+                } else {
+                    Err(format!("{program} exited with {}: {line}", output.status))
+                }
+            }
+            Err(error) => Err(format!("failed to invoke {program}: {error}")),
+        };
+        Self { name, result, hint }
+    }
+
+    /// Check whether directory is writable, directory is created if missing.
+    pub fn writable(name: &'static str, dir: &Path, hint: &'static str) -> Self {
+        let probe = dir.join(".xtask-doctor");
+        let result = std::fs::create_dir_all(dir)
+            .and_then(|_| std::fs::write(&probe, b""))
+            .and_then(|_| std::fs::remove_file(&probe))
+            .map(|_| dir.display().to_string())
+            .map_err(|error| format!("{} is not writable: {error}", dir.display()));
+        Self { name, result, hint }
+    }
+
+    /// Whether the check passed.
+    pub fn passed(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Run every environment check.
+///
+/// Target directory is read from `CARGO_TARGET_DIR` at runtime, defaults to `target`.
+pub fn checks(opts: &DoctorOptions) -> Vec<Check> {
+    let mut maelstrom =
+        Maelstrom::new(&opts.maelstrom_bin, opts.maelstrom_cmd.as_deref()).command();
+    maelstrom.arg("--version");
+    let mut java = Command::new(&opts.java);
+    java.arg("-version");
+    let target_dir = PathBuf::from(var("CARGO_TARGET_DIR").unwrap_or("target".to_string()));
+    vec![
+        Check::command(
+            "maelstrom",
+            maelstrom,
+            "install maelstrom and add it to `PATH`, or pass `-m` or `MAELSTROM_BIN`",
+        ),
+        Check::command("java", java, "install a JDK, maelstrom runs on the JVM"),
+        Check::writable(
+            "target dir",
+            &target_dir,
+            "make target dir writable or set `CARGO_TARGET_DIR`",
+        ),
+        Check {
+            name: "cargo build",
+            result: build(false, &opts.bin)
+                .map(|bin_path| format!("built {bin_path}"))
+                .map_err(|error| error.to_string()),
+            hint: "fix build errors reported by cargo above",
+        },
+    ]
+}
+
+/// Report checks, with hint for every failed check.
+pub fn report(checks: &[Check]) -> String {
+    checks
+        .iter()
+        .map(|check| match &check.result {
+            Ok(output) => format!("[pass] {}: {output}", check.name),
+            Err(reason) => format!("[fail] {}: {reason}\n  hint: {}", check.name, check.hint),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Check environment and report the checks.
+pub fn doctor(opts: DoctorOptions) -> Result<(), XtaskError> {
+    let checks = checks(&opts);
+    println!("{}", report(&checks));
+    let failed = checks
+        .iter()
+        .filter(|check| !check.passed())
+        .map(|check| check.name.to_string())
+        .collect::<Vec<_>>();
+    if !failed.is_empty() {
+        return Err(XtaskError::Doctor(failed));
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+//! Module to describe failures of xtask.
+use std::fmt::{Display, Formatter};
+
+/// Failure of xtask command.
+#[derive(Debug)]
+pub enum XtaskError {
+    /// Building the challenge binary failed.
+    Build(String),
+    /// Command could not be invoked.
+    Invoke(String, std::io::Error),
+    /// Maelstrom exited with failure.
+    Maelstrom {
+        /// Exit code of last attempt, `None` if killed by signal.
+        code: Option<i32>,
+        /// Number of attempts made.
+        attempts: usize,
+    },
+    /// Results could not be read or parsed.
+    Results(String),
+    /// Results did not satisfy thresholds.
+    Assertion(Vec<String>),
+    /// Maelstrom verdict of results is not valid.
+    Invalid,
+    /// Maelstrom could not determine validity of results.
+    Unknown,
+    /// Environment checks failed.
+    Doctor(Vec<String>),
+    /// Xtask was interrupted, maelstrom was stopped.
+    Interrupted,
+}
+
+impl XtaskError {
+    /// Exit code of xtask for the error.
+    ///
+    /// Unknown verdict exits with `2`, interrupt with `130` like shells do,
+    /// every other error with `1`.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            XtaskError::Unknown => 2,
+            XtaskError::Interrupted => 130,
+            _ => 1,
+        }
+    }
+}
+
+impl Display for XtaskError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XtaskError::Build(bin_name) => write!(f, "failed to build {bin_name}"),
+            XtaskError::Invoke(command, error) => {
+                write!(f, "failed to invoke {command}: {error}")
+            }
+            XtaskError::Maelstrom {
+                code: Some(code),
+                attempts,
+            } => write!(
+                f,
+                "maelstrom failed with exit code {code} after {attempts} attempts"
+            ),
+            XtaskError::Maelstrom {
+                code: None,
+                attempts,
+            } => write!(
+                f,
+                "maelstrom was terminated by signal after {attempts} attempts"
+            ),
+            XtaskError::Results(error) => write!(f, "failed to get results: {error}"),
+            XtaskError::Assertion(violations) => {
+                write!(f, "results failed checks: {}", violations.join(", "))
+            }
+            XtaskError::Invalid => write!(f, "maelstrom verdict is invalid"),
+            XtaskError::Unknown => write!(f, "maelstrom verdict is unknown"),
+            XtaskError::Doctor(checks) => {
+                write!(f, "environment checks failed: {}", checks.join(", "))
+            }
+            XtaskError::Interrupted => write!(f, "interrupted, maelstrom was stopped"),
+        }
+    }
+}
+
+impl std::error::Error for XtaskError {}
@@ -0,0 +1,94 @@
+//! Module to stop maelstrom when xtask is interrupted.
+//!
+//! Signals are handled only on unix, elsewhere xtask is never [interrupted]
+//! and the child is killed on stop.
+use std::{
+    io,
+    process::{Child, ExitStatus},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+/// Interval of polling child for exit or interrupt.
+pub const POLL: Duration = Duration::from_millis(50);
+
+/// Time child gets to exit after `SIGTERM`, before it is killed.
+pub const GRACE: Duration = Duration::from_secs(5);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_signal(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Install handler of `SIGINT` and `SIGTERM`, which only records the interrupt.
+///
+/// Xtask keeps running after the signal, hence it can stop the child before exiting.
+#[cfg(unix)]
+pub fn install() {
+    let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // SAFETY: handler only stores to an atomic, which is async signal safe.
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+/// Install handler of interrupts, which is not supported on this target.
+#[cfg(not(unix))]
+pub fn install() {}
+
+/// Whether `SIGINT` or `SIGTERM` was received after [install].
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Forget interrupt received so far, hence later runs are not stopped by it.
+pub fn reset() {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+}
+
+/// Wait for child to exit, stopping it if interrupted.
+///
+/// Child is sent `SIGTERM` and killed if it does not exit within [GRACE].
+///
+/// # Returns
+/// Exit status of child, `None` if child was stopped on interrupt.
+pub fn wait(child: &mut Child, interrupted: impl Fn() -> bool) -> io::Result<Option<ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if interrupted() {
+            stop(child)?;
+            return Ok(None);
+        }
+        std::thread::sleep(POLL);
+    }
+}
+
+/// Terminate child, killing it if it outlives [GRACE].
+#[cfg(unix)]
+fn stop(child: &mut Child) -> io::Result<()> {
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: child is not reaped yet, hence pid still refers to it.
+        unsafe { libc::kill(pid, libc::SIGTERM) };
+    }
+    let start = std::time::Instant::now();
+    while start.elapsed() < GRACE {
+        if child.try_wait()?.is_some() {
+            return Ok(());
+        }
+        std::thread::sleep(POLL);
+    }
+    child.kill()?;
+    child.wait().map(|_| ())
+}
+
+/// Kill child, as it cannot be asked to terminate on this target.
+#[cfg(not(unix))]
+fn stop(child: &mut Child) -> io::Result<()> {
+    child.kill()?;
+    child.wait().map(|_| ())
+}
@@ -1,7 +1,14 @@
 //! Utility to run Gossip Glomers challenge.
 use clap::Parser;
 
+pub mod bench;
 pub mod challange;
+pub mod clean;
+pub mod doctor;
+pub mod error;
+pub mod interrupt;
+pub mod pipe;
+pub mod raw;
 pub mod serve;
 
 /// CLI to run Gossip Glomers challenge.
@@ -18,8 +25,18 @@ pub struct Xtask {
 pub enum XtaskCommand {
     /// Run some challenge.
     Run(challange::RunOptions),
+    /// Run binary against any maelstrom workload, without challenge presets.
+    RunRaw(raw::RawOptions),
+    /// Pipe messages of file to challenge binary, without maelstrom.
+    Pipe(pipe::PipeOptions),
+    /// Run some challenge multiple times and report metrics.
+    Bench(bench::BenchOptions),
     /// Serve results of previous run challenges.
     Serve(serve::ServeOptions),
     /// List all challenges.
-    List,
+    List(challange::ListOptions),
+    /// Remove results of previous run challenges.
+    Clean(clean::CleanOptions),
+    /// Check environment needed to run challenges.
+    Doctor(doctor::DoctorOptions),
 }
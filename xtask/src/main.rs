@@ -1,13 +1,33 @@
 //! Utility to run Gossip Glomers challenge.
 use clap::Parser;
-use xtask::{challange, serve, Xtask, XtaskCommand};
+use xtask::{
+    bench, challange, clean, doctor, error::XtaskError, pipe, raw, serve, Xtask, XtaskCommand,
+};
 
 /// Parse and run the CLI.
 fn main() {
     let opts = Xtask::parse();
-    match opts.command {
+    let result = match opts.command {
         XtaskCommand::Run(options) => challange::run(options),
+        XtaskCommand::RunRaw(options) => raw::run_raw(options),
+        XtaskCommand::Pipe(options) => pipe::pipe(options),
+        XtaskCommand::Bench(options) => bench::bench(options),
         XtaskCommand::Serve(options) => serve::serve(options),
-        XtaskCommand::List => challange::list(),
+        XtaskCommand::List(options) => {
+            challange::list(options);
+            Ok(())
+        }
+        XtaskCommand::Clean(options) => {
+            clean::clean(options);
+            Ok(())
+        }
+        XtaskCommand::Doctor(options) => doctor::doctor(options),
+    };
+    if let Err(error) = result {
+        match error {
+            XtaskError::Unknown => eprintln!("warning: {error}"),
+            _ => eprintln!("error: {error}"),
+        }
+        std::process::exit(error.exit_code());
     }
 }
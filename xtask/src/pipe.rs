@@ -0,0 +1,69 @@
+//! Module to pipe messages to challange binary without maelstrom.
+use std::{
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use clap::Parser;
+
+use crate::{
+    challange::{build, Challange},
+    error::XtaskError,
+};
+
+/// Options to pipe command.
+#[derive(Parser, Debug)]
+pub struct PipeOptions {
+    /// Package binary to build
+    #[arg(value_enum)]
+    pub challange: Challange,
+
+    /// File of messages, one JSON message per line
+    pub input_file: PathBuf,
+
+    /// Build and run the release target
+    #[clap(long)]
+    pub release: bool,
+}
+
+/// Run binary with input piped to stdin, until binary exits on end of input.
+///
+/// Stderr of binary is inherited, hence logs are shown as is.
+///
+/// # Returns
+/// Output of binary.
+pub fn pipe_output(bin_path: &str, input: &[u8]) -> Result<Vec<u8>, XtaskError> {
+    let invoke = |e| XtaskError::Invoke(bin_path.to_string(), e);
+    let mut child = Command::new(bin_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(invoke)?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // input is written while output is read, hence large output does not block binary.
+    // stdin is closed once written, which ends input of binary.
+    let (written, output) = std::thread::scope(|scope| {
+        let written = scope.spawn(move || stdin.write_all(input));
+        let output = child.wait_with_output();
+        (written.join().expect("failed to write input"), output)
+    });
+    let output = output.map_err(invoke)?;
+    // binary may exit before reading all of input.
+    match written {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(invoke(e)),
+        _ => Ok(output.stdout),
+    }
+}
+
+/// Build the challenge binary and print its output for messages of input file.
+pub fn pipe(opts: PipeOptions) -> Result<(), XtaskError> {
+    let input = std::fs::read(&opts.input_file)
+        .map_err(|e| XtaskError::Invoke(format!("read of {}", opts.input_file.display()), e))?;
+    let bin_path = build(opts.release, &opts.challange.get_name())?;
+    let output = pipe_output(&bin_path, &input)?;
+    std::io::stdout()
+        .write_all(&output)
+        .expect("failed to write output");
+    Ok(())
+}
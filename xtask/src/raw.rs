@@ -0,0 +1,107 @@
+//! Module to run binary against arbitrary maelstrom workload.
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::{
+    challange::{absolute, build, run_dir, store, MaelStromCommand, Maelstrom},
+    error::XtaskError,
+};
+
+/// Options to run raw command.
+#[derive(Parser, Debug)]
+pub struct RawOptions {
+    /// Package binary to build
+    pub bin: String,
+
+    /// Maelstrom workload to run binary against
+    ///
+    /// Example: `echo`, `unique-ids`, `txn-list-append`
+    #[arg(short, long)]
+    pub workload: String,
+
+    /// Maelstrom binary location
+    #[arg(short, long, env, default_value = "maelstrom")]
+    pub maelstrom_bin: PathBuf,
+
+    /// Maelstrom command with leading arguments, overrides binary location
+    ///
+    /// Example: `java -jar maelstrom.jar`
+    #[arg(long, env)]
+    pub maelstrom_cmd: Option<String>,
+
+    /// Build and run the release target
+    #[clap(long)]
+    pub release: bool,
+
+    /// Number of nodes
+    #[arg(long, default_value_t = 1)]
+    pub node_count: usize,
+
+    /// Time limit in seconds, `0` runs until interrupted
+    #[arg(long, default_value_t = 10)]
+    pub time_limit: usize,
+
+    /// Directory to run maelstrom in, results are read from its store
+    ///
+    /// Defaults to current directory, use different directories to run in parallel.
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Identifier of the run, maelstrom runs in directory of this name inside output directory
+    #[arg(long)]
+    pub run_id: Option<String>,
+
+    /// Extra arguments to be passed to maelstrom.
+    ///
+    /// Example: `--rate`, `--nemesis`, `--log-stderr`
+    #[clap(last = true)]
+    pub extra_args: Vec<String>,
+}
+
+impl RawOptions {
+    /// Directory maelstrom runs in, if not current directory, see [run_dir].
+    pub fn run_dir(&self) -> Option<PathBuf> {
+        run_dir(self.output_dir.as_deref(), self.run_id.as_deref())
+    }
+
+    /// Maelstrom store directory of the run, see [store].
+    pub fn store(&self) -> PathBuf {
+        store(self.run_dir().as_deref())
+    }
+}
+
+/// Create maelstrom command to run the binary against workload.
+///
+/// No challenge presets are applied, anything else is passed through extra arguments.
+/// Maelstrom runs in [run directory](RawOptions::run_dir) if present.
+pub fn command(opts: &RawOptions, bin_path: &str) -> MaelStromCommand {
+    let mut maelstrom = Maelstrom::new(&opts.maelstrom_bin, opts.maelstrom_cmd.as_deref());
+    let mut bin_path = PathBuf::from(bin_path);
+    let run_dir = opts.run_dir();
+    if run_dir.is_some() {
+        maelstrom = maelstrom.absolute();
+        bin_path = absolute(&bin_path);
+    }
+    let command = MaelStromCommand::new(
+        &maelstrom,
+        &bin_path.to_string_lossy(),
+        &opts.workload,
+        opts.node_count,
+        opts.time_limit,
+        &opts.extra_args,
+    );
+    match &run_dir {
+        Some(dir) => command.current_dir(dir),
+        None => command,
+    }
+}
+
+/// Build binary and run it against workload.
+pub fn run_raw(opts: RawOptions) -> Result<(), XtaskError> {
+    let bin_path = build(opts.release, &opts.bin)?;
+    command(&opts, &bin_path).execute(0)?;
+    let results = MaelStromCommand::get_results(&opts.store())?;
+    println!("{}", results.summary());
+    results.verdict()
+}
@@ -3,19 +3,53 @@ use std::{path::PathBuf, process::Command};
 
 use clap::Parser;
 
+use crate::{challange::Maelstrom, error::XtaskError};
+
 /// Options to serve command.
 #[derive(Parser, Debug)]
 pub struct ServeOptions {
     /// Maelstrom binary location
     #[arg(short, long, env, default_value = "maelstrom")]
     pub maelstrom_bin: PathBuf,
+
+    /// Maelstrom command with leading arguments, overrides binary location
+    ///
+    /// Example: `java -jar maelstrom.jar`
+    #[arg(long, env)]
+    pub maelstrom_cmd: Option<String>,
+
+    /// Port to serve results on
+    #[arg(short, long)]
+    pub port: Option<u16>,
+
+    /// Extra arguments to be passed to maelstrom serve.
+    #[clap(last = true)]
+    pub extra_args: Vec<String>,
+}
+
+/// Create maelstrom command to serve results.
+pub fn command(opts: &ServeOptions) -> Command {
+    let maelstrom = Maelstrom::new(&opts.maelstrom_bin, opts.maelstrom_cmd.as_deref());
+    let mut command = maelstrom.command();
+    command.arg("serve");
+    if let Some(port) = opts.port {
+        command.args(["--port", &port.to_string()]);
+    }
+    command.args(&opts.extra_args);
+    command
 }
 
 /// Serve maelstrom results.
-pub fn serve(opts: ServeOptions) {
-    let status = Command::new(opts.maelstrom_bin)
-        .args(["serve"])
+pub fn serve(opts: ServeOptions) -> Result<(), XtaskError> {
+    let mut command = command(&opts);
+    let status = command
         .status()
-        .expect("failed to serve!");
-    assert!(status.success());
+        .map_err(|e| XtaskError::Invoke(command.get_program().to_string_lossy().into_owned(), e))?;
+    if !status.success() {
+        return Err(XtaskError::Maelstrom {
+            code: status.code(),
+            attempts: 1,
+        });
+    }
+    Ok(())
 }
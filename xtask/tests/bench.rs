@@ -0,0 +1,92 @@
+use xtask::{
+    bench::{aggregate, csv_row, Distribution, CSV_HEADER},
+    challange::MaelStromResult,
+};
+
+/// Parse fixture result file.
+fn fixture(name: &str) -> MaelStromResult {
+    let file = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/bench")
+        .join(name);
+    edn_format::parse_str(&std::fs::read_to_string(file).unwrap())
+        .expect("valid edn")
+        .into()
+}
+
+/// test distribution of values.
+#[test]
+fn test_distribution() {
+    assert_eq!(Distribution::new(vec![]), None);
+    assert_eq!(
+        Distribution::new(vec![4.0, 1.0, 3.0, 2.0]),
+        Some(Distribution {
+            min: 1.0,
+            median: 2.5,
+            max: 4.0
+        })
+    );
+}
+
+/// test aggregation of metrics across fixture results.
+#[test]
+fn test_aggregate() {
+    let results = ["run1.edn", "run2.edn", "run3.edn", "failed.edn"].map(fixture);
+    assert_eq!(
+        aggregate(&results),
+        [
+            (
+                "msgs-per-op",
+                Some(Distribution {
+                    min: 20.5,
+                    median: 22.0,
+                    max: 24.0
+                })
+            ),
+            (
+                "latency p50",
+                Some(Distribution {
+                    min: 250.0,
+                    median: 300.0,
+                    max: 350.0
+                })
+            ),
+            (
+                "latency p99",
+                Some(Distribution {
+                    min: 400.0,
+                    median: 450.0,
+                    max: 500.0
+                })
+            ),
+            (
+                "latency p100",
+                Some(Distribution {
+                    min: 500.0,
+                    median: 550.0,
+                    max: 600.0
+                })
+            ),
+        ]
+    );
+    assert!(aggregate(&[fixture("failed.edn")])
+        .iter()
+        .all(|(_, distribution)| distribution.is_none()));
+}
+
+/// test CSV header and rows of fixture results, with empty cells for missing fields.
+#[test]
+fn test_csv() {
+    assert_eq!(
+        CSV_HEADER,
+        "run,rate,msgs-per-op,latency p50,latency p100,valid"
+    );
+    assert_eq!(
+        csv_row(1, Some(100), Some(&fixture("run1.edn"))),
+        "1,100,20.5,300,500,true"
+    );
+    assert_eq!(
+        csv_row(2, None, Some(&fixture("failed.edn"))),
+        "2,,,,,false"
+    );
+    assert_eq!(csv_row(3, Some(10), None), "3,10,,,,");
+}
@@ -0,0 +1,806 @@
+use clap::ValueEnum;
+use std::time::Duration;
+use xtask::challange::{
+    binary_path, broadcast_convergence, build, command, convergence_summary, counter_convergence,
+    edn_to_json, explain, listing, parse_history, replace_ratios, run, Challange, MaelStromCommand,
+    MaelStromResult, Maelstrom, RunOptions, Thresholds, DEBUG_ARGS, LATEST_RUN, NO_TIME_LIMIT,
+    STORE,
+};
+use xtask::error::XtaskError;
+
+mod testutil;
+use testutil::fake_maelstrom;
+
+/// test maelstrom workload of each challenge.
+#[test]
+fn test_workload() {
+    for (challange, workload) in [
+        (Challange::Echo, "echo"),
+        (Challange::UniqueIds, "unique-ids"),
+        (Challange::SingleBroadcast, "broadcast"),
+        (Challange::MultiBroadcast, "broadcast"),
+        (Challange::FaultyBroadcast, "broadcast"),
+        (Challange::EfficientBroadcast, "broadcast"),
+        (Challange::EfficientBroadcast2, "broadcast"),
+        (Challange::GrowOnlyCounter, "g-counter"),
+    ] {
+        assert_eq!(challange.workload(), workload, "{challange:?}");
+    }
+}
+
+/// test counter convergence from history.
+#[test]
+fn test_counter_convergence() {
+    let history = parse_history(
+        r#"
+        {:type :invoke, :f :add, :value 3, :time 100, :process 0, :index 0}
+        {:type :ok, :f :add, :value 3, :time 200, :process 0, :index 1}
+        {:type :invoke, :f :add, :value 4, :time 300, :process 1, :index 2}
+        {:type :ok, :f :add, :value 4, :time 1000, :process 1, :index 3}
+        {:type :invoke, :f :read, :value nil, :time 1100, :process 0, :index 4}
+        {:type :ok, :f :read, :value 3, :time 1200, :process 0, :index 5}
+        {:type :invoke, :f :read, :value nil, :time 1300, :process 1, :index 6}
+        {:type :ok, :f :read, :value 7, :time 1500, :process 1, :index 7}
+        {:type :invoke, :f :read, :value nil, :time 1600, :process 0, :index 8}
+        {:type :ok, :f :read, :value 7, :time 1800, :process 0, :index 9}
+        "#,
+    );
+    assert_eq!(
+        counter_convergence(&history),
+        Some(Duration::from_nanos(800))
+    );
+    // process 0 has not read the sum yet.
+    assert_eq!(counter_convergence(&history[..8]), None);
+    assert_eq!(counter_convergence(&history[..4]), None);
+}
+
+/// test broadcast convergence from history is when reads of every process see all messages.
+#[test]
+fn test_broadcast_convergence() {
+    let history = parse_history(
+        r#"
+        {:type :invoke, :f :broadcast, :value 0, :time 100, :process 0, :index 0}
+        {:type :ok, :f :broadcast, :value 0, :time 200, :process 0, :index 1}
+        {:type :invoke, :f :broadcast, :value 1, :time 300, :process 1, :index 2}
+        {:type :ok, :f :broadcast, :value 1, :time 1000, :process 1, :index 3}
+        {:type :invoke, :f :read, :value nil, :time 1100, :process 0, :index 4}
+        {:type :ok, :f :read, :value [0], :time 1200, :process 0, :index 5}
+        {:type :invoke, :f :read, :value nil, :time 1300, :process 1, :index 6}
+        {:type :ok, :f :read, :value [1 0], :time 1700, :process 1, :index 7}
+        {:type :invoke, :f :read, :value nil, :time 1800, :process 0, :index 8}
+        {:type :ok, :f :read, :value [0 1], :time 2000, :process 0, :index 9}
+        "#,
+    );
+    assert_eq!(
+        broadcast_convergence(&history),
+        Some(Duration::from_nanos(1000))
+    );
+    // process 0 has not seen message 1 yet.
+    assert_eq!(broadcast_convergence(&history[..8]), None);
+    assert_eq!(
+        Challange::EfficientBroadcast.convergence(&history),
+        Some(Some(Duration::from_nanos(1000)))
+    );
+    assert_eq!(Challange::UniqueIds.convergence(&history), None);
+}
+
+/// test every challenge is wired to a binary and a workload.
+#[test]
+fn test_challanges_wired() {
+    for challange in <Challange as clap::ValueEnum>::value_variants() {
+        let bin = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../src/bin")
+            .join(format!("{}.rs", challange.get_name()));
+        assert!(bin.exists(), "{challange:?} has no binary at {bin:?}");
+        assert!(
+            !challange.workload().is_empty(),
+            "{challange:?} has no workload"
+        );
+    }
+}
+
+/// Check if arguments contain the flag with value.
+fn has_arg(args: &[String], flag: &str, value: &str) -> bool {
+    args.windows(2).any(|arg| arg[0] == flag && arg[1] == value)
+}
+
+/// test command line overrides of challenge defaults.
+#[test]
+fn test_command_overrides() {
+    let opts = <RunOptions as clap::Parser>::parse_from([
+        "run",
+        "multi_broadcast",
+        "--node-count",
+        "50",
+        "--rate",
+        "20",
+    ]);
+    let args = command(&opts, "target/debug/broadcast").args();
+    assert!(has_arg(&args, "--node-count", "50"), "{args:?}");
+    assert!(has_arg(&args, "--rate", "20"), "{args:?}");
+    assert!(has_arg(&args, "--time-limit", "20"), "{args:?}");
+    assert!(!has_arg(&args, "--rate", "10"), "{args:?}");
+}
+
+/// test latency and topology options are appended after challenge defaults.
+#[test]
+fn test_command_latency_topology() {
+    let opts = <RunOptions as clap::Parser>::parse_from([
+        "run",
+        "efficient_broadcast",
+        "--topology",
+        "line",
+        "--latency",
+        "50",
+    ]);
+    assert_eq!(opts.topology.as_deref(), Some("line"));
+    assert_eq!(opts.latency, Some(50));
+    let args = command(&opts, "target/debug/broadcast").args();
+    let position = |flag: &str, value: &str| {
+        args.windows(2)
+            .rposition(|arg| arg[0] == flag && arg[1] == value)
+    };
+    assert!(position("--topology", "line") > position("--topology", "tree4"));
+    assert!(position("--latency", "50") > position("--latency", "100"));
+}
+
+/// test result lookup descends into maps and vectors.
+#[test]
+fn test_result_value_at() {
+    let value =
+        edn_format::parse_str(r#"{:workload {:latencies [10 {:max 20}]}}"#).expect("valid edn");
+    let path = |index: i64| {
+        vec![
+            edn_format::Keyword::from_name("workload").into(),
+            edn_format::Keyword::from_name("latencies").into(),
+            index.into(),
+        ]
+    };
+    assert_eq!(
+        MaelStromResult::get_value_at_inner(&value, &path(0)),
+        Some(&edn_format::Value::Integer(10))
+    );
+    let mut max = path(1);
+    max.push(edn_format::Keyword::from_name("max").into());
+    assert_eq!(
+        MaelStromResult::get_value_at_inner(&value, &max),
+        Some(&edn_format::Value::Integer(20))
+    );
+    assert_eq!(MaelStromResult::get_value_at_inner(&value, &path(2)), None);
+    assert_eq!(MaelStromResult::get_value_at_inner(&value, &path(-1)), None);
+}
+
+/// test conversion of results to JSON.
+#[test]
+fn test_edn_to_json() {
+    let value = edn_format::parse_str(
+        r#"{:net {:servers {:msgs-per-op 12.5}}
+            :workload {:stable-latencies {0 1, 0.5 100}
+                       :valid? true
+                       :lost-count 1.5M
+                       :attempt #{:ok}
+                       :history [nil "a" \b]}
+            :ns/key :ns/value}"#,
+    )
+    .expect("valid edn");
+    assert_eq!(
+        edn_to_json(&value),
+        serde_json::json!({
+            "net": {"servers": {"msgs-per-op": 12.5}},
+            "workload": {
+                "stable-latencies": {"0": 1, "0.5": 100},
+                "valid?": true,
+                "lost-count": 1.5,
+                "attempt": ["ok"],
+                "history": [null, "a", "b"],
+            },
+            "ns/key": "ns/value",
+        })
+    );
+}
+
+/// test summary of results shows known metrics and n/a for missing ones.
+#[test]
+fn test_result_summary() {
+    let result: MaelStromResult = edn_format::parse_str(
+        r#"{:valid? true
+            :net {:servers {:msgs-per-op 12.5}}
+            :workload {:stable-latencies {0 0, 0.5 100, 0.95 150, 1 200}}}"#,
+    )
+    .expect("valid edn")
+    .into();
+    assert_eq!(
+        result.summary(),
+        "valid?: true\n\
+         msgs-per-op: 12.5\n\
+         stable-latency p50: 100\n\
+         stable-latency p99: n/a\n\
+         availability: n/a"
+    );
+}
+
+/// test threshold overrides are checked against results.
+#[test]
+fn test_threshold_overrides() {
+    let result: MaelStromResult = edn_format::parse_str(
+        r#"{:net {:servers {:msgs-per-op 25.0}}
+            :workload {:stable-latencies {0.5 300, 1 500}}}"#,
+    )
+    .expect("valid edn")
+    .into();
+    let opts = <RunOptions as clap::Parser>::parse_from(["run", "efficient_broadcast"]);
+    let thresholds = Thresholds::from_options(&opts).expect("has thresholds");
+    assert!(thresholds.violations(&result).is_empty());
+
+    let opts = <RunOptions as clap::Parser>::parse_from([
+        "run",
+        "efficient_broadcast",
+        "--max-msgs-per-op",
+        "20",
+        "--max-p100-latency",
+        "450",
+    ]);
+    let thresholds = Thresholds::from_options(&opts).expect("has thresholds");
+    assert_eq!(
+        thresholds,
+        Thresholds {
+            msgs_per_op: 20.0,
+            median_latency: 400.0,
+            max_latency: 450.0,
+        }
+    );
+    assert_eq!(
+        thresholds.violations(&result),
+        [
+            "msgs-per-op 25 is not below 20",
+            "maximum latency 500 is not below 450",
+        ]
+    );
+
+    let opts = <RunOptions as clap::Parser>::parse_from(["run", "echo", "--max-msgs-per-op", "1"]);
+    assert_eq!(Thresholds::from_options(&opts), None);
+}
+
+/// test explanation of efficient broadcast run.
+#[test]
+fn test_explain() {
+    let opts =
+        <RunOptions as clap::Parser>::parse_from(["run", "efficient_broadcast", "--explain"]);
+    assert!(opts.explain);
+    let explanation = explain(&opts);
+    assert!(explanation.contains("* topology: tree4"), "{explanation}");
+    assert!(
+        explanation.contains("* msgs-per-op below 30"),
+        "{explanation}"
+    );
+}
+
+/// test results are read from store and checked against thresholds.
+#[test]
+fn test_results_fixture() {
+    let store = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/store");
+    let result = MaelStromCommand::get_results(&store).expect("fixture is valid");
+    assert_eq!(
+        result.get_value_at(&[
+            edn_format::Keyword::from_name("workload").into(),
+            edn_format::Keyword::from_name("stable-latencies").into(),
+            0.5.into(),
+        ]),
+        Some(&edn_format::Value::Integer(312))
+    );
+    let opts = <RunOptions as clap::Parser>::parse_from(["run", "efficient_broadcast"]);
+    let thresholds = Thresholds::from_options(&opts).expect("has thresholds");
+    assert!(thresholds.violations(&result).is_empty());
+}
+
+/// Create fake maelstrom which fails till marker file exists.
+fn flaky_maelstrom(name: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+    let bin = fake_maelstrom(
+        name,
+        "[ -e \"$MARKER\" ] && exit 0\ntouch \"$MARKER\"\nexit 1\n",
+    );
+    let marker = bin.with_file_name("marker");
+    (bin, marker)
+}
+
+/// test failed run is rerun.
+#[test]
+fn test_retry_failed_maelstrom() {
+    let (bin, marker) = flaky_maelstrom("rerun-on-fail");
+    let attempt = MaelStromCommand::new(&Maelstrom::new(&bin, None), "echo", "echo", 1, 1, &[])
+        .env("MARKER", marker.to_str().unwrap())
+        .execute(1);
+    assert_eq!(attempt.expect("second attempt succeeds"), 2);
+}
+
+/// test results are read from store of output directory maelstrom ran in.
+#[test]
+fn test_output_dir() {
+    let bin = fake_maelstrom(
+        "output-dir",
+        "mkdir -p store/current\necho '{:valid? true}' > store/current/results.edn\n",
+    );
+    let dir = bin.parent().unwrap();
+    let output_dir = dir.join("runs/1");
+    let opts = <RunOptions as clap::Parser>::parse_from([
+        "run",
+        "echo",
+        "-m",
+        bin.to_str().unwrap(),
+        "--output-dir",
+        output_dir.to_str().unwrap(),
+    ]);
+    assert_eq!(opts.store(), output_dir.join(STORE));
+    let command = command(&opts, "target/debug/echo");
+    assert_eq!(command.get_current_dir(), Some(output_dir.as_path()));
+    let args = command.args();
+    let bin_path = &args[args.iter().position(|arg| arg == "--bin").unwrap() + 1];
+    assert!(std::path::Path::new(bin_path).is_absolute(), "{args:?}");
+    command.execute(0).expect("fake maelstrom succeeds");
+    let result = MaelStromCommand::get_results(&opts.store()).expect("results in output dir");
+    assert_eq!(result.verdict().ok(), Some(()));
+}
+
+/// test runs in output directory get own run id unless given, validation reads latest run.
+#[test]
+fn test_default_run_id() {
+    let parse = |args: &[&str]| {
+        <RunOptions as clap::Parser>::parse_from(["run", "echo"].iter().chain(args))
+            .with_default_run_id()
+    };
+    let opts = parse(&["--output-dir", "runs"]);
+    let run_id = opts.run_id.clone().expect("default run id");
+    assert!(run_id.starts_with("echo-"), "{run_id}");
+    assert_eq!(
+        opts.store(),
+        std::path::Path::new("runs").join(&run_id).join(STORE)
+    );
+    assert_ne!(parse(&["--output-dir", "runs"]).run_id, Some(run_id));
+    assert_eq!(
+        parse(&["--output-dir", "runs", "--run-id", "1"])
+            .run_id
+            .as_deref(),
+        Some("1")
+    );
+    assert_eq!(
+        parse(&["--output-dir", "runs", "--validate-only"])
+            .run_id
+            .as_deref(),
+        Some(LATEST_RUN)
+    );
+    assert_eq!(parse(&[]).run_id, None);
+
+    // targets without symbolic links name latest run in a file.
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("latest-file");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(LATEST_RUN), "echo-1\n").unwrap();
+    let opts = parse(&["--output-dir", dir.to_str().unwrap(), "--validate-only"]);
+    assert_eq!(opts.run_id.as_deref(), Some("echo-1"));
+}
+
+/// test concurrent runs with different run ids read their own results.
+#[test]
+fn test_parallel_runs() {
+    // arguments start with `test -w <workload>`, results mention the workload.
+    let bin = fake_maelstrom(
+        "run-ids",
+        "sleep 0.2\nmkdir -p store/current\n\
+         echo \"{:valid? true :workload \\\"$3\\\"}\" > store/current/results.edn\n",
+    );
+    let dir = bin.parent().unwrap();
+    let runs = [Challange::Echo, Challange::UniqueIds].map(|challange| {
+        let opts = <RunOptions as clap::Parser>::parse_from([
+            "run",
+            challange.to_possible_value().unwrap().get_name(),
+            "-m",
+            bin.to_str().unwrap(),
+            "--output-dir",
+            dir.to_str().unwrap(),
+            "--run-id",
+            &challange.get_name(),
+        ]);
+        let command = command(&opts, "target/debug/echo");
+        (opts, std::thread::spawn(move || command.execute(0)))
+    });
+    for (opts, run) in runs {
+        run.join().unwrap().expect("fake maelstrom succeeds");
+        assert_eq!(
+            opts.run_dir(),
+            Some(dir.join(opts.challange.get_name())),
+            "{opts:?}"
+        );
+        let result = MaelStromCommand::get_results(&opts.store()).expect("results of run");
+        assert_eq!(
+            result.get_value_at(&[edn_format::Keyword::from_name("workload").into()]),
+            Some(&edn_format::Value::String(
+                opts.challange.workload().to_string()
+            ))
+        );
+    }
+}
+
+/// test dry run prints command of constructed arguments without running maelstrom.
+#[test]
+fn test_dry_run() {
+    let opts = <RunOptions as clap::Parser>::parse_from([
+        "run",
+        "efficient_broadcast2",
+        "-m",
+        "no/such/maelstrom",
+        "--extra-env",
+        "NOTE=it's slow",
+        "--dry-run",
+    ]);
+    let command = command(&opts, "target/debug/broadcast");
+    assert_eq!(
+        command.to_string(),
+        format!(
+            "FORCE_TICK=false NOTE='it'\\''s slow' no/such/maelstrom {}",
+            command.args().join(" ")
+        )
+    );
+    assert!(command.args().starts_with(&[
+        "test".to_string(),
+        "-w".to_string(),
+        "broadcast".to_string()
+    ]));
+    run(opts).expect("dry run neither builds nor runs");
+}
+
+/// test failed run without reruns fails.
+#[test]
+fn test_fail_without_rerun() {
+    let (bin, marker) = flaky_maelstrom("fail-without-rerun");
+    let result = MaelStromCommand::new(&Maelstrom::new(&bin, None), "echo", "echo", 1, 1, &[])
+        .env("MARKER", marker.to_str().unwrap())
+        .execute(0);
+    assert!(
+        matches!(
+            result,
+            Err(XtaskError::Maelstrom {
+                code: Some(1),
+                attempts: 1
+            })
+        ),
+        "{result:?}"
+    );
+}
+
+/// test building unknown binary fails.
+#[test]
+fn test_build_failure() {
+    let result = build(false, "no_such_challenge");
+    assert!(
+        matches!(&result, Err(XtaskError::Build(bin_name)) if bin_name == "no_such_challenge"),
+        "{result:?}"
+    );
+}
+
+/// test missing results fail.
+#[test]
+fn test_missing_results() {
+    let result = MaelStromCommand::get_results(std::path::Path::new("no/such/store"));
+    assert!(matches!(result, Err(XtaskError::Results(_))));
+}
+
+/// test plain and verbose listing of challenges.
+#[test]
+fn test_listing() {
+    let plain = listing(false);
+    assert_eq!(plain.lines().next(), Some("echo"));
+    assert_eq!(plain.lines().count(), Challange::value_variants().len());
+
+    let verbose = listing(true);
+    let mut lines = verbose.lines();
+    let header = lines.next().expect("has header");
+    assert!(header.starts_with("NAME"), "{verbose}");
+    let row = lines
+        .find(|line| line.starts_with("efficient_broadcast2 "))
+        .expect("has efficient broadcast two");
+    assert_eq!(
+        row.split("  ")
+            .filter(|cell| !cell.is_empty())
+            .map(str::trim)
+            .collect::<Vec<_>>(),
+        [
+            "efficient_broadcast2",
+            "Efficient broadcast two",
+            "broadcast",
+            "broadcast",
+            "25"
+        ]
+    );
+    let columns = header.find("BINARY").expect("has binary column");
+    assert!(
+        verbose
+            .lines()
+            .skip(1)
+            .all(|line| line[columns..].starts_with(|c: char| c.is_alphabetic())),
+        "{verbose}"
+    );
+}
+
+/// test binary path respects target directory.
+#[test]
+fn test_binary_path() {
+    assert_eq!(binary_path(None, false, "echo"), "target/debug/echo");
+    assert_eq!(
+        binary_path(Some("/tmp/custom".to_string()), true, "broadcast"),
+        "/tmp/custom/release/broadcast"
+    );
+}
+
+/// test maelstrom command is split into program and leading arguments.
+#[test]
+fn test_maelstrom_cmd() {
+    let opts = <RunOptions as clap::Parser>::parse_from([
+        "run",
+        "echo",
+        "--maelstrom-cmd",
+        "java -jar maelstrom.jar",
+    ]);
+    let maelstrom = Maelstrom::new(&opts.maelstrom_bin, opts.maelstrom_cmd.as_deref());
+    assert_eq!(
+        maelstrom,
+        Maelstrom {
+            program: "java".into(),
+            args: vec!["-jar".to_string(), "maelstrom.jar".to_string()],
+        }
+    );
+    let args = command(&opts, "target/debug/echo").args();
+    assert_eq!(args[..4], ["-jar", "maelstrom.jar", "test", "-w"]);
+
+    let opts = <RunOptions as clap::Parser>::parse_from(["run", "echo", "-m", "bin/maelstrom"]);
+    assert_eq!(
+        Maelstrom::new(&opts.maelstrom_bin, opts.maelstrom_cmd.as_deref()),
+        Maelstrom {
+            program: "bin/maelstrom".into(),
+            args: vec![],
+        }
+    );
+}
+
+/// test seed is passed to binary.
+#[test]
+fn test_command_seed() {
+    let opts = <RunOptions as clap::Parser>::parse_from(["run", "multi_broadcast", "--seed", "42"]);
+    let envs = command(&opts, "target/debug/broadcast").envs();
+    assert!(
+        envs.contains(&("GOSSIP_SEED".to_string(), "42".to_string())),
+        "{envs:?}"
+    );
+}
+
+/// test repeated extra env options are set on binary, overriding challenge env.
+#[test]
+fn test_command_extra_env() {
+    let opts = <RunOptions as clap::Parser>::parse_from([
+        "run",
+        "efficient_broadcast2",
+        "--extra-env",
+        "TICK_TIME=100",
+        "--extra-env",
+        "FORCE_TICK=true",
+        "--extra-env",
+        "GOSSIP_FANOUT=",
+    ]);
+    assert_eq!(
+        opts.extra_env,
+        [
+            ("TICK_TIME", "100"),
+            ("FORCE_TICK", "true"),
+            ("GOSSIP_FANOUT", "")
+        ]
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+    );
+    let envs = command(&opts, "target/debug/broadcast").envs();
+    for env in &opts.extra_env {
+        assert!(envs.contains(env), "{envs:?}");
+    }
+    assert!(
+        !envs.contains(&("FORCE_TICK".to_string(), "false".to_string())),
+        "{envs:?}"
+    );
+    for invalid in ["TICK_TIME", "=100"] {
+        let parsed =
+            <RunOptions as clap::Parser>::try_parse_from(["run", "echo", "--extra-env", invalid]);
+        assert!(parsed.is_err(), "{invalid}");
+    }
+}
+
+/// test debug flag adds maelstrom logging arguments once.
+#[test]
+fn test_command_debug() {
+    let opts = <RunOptions as clap::Parser>::parse_from(["run", "echo", "--debug"]);
+    let args = command(&opts, "target/debug/echo").args();
+    assert_eq!(args[args.len() - 3..], DEBUG_ARGS);
+
+    let opts = <RunOptions as clap::Parser>::parse_from([
+        "run",
+        "echo",
+        "--debug",
+        "--",
+        "--log-stderr",
+        "--concurrency",
+        "2",
+    ]);
+    let args = command(&opts, "target/debug/echo").args();
+    assert_eq!(
+        args[args.len() - 5..],
+        [
+            "--log-stderr",
+            "--concurrency",
+            "2",
+            "--log-net-send",
+            "--log-net-recv"
+        ]
+    );
+
+    let opts = <RunOptions as clap::Parser>::parse_from(["run", "echo"]);
+    let args = command(&opts, "target/debug/echo").args();
+    assert!(!args.iter().any(|arg| arg == "--log-stderr"), "{args:?}");
+}
+
+/// test maelstrom verdict of fixture results.
+#[test]
+fn test_verdict() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/verdict");
+    let verdict = |name| {
+        MaelStromCommand::get_results(&fixtures.join(name))
+            .expect("fixture is valid")
+            .verdict()
+    };
+    assert!(verdict("valid").is_ok());
+    let invalid = verdict("invalid").unwrap_err();
+    assert!(matches!(invalid, XtaskError::Invalid));
+    assert_eq!(invalid.exit_code(), 1);
+    let unknown = verdict("unknown").unwrap_err();
+    assert!(matches!(unknown, XtaskError::Unknown));
+    assert_eq!(unknown.exit_code(), 2);
+
+    let missing = MaelStromResult::from(edn_format::parse_str("{}").unwrap()).verdict();
+    assert!(matches!(missing, Err(XtaskError::Invalid)));
+}
+
+/// test typed accessors of fixture results.
+#[test]
+fn test_result_accessors() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let result =
+        |name| MaelStromCommand::get_results(&fixtures.join(name)).expect("fixture is valid");
+    let store = result("store");
+    assert_eq!(store.valid(), Some(true));
+    assert_eq!(store.msgs_per_op(), Some(23.98906));
+    assert_eq!(store.stable_latency(0.0), Some(0.0));
+    assert_eq!(store.stable_latency(0.5), Some(312.0));
+    assert_eq!(store.stable_latency(0.99), Some(491.0));
+    assert_eq!(store.stable_latency(1.0), Some(530.0));
+    assert_eq!(store.stable_latency(0.75), None);
+    assert_eq!(store.availability(), Some(1.0));
+    assert!(store
+        .summary()
+        .ends_with("stable-latency p99: 491\navailability: 1"));
+
+    assert_eq!(result("verdict/valid").valid(), Some(true));
+    assert_eq!(result("verdict/invalid").valid(), Some(false));
+    assert_eq!(result("verdict/unknown").valid(), None);
+    let unknown = result("verdict/unknown");
+    assert_eq!(unknown.msgs_per_op(), None);
+    assert_eq!(unknown.stable_latency(0.5), None);
+    assert_eq!(unknown.availability(), None);
+}
+
+/// test ratios and big numbers of results are normalized to floats.
+#[test]
+fn test_result_ratio() {
+    assert_eq!(
+        replace_ratios(r#"{:a 3/2, :b [-1/4 4/2] :c "3/2" :d n1/2 :e 1/0}"#),
+        r#"{:a 1.5, :b [-0.25 2.0] :c "3/2" :d n1/2 :e 1/0}"#
+    );
+    assert_eq!(
+        replace_ratios("[\\\" 1/2 \\; 3/4 ; 5/6 \"7/8\"\n \\newline 9/8]"),
+        "[\\\" 0.5 \\; 0.75 ; 5/6 \"7/8\"\n \\newline 1.125]"
+    );
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ratio");
+    let result = MaelStromCommand::get_results(&fixture).expect("fixture is valid");
+    assert_eq!(result.msgs_per_op(), Some(1.5));
+    assert_eq!(result.stable_latency(0.5), Some(12.5));
+    assert_eq!(result.stable_latency(1.0), Some(100.0));
+    assert_eq!(result.availability(), Some(1.0));
+    let note = result.get_value_at(&[
+        edn_format::Keyword::from_name("workload").into(),
+        edn_format::Keyword::from_name("note").into(),
+    ]);
+    assert_eq!(
+        note,
+        Some(&edn_format::Value::String("latency 1/2 of limit".into()))
+    );
+    let thresholds = Thresholds {
+        msgs_per_op: 1.0,
+        median_latency: 20.0,
+        max_latency: 100.0,
+    };
+    assert_eq!(
+        thresholds.violations(&result),
+        [
+            "msgs-per-op 1.5 is not below 1",
+            "maximum latency 100 is not below 100"
+        ]
+    );
+}
+
+/// test concurrency is passed to maelstrom only when set.
+#[test]
+fn test_command_concurrency() {
+    let opts = <RunOptions as clap::Parser>::parse_from(["run", "echo", "--concurrency", "2n"]);
+    let args = command(&opts, "target/debug/echo").args();
+    assert!(
+        args.windows(2).any(|arg| arg == ["--concurrency", "2n"]),
+        "{args:?}"
+    );
+    assert!(explain(&opts).contains("* concurrency: 2n"));
+
+    let opts = <RunOptions as clap::Parser>::parse_from(["run", "echo"]);
+    let args = command(&opts, "target/debug/echo").args();
+    assert!(!args.iter().any(|arg| arg == "--concurrency"), "{args:?}");
+}
+
+/// test zero time limit runs until interrupted.
+#[test]
+fn test_command_no_time_limit() {
+    let opts = <RunOptions as clap::Parser>::parse_from(["run", "echo", "--time-limit", "0"]);
+    let args = command(&opts, "target/debug/echo").args();
+    assert!(
+        has_arg(&args, "--time-limit", &NO_TIME_LIMIT.to_string()),
+        "{args:?}"
+    );
+    assert!(explain(&opts).contains("* time limit: until interrupted"));
+}
+
+/// test validate only checks results of fixture store without building or spawning maelstrom.
+#[test]
+fn test_validate_only() {
+    let tests = concat!(env!("CARGO_MANIFEST_DIR"), "/tests");
+    let validate = |challange: &str, run_id: &str, extra: &[&str]| {
+        let args = [
+            "run",
+            challange,
+            "-m",
+            "/no/such/maelstrom",
+            "--validate-only",
+            "--output-dir",
+            tests,
+            "--run-id",
+            run_id,
+        ];
+        run(<RunOptions as clap::Parser>::parse_from(
+            args.iter().chain(extra),
+        ))
+    };
+    assert!(validate("efficient_broadcast", "fixtures", &[]).is_ok());
+    let result = validate("efficient_broadcast2", "fixtures", &[]);
+    assert!(
+        matches!(&result, Err(XtaskError::Assertion(violations)) if violations.len() == 1),
+        "{result:?}"
+    );
+    assert!(validate(
+        "efficient_broadcast2",
+        "fixtures",
+        &["--max-msgs-per-op", "25"]
+    )
+    .is_ok());
+    let opts = <RunOptions as clap::Parser>::parse_from([
+        "run",
+        "efficient_broadcast",
+        "--output-dir",
+        tests,
+        "--run-id",
+        "fixtures",
+    ]);
+    assert_eq!(
+        convergence_summary(&opts).as_deref(),
+        Some("convergence: 450ms")
+    );
+    let result = validate("echo", "missing", &[]);
+    assert!(matches!(result, Err(XtaskError::Results(_))), "{result:?}");
+}
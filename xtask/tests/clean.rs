@@ -0,0 +1,60 @@
+use std::path::Path;
+use xtask::clean::{clean, CleanOptions};
+
+/// Create fake maelstrom store with runs of a workload.
+fn fake_store(store: &str, runs: &[&str]) -> std::path::PathBuf {
+    let store = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(store);
+    let _ = std::fs::remove_dir_all(&store);
+    for run in runs {
+        let run = store.join("broadcast").join(run);
+        std::fs::create_dir_all(&run).unwrap();
+        std::fs::write(run.join("results.edn"), "{:valid? true}").unwrap();
+    }
+    store
+}
+
+/// test clean removes the store.
+#[test]
+fn test_clean() {
+    let store = fake_store("target/clean-store", &["20230101T000000.000Z"]);
+    clean(<CleanOptions as clap::Parser>::parse_from([
+        "clean",
+        "--store",
+        "target/clean-store",
+    ]));
+    assert!(!store.exists());
+}
+
+/// test clean keeps most recent runs.
+#[test]
+fn test_clean_keep() {
+    let store = fake_store(
+        "target/clean-keep-store",
+        &[
+            "20230101T000000.000Z",
+            "20230102T000000.000Z",
+            "20230103T000000.000Z",
+        ],
+    );
+    clean(<CleanOptions as clap::Parser>::parse_from([
+        "clean",
+        "--store",
+        "target/clean-keep-store",
+        "--keep",
+        "2",
+    ]));
+    let workload = store.join("broadcast");
+    assert!(!workload.join("20230101T000000.000Z").exists());
+    assert!(workload.join("20230102T000000.000Z").exists());
+    assert!(workload.join("20230103T000000.000Z").exists());
+    std::fs::remove_dir_all(store).unwrap();
+}
+
+/// test clean refuses to remove outside the project.
+#[test]
+#[should_panic(expected = "refusing to clean")]
+fn test_clean_outside_project() {
+    clean(<CleanOptions as clap::Parser>::parse_from([
+        "clean", "--store", "..",
+    ]));
+}
@@ -0,0 +1,66 @@
+use std::{path::Path, process::Command};
+use xtask::doctor::{report, Check};
+
+mod testutil;
+use testutil::fake_maelstrom;
+
+/// Create fake program printing a line and exiting with code.
+fn fake_program(name: &str, line: &str, code: i32) -> std::path::PathBuf {
+    fake_maelstrom(
+        &format!("doctor-{name}"),
+        &format!("echo '{line}' >&2\nexit {code}\n"),
+    )
+}
+
+/// test command checks pass on success and fail with reason otherwise.
+#[test]
+fn test_check_command() {
+    let program = fake_program("maelstrom", "maelstrom 0.2.3", 0);
+    let check = Check::command("maelstrom", Command::new(program), "install maelstrom");
+    assert_eq!(check.result, Ok("maelstrom 0.2.3".to_string()));
+
+    let program = fake_program("java", "no java runtime", 1);
+    let check = Check::command("java", Command::new(program), "install a JDK");
+    let reason = check.result.unwrap_err();
+    assert!(reason.contains("no java runtime"), "{reason}");
+
+    let check = Check::command("java", Command::new("no/such/java"), "install a JDK");
+    let reason = check.result.unwrap_err();
+    assert!(
+        reason.starts_with("failed to invoke no/such/java"),
+        "{reason}"
+    );
+}
+
+/// test writable check creates directory and fails below a file.
+#[test]
+fn test_check_writable() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("doctor/writable");
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(Check::writable("target dir", &dir, "fix it").passed());
+    assert!(dir.exists());
+    let file = dir.join("file");
+    std::fs::write(&file, "").unwrap();
+    assert!(!Check::writable("target dir", &file.join("dir"), "fix it").passed());
+}
+
+/// test report shows hint only for failed checks.
+#[test]
+fn test_report() {
+    let checks = [
+        Check {
+            name: "maelstrom",
+            result: Ok("maelstrom 0.2.3".into()),
+            hint: "install maelstrom",
+        },
+        Check {
+            name: "java",
+            result: Err("failed to invoke java".into()),
+            hint: "install a JDK",
+        },
+    ];
+    assert_eq!(
+        report(&checks),
+        "[pass] maelstrom: maelstrom 0.2.3\n[fail] java: failed to invoke java\n  hint: install a JDK"
+    );
+}
@@ -0,0 +1,49 @@
+use std::{
+    process::Command,
+    time::{Duration, Instant},
+};
+use xtask::interrupt::{install, interrupted, reset, wait, GRACE};
+
+/// spawn a command which runs far longer than any test.
+fn long_running() -> std::process::Child {
+    Command::new("sleep")
+        .arg("600")
+        .spawn()
+        .expect("failed to spawn sleep")
+}
+
+/// test exit status of child is returned when not interrupted.
+#[test]
+fn test_wait_exit() {
+    let mut child = Command::new("true").spawn().expect("failed to spawn true");
+    let status = wait(&mut child, || false).unwrap();
+    assert!(status.is_some_and(|status| status.success()));
+}
+
+/// test interrupted child is stopped instead of being left running.
+#[test]
+fn test_wait_interrupted() {
+    let mut child = long_running();
+    let start = Instant::now();
+    assert!(wait(&mut child, || true).unwrap().is_none());
+    assert!(start.elapsed() < GRACE, "child outlived SIGTERM");
+    assert!(child.try_wait().unwrap().is_some(), "child still running");
+}
+
+/// test signal received by xtask stops the child.
+#[cfg(unix)]
+#[test]
+fn test_signal_stops_child() {
+    let mut child = long_running();
+    install();
+    std::thread::spawn(|| {
+        std::thread::sleep(Duration::from_millis(200));
+        // SAFETY: handler installed above only records the signal.
+        unsafe { libc::raise(libc::SIGINT) };
+    });
+    assert!(wait(&mut child, interrupted).unwrap().is_none());
+    assert!(interrupted());
+    reset();
+    assert!(!interrupted());
+    assert!(child.try_wait().unwrap().is_some(), "child still running");
+}
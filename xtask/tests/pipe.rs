@@ -0,0 +1,29 @@
+use std::path::Path;
+use xtask::{
+    challange::{build, Challange},
+    pipe::{pipe_output, PipeOptions},
+};
+
+/// test init and echo messages of fixture are piped to echo binary.
+#[test]
+fn test_pipe_echo() {
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+    let input_file = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/pipe/echo.jsonl");
+    let opts = <PipeOptions as clap::Parser>::parse_from([
+        "pipe".as_ref(),
+        "echo".as_ref(),
+        input_file.as_os_str(),
+    ]);
+    assert!(matches!(opts.challange, Challange::Echo));
+    let bin_path = build(opts.release, &opts.challange.get_name()).expect("echo builds");
+    let output = pipe_output(&bin_path, &std::fs::read(opts.input_file).unwrap()).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        concat!(
+            r#"{"src":"n1","dest":"c1","body":{"msg_id":null,"in_reply_to":1,"type":"init_ok"}}"#,
+            "\n",
+            r#"{"src":"n1","dest":"c1","body":{"msg_id":0,"in_reply_to":2,"type":"echo_ok","echo":"Please echo 35"}}"#,
+            "\n",
+        )
+    );
+}
@@ -0,0 +1,72 @@
+use xtask::{
+    challange::{MaelStromCommand, STORE},
+    raw::{command, RawOptions},
+};
+
+mod testutil;
+use testutil::fake_maelstrom;
+
+/// test raw options are passed to maelstrom without challenge presets.
+#[test]
+fn test_raw_args() {
+    let opts = <RawOptions as clap::Parser>::parse_from([
+        "run-raw",
+        "broadcast",
+        "--workload",
+        "txn-list-append",
+        "--node-count",
+        "2",
+        "--",
+        "--rate",
+        "100",
+    ]);
+    let args = command(&opts, "target/debug/broadcast").args();
+    assert_eq!(
+        args,
+        [
+            "test",
+            "-w",
+            "txn-list-append",
+            "--bin",
+            "target/debug/broadcast",
+            "--node-count",
+            "2",
+            "--time-limit",
+            "10",
+            "--rate",
+            "100"
+        ]
+    );
+    assert!(command(&opts, "target/debug/broadcast").envs().is_empty());
+}
+
+/// test raw run in output directory reads results from store of its run directory.
+#[test]
+fn test_raw_output_dir() {
+    let bin = fake_maelstrom(
+        "raw-output-dir",
+        "mkdir -p store/current\necho '{:valid? true}' > store/current/results.edn\n",
+    );
+    let output_dir = bin.parent().unwrap().join("runs");
+    let opts = <RawOptions as clap::Parser>::parse_from([
+        "run-raw",
+        "echo",
+        "--workload",
+        "echo",
+        "-m",
+        bin.to_str().unwrap(),
+        "--output-dir",
+        output_dir.to_str().unwrap(),
+        "--run-id",
+        "1",
+    ]);
+    assert_eq!(opts.store(), output_dir.join("1").join(STORE));
+    let command = command(&opts, "target/debug/echo");
+    assert_eq!(
+        command.get_current_dir(),
+        Some(output_dir.join("1").as_path())
+    );
+    command.execute(0).expect("fake maelstrom succeeds");
+    let result = MaelStromCommand::get_results(&opts.store()).expect("results in run dir");
+    assert_eq!(result.verdict().ok(), Some(()));
+}
@@ -0,0 +1,33 @@
+use xtask::serve::{command, ServeOptions};
+
+/// test port and extra arguments are passed to maelstrom serve.
+#[test]
+fn test_serve_args() {
+    let opts = <ServeOptions as clap::Parser>::parse_from([
+        "serve", "--port", "9090", "--", "--host", "0.0.0.0",
+    ]);
+    let args = command(&opts)
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(args, ["serve", "--port", "9090", "--host", "0.0.0.0"]);
+
+    let opts = <ServeOptions as clap::Parser>::parse_from(["serve"]);
+    assert_eq!(command(&opts).get_args().collect::<Vec<_>>(), ["serve"]);
+}
+
+/// test maelstrom command is used for serve.
+#[test]
+fn test_serve_maelstrom_cmd() {
+    let opts = <ServeOptions as clap::Parser>::parse_from([
+        "serve",
+        "--maelstrom-cmd",
+        "java -jar maelstrom.jar",
+    ]);
+    let command = command(&opts);
+    assert_eq!(command.get_program(), "java");
+    assert_eq!(
+        command.get_args().collect::<Vec<_>>(),
+        ["-jar", "maelstrom.jar", "serve"]
+    );
+}
@@ -1,61 +1,82 @@
 use serial_test::{parallel, serial};
-use xtask::challange::{run, Challange, RunOptions};
+use std::path::PathBuf;
+use std::time::Duration;
+use xtask::challange::{counter_convergence, read_history, run, Challange, RunOptions};
 
-fn run_challange(challange: Challange) {
+/// Run challenge in its own run directory, hence runs do not overwrite results of each other.
+///
+/// # Returns
+/// Maelstrom store of the run.
+fn run_challange(challange: Challange, run_id: &str) -> PathBuf {
     std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
-    run(<RunOptions as clap::Parser>::parse_from([
+    let opts = <RunOptions as clap::Parser>::parse_from([
         "--challenge",
         <Challange as clap::ValueEnum>::to_possible_value(&challange)
             .unwrap()
             .get_name(),
         "--release",
-    ]));
+        "--output-dir",
+        "target/runs",
+        "--run-id",
+        run_id,
+    ]);
+    let store = opts.store();
+    run(opts).unwrap();
+    store
 }
 
 #[test]
 #[parallel]
 fn run_echo() {
-    run_challange(Challange::Echo);
+    run_challange(Challange::Echo, "echo");
 }
 
 #[test]
 #[parallel]
 fn run_unique() {
-    run_challange(Challange::UniqueIds);
+    run_challange(Challange::UniqueIds, "unique");
 }
 
 #[test]
 #[parallel]
 fn run_single_broadcast() {
-    run_challange(Challange::SingleBroadcast);
+    run_challange(Challange::SingleBroadcast, "single_broadcast");
 }
 
 #[test]
 #[parallel]
 fn run_multi_broadcast() {
-    run_challange(Challange::MultiBroadcast);
+    run_challange(Challange::MultiBroadcast, "multi_broadcast");
 }
 
 #[test]
 #[parallel]
 fn run_faulty_broadcast() {
-    run_challange(Challange::FaultyBroadcast);
+    run_challange(Challange::FaultyBroadcast, "faulty_broadcast");
 }
 
 #[test]
-#[serial]
+#[parallel]
 fn run_efficent_broadcast() {
-    run_challange(Challange::EfficientBroadcast);
+    run_challange(Challange::EfficientBroadcast, "efficent_broadcast");
 }
 
 #[test]
-#[serial]
+#[parallel]
 fn run_efficent_broadcast2() {
-    run_challange(Challange::EfficientBroadcast2);
+    run_challange(Challange::EfficientBroadcast2, "efficent_broadcast2");
 }
 
 #[test]
 #[parallel]
 fn run_grow_only_counter() {
-    run_challange(Challange::GrowOnlyCounter);
+    run_challange(Challange::GrowOnlyCounter, "grow_only_counter");
+}
+
+#[test]
+#[serial]
+fn run_grow_only_counter_convergence() {
+    let store = run_challange(Challange::GrowOnlyCounter, "grow_only_counter_convergence");
+    let convergence = counter_convergence(&read_history(&store)).expect("counter did not converge");
+    assert!(convergence < Duration::from_secs(5), "{convergence:?}");
 }
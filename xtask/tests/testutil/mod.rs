@@ -0,0 +1,16 @@
+//! Utilities shared by tests.
+
+use std::{os::unix::fs::PermissionsExt, path::PathBuf};
+
+/// Create fake maelstrom running shell script, in a fresh directory of name.
+///
+/// Directory of fake maelstrom is its parent.
+pub fn fake_maelstrom(name: &str, script: &str) -> PathBuf {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let bin = dir.join("maelstrom");
+    std::fs::write(&bin, format!("#!/bin/sh\n{script}")).unwrap();
+    std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+    bin
+}